@@ -1,6 +1,6 @@
 use std::{
     any::Any,
-    collections::HashMap,
+    collections::{HashMap, HashSet, VecDeque},
     io::Cursor,
     sync::{
         atomic::{AtomicU64, Ordering},
@@ -16,8 +16,23 @@ pub struct WebBackend {
     pub stderr: Mutex<String>,
     pub trace: Mutex<String>,
     pub files: Mutex<HashMap<String, Vec<u8>>>,
+    /// Directories that were created explicitly
+    ///
+    /// Directories that contain files exist implicitly.
+    dirs: Mutex<HashSet<String>>,
     next_thread_id: AtomicU64,
     thread_results: Mutex<HashMap<Handle, UiuaResult<Vec<Value>>>>,
+    next_channel_id: AtomicU64,
+    channels: Mutex<HashMap<Handle, WebChannel>>,
+}
+
+/// A channel on the web
+///
+/// Threads run to completion when they are spawned, so a channel is just a queue.
+#[derive(Default)]
+struct WebChannel {
+    queue: VecDeque<Value>,
+    closed: bool,
 }
 
 impl Default for WebBackend {
@@ -27,12 +42,20 @@ impl Default for WebBackend {
             stderr: String::new().into(),
             trace: String::new().into(),
             files: HashMap::new().into(),
+            dirs: HashSet::new().into(),
             next_thread_id: 0.into(),
             thread_results: HashMap::new().into(),
+            next_channel_id: 0.into(),
+            channels: HashMap::new().into(),
         }
     }
 }
 
+/// Get the prefix that paths inside a directory start with
+fn dir_prefix(path: &str) -> String {
+    format!("{}/", path.trim_end_matches('/'))
+}
+
 pub enum OutputItem {
     String(String),
     Image(Vec<u8>),
@@ -108,6 +131,90 @@ impl SysBackend for WebBackend {
             .cloned()
             .ok_or_else(|| format!("File not found: {path}"))
     }
+    fn file_exists(&self, path: &str) -> bool {
+        let is_file = self.files.lock().unwrap().contains_key(path);
+        is_file || self.is_dir(path).unwrap_or(false)
+    }
+    fn is_file(&self, path: &str) -> Result<bool, String> {
+        Ok(self.files.lock().unwrap().contains_key(path))
+    }
+    fn is_dir(&self, path: &str) -> Result<bool, String> {
+        let prefix = dir_prefix(path);
+        if self.dirs.lock().unwrap().contains(path) {
+            return Ok(true);
+        }
+        let files = self.files.lock().unwrap();
+        Ok(files.keys().any(|file| file.starts_with(&prefix)))
+    }
+    fn file_size(&self, path: &str) -> Result<u64, String> {
+        self.files
+            .lock()
+            .unwrap()
+            .get(path)
+            .map(|bytes| bytes.len() as u64)
+            .ok_or_else(|| format!("File not found: {path}"))
+    }
+    fn file_modified(&self, _path: &str) -> Result<f64, String> {
+        Err("File modification times are not tracked in the browser".into())
+    }
+    fn delete(&self, path: &str) -> Result<(), String> {
+        let prefix = dir_prefix(path);
+        let mut files = self.files.lock().unwrap();
+        let mut dirs = self.dirs.lock().unwrap();
+        let file_count = files.len();
+        let was_dir = dirs.remove(path);
+        files.retain(|file, _| file != path && !file.starts_with(&prefix));
+        dirs.retain(|dir| !dir.starts_with(&prefix));
+        if files.len() == file_count && !was_dir {
+            return Err(format!("File not found: {path}"));
+        }
+        Ok(())
+    }
+    fn rename(&self, from: &str, to: &str) -> Result<(), String> {
+        let (from_prefix, to_prefix) = (dir_prefix(from), dir_prefix(to));
+        let mut files = self.files.lock().unwrap();
+        let mut dirs = self.dirs.lock().unwrap();
+        if let Some(bytes) = files.remove(from) {
+            files.insert(to.into(), bytes);
+            return Ok(());
+        }
+        let moved: Vec<String> = files
+            .keys()
+            .filter(|file| file.starts_with(&from_prefix))
+            .cloned()
+            .collect();
+        let was_dir = dirs.remove(from);
+        if moved.is_empty() && !was_dir {
+            return Err(format!("File not found: {from}"));
+        }
+        for file in moved {
+            let bytes = files.remove(&file).unwrap();
+            files.insert(format!("{to_prefix}{}", &file[from_prefix.len()..]), bytes);
+        }
+        let renamed: HashSet<String> = dirs
+            .drain()
+            .map(|dir| match dir.strip_prefix(&from_prefix) {
+                Some(rest) => format!("{to_prefix}{rest}"),
+                None => dir,
+            })
+            .collect();
+        *dirs = renamed;
+        dirs.insert(to.into());
+        Ok(())
+    }
+    fn copy_file(&self, from: &str, to: &str) -> Result<(), String> {
+        let mut files = self.files.lock().unwrap();
+        let bytes = files
+            .get(from)
+            .cloned()
+            .ok_or_else(|| format!("File not found: {from}"))?;
+        files.insert(to.into(), bytes);
+        Ok(())
+    }
+    fn make_dir(&self, path: &str) -> Result<(), String> {
+        self.dirs.lock().unwrap().insert(path.into());
+        Ok(())
+    }
     fn play_audio(&self, wav_bytes: Vec<u8>) -> Result<(), String> {
         self.stdout
             .lock()
@@ -138,4 +245,52 @@ impl SysBackend for WebBackend {
             None => Err(Err("Invalid thread handle".into())),
         }
     }
+    fn close(&self, handle: Handle) -> Result<(), String> {
+        self.channels.lock().unwrap().remove(&handle);
+        Ok(())
+    }
+    fn channel_new(&self) -> Result<Handle, String> {
+        let handle = Handle(self.next_channel_id.fetch_add(1, Ordering::SeqCst));
+        self.channels
+            .lock()
+            .unwrap()
+            .insert(handle, WebChannel::default());
+        Ok(handle)
+    }
+    fn channel_send(&self, handle: Handle, value: Value) -> Result<(), String> {
+        let mut channels = self.channels.lock().unwrap();
+        let channel = channels.get_mut(&handle).ok_or("Invalid channel handle")?;
+        if channel.closed {
+            return Err("Cannot send on a closed channel".into());
+        }
+        channel.queue.push_back(value);
+        Ok(())
+    }
+    fn channel_recv(&self, handle: Handle) -> Result<Value, String> {
+        let mut channels = self.channels.lock().unwrap();
+        let channel = channels.get_mut(&handle).ok_or("Invalid channel handle")?;
+        // Nothing else can run while we wait, so an empty channel would block forever
+        channel.queue.pop_front().ok_or_else(|| {
+            if channel.closed {
+                "Cannot receive from a closed and empty channel".into()
+            } else {
+                "Receiving from an empty channel would block forever".into()
+            }
+        })
+    }
+    fn channel_try_recv(&self, handle: Handle) -> Result<Option<Value>, String> {
+        let mut channels = self.channels.lock().unwrap();
+        let channel = channels.get_mut(&handle).ok_or("Invalid channel handle")?;
+        match channel.queue.pop_front() {
+            Some(value) => Ok(Some(value)),
+            None if channel.closed => Err("Cannot receive from a closed and empty channel".into()),
+            None => Ok(None),
+        }
+    }
+    fn channel_close(&self, handle: Handle) -> Result<(), String> {
+        let mut channels = self.channels.lock().unwrap();
+        let channel = channels.get_mut(&handle).ok_or("Invalid channel handle")?;
+        channel.closed = true;
+        Ok(())
+    }
 }
@@ -0,0 +1,509 @@
+//! Date and time algorithms
+//!
+//! Times are seconds since the Unix epoch in UTC, like the ones returned by [`Primitive::Now`](crate::primitive::Primitive::Now).
+//! Calendar fields are `[year month day hour minute second]`, where the second may have a fractional part.
+
+use std::sync::Arc;
+
+use crate::{
+    array::{Array, Shape},
+    function::Function,
+    value::Value,
+    Uiua, UiuaResult,
+};
+
+const MONTHS: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+const WEEKDAYS: [&str; 7] = [
+    "Sunday",
+    "Monday",
+    "Tuesday",
+    "Wednesday",
+    "Thursday",
+    "Friday",
+    "Saturday",
+];
+
+/// The largest magnitude of a time that can be converted to or from calendar fields
+///
+/// This is 2^53 seconds, or about 285 million years, past which times lose precision.
+/// Keeping years and months below it also keeps the calendar arithmetic from overflowing.
+const MAX_TIME: f64 = 9007199254740992.0;
+
+/// The fields of a UTC date and time
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct DateTime {
+    year: i64,
+    month: u32,
+    day: u32,
+    hour: u32,
+    minute: u32,
+    second: f64,
+}
+
+/// Convert days since the epoch to a year, month, and day
+///
+/// This is Howard Hinnant's `civil_from_days` algorithm.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + (month <= 2) as i64;
+    (year, month, day)
+}
+
+/// Convert a year, month, and day to days since the epoch
+///
+/// This is Howard Hinnant's `days_from_civil` algorithm.
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = year - (month <= 2) as i64;
+    let era = year.div_euclid(400);
+    let yoe = year.rem_euclid(400);
+    let mp = (month as i64 + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146097 + doe - 719468
+}
+
+impl DateTime {
+    /// Split a finite time into fields
+    fn from_timestamp(time: f64) -> Result<Self, String> {
+        if time.abs() > MAX_TIME {
+            return Err(format!("Time {time} is out of the range of dates"));
+        }
+        let days = (time / 86400.0).floor();
+        let (year, month, day) = civil_from_days(days as i64);
+        let secs = time - days * 86400.0;
+        let hour = (secs / 3600.0).floor().min(23.0);
+        let minute = ((secs - hour * 3600.0) / 60.0).floor().min(59.0);
+        Ok(DateTime {
+            year,
+            month,
+            day,
+            hour: hour as u32,
+            minute: minute as u32,
+            second: secs - hour * 3600.0 - minute * 60.0,
+        })
+    }
+    fn weekday(&self) -> usize {
+        (days_from_civil(self.year, self.month, self.day) + 4).rem_euclid(7) as usize
+    }
+    fn day_of_year(&self) -> i64 {
+        days_from_civil(self.year, self.month, self.day) - days_from_civil(self.year, 1, 1) + 1
+    }
+}
+
+/// Convert calendar fields to a timestamp
+///
+/// Fields outside their usual ranges carry over, so month `13` is January of the next year.
+fn timestamp(fields: &[f64]) -> Result<f64, String> {
+    let field = |i: usize, default: f64| fields.get(i).copied().unwrap_or(default);
+    let (year, month) = (field(0, 1970.0), field(1, 1.0));
+    if fields.iter().any(|f| f.is_nan()) || year.is_infinite() || month.is_infinite() {
+        return Ok(f64::NAN);
+    }
+    if year.abs() > MAX_TIME {
+        return Err(format!("Year {year} is out of the range of dates"));
+    }
+    if month.abs() > MAX_TIME {
+        return Err(format!("Month {month} is out of the range of dates"));
+    }
+    let month = month.floor() as i64 - 1;
+    let year = year.floor() as i64 + month.div_euclid(12);
+    let month = month.rem_euclid(12) as u32 + 1;
+    let days = days_from_civil(year, month, 1) as f64 + field(2, 1.0) - 1.0;
+    Ok(days * 86400.0 + field(3, 0.0) * 3600.0 + field(4, 0.0) * 60.0 + field(5, 0.0))
+}
+
+fn numbers(value: &Value, env: &Uiua, requirement: &'static str) -> UiuaResult<Array<f64>> {
+    match value {
+        Value::Num(arr) => Ok(arr.clone()),
+        Value::Byte(arr) => Ok(arr.convert_ref()),
+        value => Err(env.error(format!(
+            "{requirement}, but its type is {}",
+            value.type_name()
+        ))),
+    }
+}
+
+/// Get the strings to parse from a string, a rank 2 character array, or an array of boxed strings
+fn strings(value: &Value, env: &Uiua) -> UiuaResult<(Vec<usize>, Vec<String>)> {
+    const REQUIREMENT: &str = "Dates must be strings";
+    match value {
+        Value::Char(arr) if arr.rank() == 0 => Ok((Vec::new(), vec![arr.data[0].into()])),
+        Value::Char(arr) => {
+            let shape = arr.shape()[..arr.rank() - 1].to_vec();
+            let width = *arr.shape().last().unwrap();
+            let strings = if width == 0 {
+                vec![String::new(); shape.iter().product()]
+            } else {
+                arr.data.chunks(width).map(|s| s.iter().collect()).collect()
+            };
+            Ok((shape, strings))
+        }
+        Value::Func(arr) => {
+            let strings = arr
+                .data
+                .iter()
+                .map(|f| match f.as_boxed() {
+                    Some(value) => value.as_string(env, REQUIREMENT),
+                    None => Err(env.error(format!("{REQUIREMENT}, but one is a function"))),
+                })
+                .collect::<UiuaResult<_>>()?;
+            Ok((arr.shape().to_vec(), strings))
+        }
+        value => Err(env.error(format!(
+            "{REQUIREMENT}, but the type is {}",
+            value.type_name()
+        ))),
+    }
+}
+
+impl Value {
+    /// Split timestamps into `[year month day hour minute second]`
+    pub fn datetime(&self, env: &Uiua) -> UiuaResult<Self> {
+        let times = numbers(self, env, "Timestamps must be numbers")?;
+        let mut shape = times.shape.clone();
+        shape.push(6);
+        let mut fields = Vec::with_capacity(times.data.len() * 6);
+        for &time in times.data.iter() {
+            if !time.is_finite() {
+                fields.extend([f64::NAN; 6]);
+                continue;
+            }
+            let dt = DateTime::from_timestamp(time).map_err(|e| env.error(e))?;
+            fields.extend([
+                dt.year as f64,
+                dt.month as f64,
+                dt.day as f64,
+                dt.hour as f64,
+                dt.minute as f64,
+                dt.second,
+            ]);
+        }
+        Ok(Array::new(shape, fields).into())
+    }
+    /// Combine calendar fields along the last axis into timestamps
+    pub fn timestamp(&self, env: &Uiua) -> UiuaResult<Self> {
+        let fields = numbers(self, env, "Date fields must be numbers")?;
+        if fields.rank() == 0 {
+            return Err(env.error("Date fields must be at least rank 1"));
+        }
+        let width = *fields.shape.last().unwrap();
+        if width > 6 {
+            return Err(env.error(format!(
+                "There can be at most 6 date fields, but there are {width}"
+            )));
+        }
+        let mut shape = fields.shape.clone();
+        shape.pop();
+        let times: Vec<f64> = if width == 0 {
+            vec![0.0; shape.iter().product()]
+        } else {
+            (fields.data.chunks(width))
+                .map(timestamp)
+                .collect::<Result<_, _>>()
+                .map_err(|e| env.error(e))?
+        };
+        Ok(Array::new(shape, times).into())
+    }
+    /// Format timestamps with a strftime-style pattern
+    pub fn format_datetime(&self, times: &Self, env: &Uiua) -> UiuaResult<Self> {
+        let pattern = self.as_string(env, "Date format must be a string")?;
+        let times = numbers(times, env, "Timestamps must be numbers")?;
+        let mut formatted = Vec::with_capacity(times.data.len());
+        for &time in times.data.iter() {
+            if !time.is_finite() {
+                return Err(env.error(format!("Cannot format {time} as a date")));
+            }
+            formatted.push(format(&pattern, time).map_err(|e| env.error(e))?);
+        }
+        if times.rank() == 0 {
+            return Ok(formatted.pop().unwrap().into());
+        }
+        let boxed: Vec<Arc<Function>> = formatted
+            .into_iter()
+            .map(|s| Arc::new(Function::constant(s)))
+            .collect();
+        Ok(Array::new(times.shape.clone(), boxed).into())
+    }
+    /// Parse dates with a strftime-style pattern into timestamps
+    pub fn parse_datetime(&self, dates: &Self, env: &Uiua) -> UiuaResult<Self> {
+        let pattern = self.as_string(env, "Date format must be a string")?;
+        let (shape, dates) = strings(dates, env)?;
+        let times = dates
+            .iter()
+            .map(|date| parse(&pattern, date).map_err(|e| env.error(e)))
+            .collect::<UiuaResult<Vec<f64>>>()?;
+        Ok(Array::new(shape.into_iter().collect::<Shape>(), times).into())
+    }
+}
+
+fn format(pattern: &str, time: f64) -> Result<String, String> {
+    let dt = DateTime::from_timestamp(time)?;
+    let mut s = String::new();
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            s.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('Y') => s.push_str(&dt.year.to_string()),
+            Some('y') => s.push_str(&format!("{:02}", dt.year.rem_euclid(100))),
+            Some('m') => s.push_str(&format!("{:02}", dt.month)),
+            Some('d') => s.push_str(&format!("{:02}", dt.day)),
+            Some('e') => s.push_str(&format!("{:2}", dt.day)),
+            Some('H') => s.push_str(&format!("{:02}", dt.hour)),
+            Some('I') => s.push_str(&format!("{:02}", (dt.hour + 11) % 12 + 1)),
+            Some('p') => s.push_str(if dt.hour < 12 { "AM" } else { "PM" }),
+            Some('M') => s.push_str(&format!("{:02}", dt.minute)),
+            Some('S') => s.push_str(&format!("{:02}", dt.second.floor())),
+            Some('f') => s.push_str(&format!("{:03}", (dt.second.fract() * 1000.0).floor())),
+            Some('j') => s.push_str(&format!("{:03}", dt.day_of_year())),
+            Some('B') => s.push_str(MONTHS[dt.month as usize - 1]),
+            Some('b') => s.push_str(&MONTHS[dt.month as usize - 1][..3]),
+            Some('A') => s.push_str(WEEKDAYS[dt.weekday()]),
+            Some('a') => s.push_str(&WEEKDAYS[dt.weekday()][..3]),
+            Some('s') => s.push_str(&time.floor().to_string()),
+            Some('F') => s.push_str(&format("%Y-%m-%d", time)?),
+            Some('T') => s.push_str(&format("%H:%M:%S", time)?),
+            Some('%') => s.push('%'),
+            Some(c) => return Err(format!("Unknown date format specifier %{c}")),
+            None => return Err("Date format ends with %".into()),
+        }
+    }
+    Ok(s)
+}
+
+fn parse(pattern: &str, date: &str) -> Result<f64, String> {
+    let mismatch = || format!("{date:?} does not match the date format {pattern:?}");
+    let mut fields = [1970.0, 1.0, 1.0, 0.0, 0.0, 0.0];
+    let mut pm = None;
+    let mut epoch = None;
+    let mut rest = date;
+    let mut pattern_chars = pattern.chars();
+    while let Some(c) = pattern_chars.next() {
+        if c != '%' {
+            rest = rest.strip_prefix(c).ok_or_else(mismatch)?;
+            continue;
+        }
+        let spec = pattern_chars
+            .next()
+            .ok_or_else(|| "Date format ends with %".to_string())?;
+        match spec {
+            'Y' => fields[0] = number(&mut rest, usize::MAX, true).ok_or_else(mismatch)?,
+            'y' => {
+                // Like POSIX, 69 to 99 are in the 1900s and 00 to 68 are in the 2000s
+                let year = number(&mut rest, 2, false).ok_or_else(mismatch)?;
+                fields[0] = year + if year < 69.0 { 2000.0 } else { 1900.0 };
+            }
+            'm' => fields[1] = number(&mut rest, 2, false).ok_or_else(mismatch)?,
+            'd' | 'e' => fields[2] = number(&mut rest, 2, false).ok_or_else(mismatch)?,
+            'H' | 'I' => fields[3] = number(&mut rest, 2, false).ok_or_else(mismatch)?,
+            'M' => fields[4] = number(&mut rest, 2, false).ok_or_else(mismatch)?,
+            'S' => fields[5] = number(&mut rest, 2, false).ok_or_else(mismatch)?,
+            'j' => {
+                fields[1] = 1.0;
+                fields[2] = number(&mut rest, 3, false).ok_or_else(mismatch)?;
+            }
+            'f' => {
+                let before = rest.len();
+                let frac = number(&mut rest, usize::MAX, false).ok_or_else(mismatch)?;
+                fields[5] += frac / 10f64.powi((before - rest.len()) as i32);
+            }
+            's' => epoch = Some(number(&mut rest, usize::MAX, true).ok_or_else(mismatch)?),
+            'p' => {
+                let upper = rest.get(..2).map(str::to_uppercase);
+                pm = Some(match upper.as_deref() {
+                    Some("AM") => false,
+                    Some("PM") => true,
+                    _ => return Err(mismatch()),
+                });
+                rest = &rest[2..];
+            }
+            'B' | 'b' => {
+                let (i, len) = match_name(rest, &MONTHS, spec == 'b').ok_or_else(mismatch)?;
+                fields[1] = i as f64 + 1.0;
+                rest = &rest[len..];
+            }
+            'A' | 'a' => {
+                let (_, len) = match_name(rest, &WEEKDAYS, spec == 'a').ok_or_else(mismatch)?;
+                rest = &rest[len..];
+            }
+            'F' => fields[..3].copy_from_slice(&date_fields(&mut rest, &mismatch)?),
+            'T' => fields[3..].copy_from_slice(&time_fields(&mut rest, &mismatch)?),
+            '%' => rest = rest.strip_prefix('%').ok_or_else(mismatch)?,
+            c => return Err(format!("Unknown date format specifier %{c}")),
+        }
+    }
+    if !rest.is_empty() {
+        return Err(mismatch());
+    }
+    if let Some(time) = epoch {
+        return Ok(time);
+    }
+    if let Some(pm) = pm {
+        fields[3] = fields[3] % 12.0 + if pm { 12.0 } else { 0.0 };
+    }
+    timestamp(&fields)
+}
+
+/// Parse a number of at most `max_digits` digits, skipping leading spaces
+fn number(rest: &mut &str, max_digits: usize, signed: bool) -> Option<f64> {
+    let trimmed = rest.trim_start_matches(' ');
+    let sign_len = (signed && trimmed.starts_with('-')) as usize;
+    let digits = trimmed[sign_len..]
+        .chars()
+        .take(max_digits)
+        .take_while(char::is_ascii_digit)
+        .count();
+    if digits == 0 {
+        return None;
+    }
+    let (n, tail) = trimmed.split_at(sign_len + digits);
+    *rest = tail;
+    n.parse().ok()
+}
+
+/// Parse the `%Y-%m-%d` part of a `%F` specifier
+fn date_fields(rest: &mut &str, mismatch: &dyn Fn() -> String) -> Result<[f64; 3], String> {
+    let parts: Vec<&str> = rest.splitn(3, '-').collect();
+    if parts.len() < 3 {
+        return Err(mismatch());
+    }
+    let day_len = parts[2].chars().take_while(char::is_ascii_digit).count();
+    let day = &parts[2][..day_len];
+    let parsed = [parts[0], parts[1], day].map(|s| s.parse::<f64>().ok());
+    *rest = &parts[2][day_len..];
+    match parsed {
+        [Some(y), Some(m), Some(d)] => Ok([y, m, d]),
+        _ => Err(mismatch()),
+    }
+}
+
+/// Parse the `%H:%M:%S` part of a `%T` specifier
+fn time_fields(rest: &mut &str, mismatch: &dyn Fn() -> String) -> Result<[f64; 3], String> {
+    let parts: Vec<&str> = rest.splitn(3, ':').collect();
+    if parts.len() < 3 {
+        return Err(mismatch());
+    }
+    let second_len = parts[2].chars().take_while(char::is_ascii_digit).count();
+    let second = &parts[2][..second_len];
+    let parsed = [parts[0], parts[1], second].map(|s| s.parse::<f64>().ok());
+    *rest = &parts[2][second_len..];
+    match parsed {
+        [Some(h), Some(m), Some(s)] => Ok([h, m, s]),
+        _ => Err(mismatch()),
+    }
+}
+
+/// Match a month or weekday name case-insensitively, returning its index and length
+fn match_name(s: &str, names: &[&str], short: bool) -> Option<(usize, usize)> {
+    names.iter().enumerate().find_map(|(i, name)| {
+        let name = if short { &name[..3] } else { name };
+        let prefix = s.get(..name.len())?;
+        prefix.eq_ignore_ascii_case(name).then_some((i, name.len()))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn civil_round_trip() {
+        for days in [-800000, -1, 0, 1, 59, 10957, 19000, 2932896] {
+            let (y, m, d) = civil_from_days(days);
+            assert_eq!(days_from_civil(y, m, d), days);
+        }
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(civil_from_days(11016), (2000, 2, 29));
+    }
+
+    #[test]
+    fn format_and_parse() {
+        // 2023-10-17 13:45:30.25 UTC
+        let time = 1697550330.25;
+        assert_eq!(
+            format("%Y-%m-%d %H:%M:%S.%f", time).unwrap(),
+            "2023-10-17 13:45:30.250"
+        );
+        assert_eq!(
+            format("%a %b %e %I%p, day %j", time).unwrap(),
+            "Tue Oct 17 01PM, day 290"
+        );
+        assert_eq!(
+            parse("%Y-%m-%d %H:%M:%S.%f", "2023-10-17 13:45:30.25").unwrap(),
+            time
+        );
+        assert_eq!(
+            parse("%d %B %Y %I:%M %p", "17 october 2023 1:45 pm").unwrap(),
+            time - 30.25
+        );
+        assert_eq!(parse("%FT%T", "2023-10-17T13:45:30").unwrap(), time - 0.25);
+        assert!(parse("%Y-%m-%d", "2023/10/17").is_err());
+        assert!(format("%Q", time).is_err());
+    }
+
+    #[test]
+    fn fields_round_trip() {
+        for time in [-1e9, -0.5, 0.0, 951782400.0, 1697550330.25] {
+            let dt = DateTime::from_timestamp(time).unwrap();
+            let fields = [
+                dt.year as f64,
+                dt.month as f64,
+                dt.day as f64,
+                dt.hour as f64,
+                dt.minute as f64,
+                dt.second,
+            ];
+            assert_eq!(timestamp(&fields), Ok(time));
+        }
+        assert_eq!(timestamp(&[2023.0, 13.0]), timestamp(&[2024.0, 1.0]));
+    }
+
+    #[test]
+    fn out_of_range() {
+        assert!(DateTime::from_timestamp(1e300).is_err());
+        assert!(DateTime::from_timestamp(-MAX_TIME).is_ok());
+        assert!(format("%Y", 1e300).is_err());
+        assert!(timestamp(&[1e300]).is_err());
+        assert!(timestamp(&[2000.0, -1e300]).is_err());
+        assert!(parse("%Y", "99999999999999999999999").is_err());
+        let mut env = Uiua::with_native_sys();
+        for code in [
+            "datetime 1e300",
+            "datefmt \"%Y\" 1e300",
+            "timestamp [1e300]",
+            "dateparse \"%Y\" \"99999999999999999999999\"",
+        ] {
+            assert!(env.load_str(code).is_err(), "{code}");
+        }
+    }
+
+    #[test]
+    fn two_digit_years() {
+        assert_eq!(parse("%y", "68"), timestamp(&[2068.0]));
+        assert_eq!(parse("%y", "69"), timestamp(&[1969.0]));
+        assert_eq!(parse("%y", "99"), timestamp(&[1999.0]));
+        assert_eq!(parse("%y", "00"), timestamp(&[2000.0]));
+    }
+}
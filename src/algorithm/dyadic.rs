@@ -118,9 +118,14 @@ impl Value {
                 |a, b| Ok(a.join_impl(b, ctx)?.into()),
                 |a, b| Ok(a.join_impl(b, ctx)?.into()),
             )?,
+            (Value::Complex(a), Value::Complex(b)) => a.join_impl(b, ctx)?.into(),
             (Value::Char(a), Value::Char(b)) => a.join_impl(b, ctx)?.into(),
             (Value::Byte(a), Value::Num(b)) => a.convert().join_impl(b, ctx)?.into(),
             (Value::Num(a), Value::Byte(b)) => a.join_impl(b.convert(), ctx)?.into(),
+            (Value::Num(a), Value::Complex(b)) => a.convert().join_impl(b, ctx)?.into(),
+            (Value::Complex(a), Value::Num(b)) => a.join_impl(b.convert(), ctx)?.into(),
+            (Value::Byte(a), Value::Complex(b)) => a.convert().join_impl(b, ctx)?.into(),
+            (Value::Complex(a), Value::Byte(b)) => a.join_impl(b.convert(), ctx)?.into(),
             (a, b) => a.coerce_to_functions(
                 b,
                 ctx,
@@ -146,6 +151,7 @@ impl Value {
                     },
                 )?;
             }
+            (Value::Complex(a), Value::Complex(b)) => a.append(b, ctx)?,
             (Value::Char(a), Value::Char(b)) => a.append(b, ctx)?,
             (Value::Byte(a), Value::Num(b)) => {
                 let mut a = a.convert_ref();
@@ -153,6 +159,18 @@ impl Value {
                 *self = a.into();
             }
             (Value::Num(a), Value::Byte(b)) => a.append(b.convert(), ctx)?,
+            (Value::Num(a), Value::Complex(b)) => {
+                let mut a = a.convert_ref();
+                a.append(b, ctx)?;
+                *self = a.into();
+            }
+            (Value::Byte(a), Value::Complex(b)) => {
+                let mut a = a.convert_ref();
+                a.append(b, ctx)?;
+                *self = a.into();
+            }
+            (Value::Complex(a), Value::Num(b)) => a.append(b.convert(), ctx)?,
+            (Value::Complex(a), Value::Byte(b)) => a.append(b.convert(), ctx)?,
             (a, b) => {
                 *self = a.clone().coerce_to_functions(
                     b,
@@ -306,9 +324,22 @@ impl Value {
                     },
                 )?
             }
+            (Value::Complex(a), Value::Complex(b)) => a.couple_impl(b, ctx)?,
             (Value::Char(a), Value::Char(b)) => a.couple_impl(b, ctx)?,
             (Value::Func(a), Value::Func(b)) => a.couple_impl(b, ctx)?,
             (Value::Num(a), Value::Byte(b)) => a.couple_impl(b.convert(), ctx)?,
+            (Value::Num(a), Value::Complex(b)) => {
+                let mut a = a.convert_ref();
+                a.couple_impl(b, ctx)?;
+                *self = a.into();
+            }
+            (Value::Byte(a), Value::Complex(b)) => {
+                let mut a = a.convert_ref();
+                a.couple_impl(b, ctx)?;
+                *self = a.into();
+            }
+            (Value::Complex(a), Value::Num(b)) => a.couple_impl(b.convert(), ctx)?,
+            (Value::Complex(a), Value::Byte(b)) => a.couple_impl(b.convert(), ctx)?,
             (Value::Byte(a), Value::Num(b)) => {
                 let mut a = a.convert_ref();
                 a.couple_impl(b, ctx)?;
@@ -332,6 +363,7 @@ impl Value {
         match self {
             Value::Num(a) => a.uncouple(env).map(|(a, b)| (a.into(), b.into())),
             Value::Byte(a) => a.uncouple(env).map(|(a, b)| (a.into(), b.into())),
+            Value::Complex(a) => a.uncouple(env).map(|(a, b)| (a.into(), b.into())),
             Value::Char(a) => a.uncouple(env).map(|(a, b)| (a.into(), b.into())),
             Value::Func(a) => a.uncouple(env).map(|(a, b)| (a.into(), b.into())),
         }
@@ -462,6 +494,7 @@ impl Value {
             match self {
                 Value::Num(a) => a.reshape_scalar(n),
                 Value::Byte(a) => a.reshape_scalar(n),
+                Value::Complex(a) => a.reshape_scalar(n),
                 Value::Char(a) => a.reshape_scalar(n),
                 Value::Func(a) => a.reshape_scalar(n),
             }
@@ -474,6 +507,7 @@ impl Value {
             match self {
                 Value::Num(a) => a.reshape(&target_shape, env),
                 Value::Byte(a) => a.reshape(&target_shape, env),
+                Value::Complex(a) => a.reshape(&target_shape, env),
                 Value::Char(a) => a.reshape(&target_shape, env),
                 Value::Func(a) => a.reshape(&target_shape, env),
             }?
@@ -605,6 +639,7 @@ impl Value {
             match kept {
                 Value::Num(a) => a.scalar_keep(counts[0]).into(),
                 Value::Byte(a) => a.scalar_keep(counts[0]).into(),
+                Value::Complex(a) => a.scalar_keep(counts[0]).into(),
                 Value::Char(a) => a.scalar_keep(counts[0]).into(),
                 Value::Func(a) => a.scalar_keep(counts[0]).into(),
             }
@@ -612,11 +647,22 @@ impl Value {
             match kept {
                 Value::Num(a) => a.list_keep(&counts, env)?.into(),
                 Value::Byte(a) => a.list_keep(&counts, env)?.into(),
+                Value::Complex(a) => a.list_keep(&counts, env)?.into(),
                 Value::Char(a) => a.list_keep(&counts, env)?.into(),
                 Value::Func(a) => a.list_keep(&counts, env)?.into(),
             }
         })
     }
+    /// Get the length of the result of [`Value::keep`] without building it
+    ///
+    /// Returns `None` if the counts are not a valid list for the kept array.
+    pub fn keep_len(&self, kept: &Self, env: &Uiua) -> Option<usize> {
+        if self.rank() != 1 || kept.rank() == 0 || self.row_count() != kept.row_count() {
+            return None;
+        }
+        let counts = self.as_naturals(env, "").ok()?;
+        Some(counts.iter().sum())
+    }
     pub fn unkeep(self, kept: Self, into: Self, env: &Uiua) -> UiuaResult<Self> {
         let counts = self.as_naturals(
             env,
@@ -629,6 +675,7 @@ impl Value {
         Ok(match (kept, into) {
             (Value::Num(a), Value::Num(b)) => a.unkeep(&counts, b, env)?.into(),
             (Value::Byte(a), Value::Byte(b)) => a.unkeep(&counts, b, env)?.into(),
+            (Value::Complex(a), Value::Complex(b)) => a.unkeep(&counts, b, env)?.into(),
             (Value::Char(a), Value::Char(b)) => a.unkeep(&counts, b, env)?.into(),
             (Value::Func(a), Value::Func(b)) => a.unkeep(&counts, b, env)?.into(),
             (Value::Num(a), Value::Byte(b)) => a.unkeep(&counts, b.convert(), env)?.into(),
@@ -841,6 +888,7 @@ impl Value {
                 |a| Ok(a.pick_shaped(&index_shape, &index_data, env)?.into()),
                 |a| Ok(a.pick_shaped(&index_shape, &index_data, env)?.into()),
             )?,
+            Value::Complex(a) => Value::Complex(a.pick_shaped(&index_shape, &index_data, env)?),
             Value::Char(a) => Value::Char(a.pick_shaped(&index_shape, &index_data, env)?),
             Value::Func(a) => Value::Func(a.pick_shaped(&index_shape, &index_data, env)?),
         })
@@ -850,6 +898,7 @@ impl Value {
         Ok(match (self, into) {
             (Value::Num(a), Value::Num(b)) => a.unpick_impl(&index, b, env)?.into(),
             (Value::Byte(a), Value::Byte(b)) => a.unpick_impl(&index, b, env)?.into(),
+            (Value::Complex(a), Value::Complex(b)) => a.unpick_impl(&index, b, env)?.into(),
             (Value::Char(a), Value::Char(b)) => a.unpick_impl(&index, b, env)?.into(),
             (Value::Func(a), Value::Func(b)) => a.unpick_impl(&index, b, env)?.into(),
             (Value::Num(a), Value::Byte(b)) => a.unpick_impl(&index, b.convert(), env)?.into(),
@@ -967,6 +1016,7 @@ impl Value {
                 |a| Ok(a.take(&index, env)?.into()),
                 |a| Ok(a.take(&index, env)?.into()),
             )?,
+            Value::Complex(a) => Value::Complex(a.take(&index, env)?),
             Value::Char(a) => Value::Char(a.take(&index, env)?),
             Value::Func(a) => Value::Func(a.take(&index, env)?),
         })
@@ -979,6 +1029,7 @@ impl Value {
         Ok(match from {
             Value::Num(a) => Value::Num(a.drop(&index, env)?),
             Value::Byte(a) => Value::Byte(a.drop(&index, env)?),
+            Value::Complex(a) => Value::Complex(a.drop(&index, env)?),
             Value::Char(a) => Value::Char(a.drop(&index, env)?),
             Value::Func(a) => Value::Func(a.drop(&index, env)?),
         })
@@ -988,6 +1039,7 @@ impl Value {
         Ok(match (self, into) {
             (Value::Num(a), Value::Num(b)) => Value::Num(a.untake(&index, b, env)?),
             (Value::Byte(a), Value::Byte(b)) => Value::Byte(a.untake(&index, b, env)?),
+            (Value::Complex(a), Value::Complex(b)) => Value::Complex(a.untake(&index, b, env)?),
             (Value::Char(a), Value::Char(b)) => Value::Char(a.untake(&index, b, env)?),
             (Value::Func(a), Value::Func(b)) => Value::Func(a.untake(&index, b, env)?),
             (Value::Num(a), Value::Byte(b)) => Value::Num(a.untake(&index, b.convert(), env)?),
@@ -1006,6 +1058,7 @@ impl Value {
         Ok(match (self, into) {
             (Value::Num(a), Value::Num(b)) => Value::Num(a.undrop(&index, b, env)?),
             (Value::Byte(a), Value::Byte(b)) => Value::Byte(a.undrop(&index, b, env)?),
+            (Value::Complex(a), Value::Complex(b)) => Value::Complex(a.undrop(&index, b, env)?),
             (Value::Char(a), Value::Char(b)) => Value::Char(a.undrop(&index, b, env)?),
             (Value::Func(a), Value::Func(b)) => Value::Func(a.undrop(&index, b, env)?),
             (Value::Num(a), Value::Byte(b)) => Value::Num(a.undrop(&index, b.convert(), env)?),
@@ -1305,6 +1358,7 @@ impl Value {
         match &mut rotated {
             Value::Num(a) => a.rotate(&by, env)?,
             Value::Byte(a) => a.rotate(&by, env)?,
+            Value::Complex(a) => a.rotate(&by, env)?,
             Value::Char(a) => a.rotate(&by, env)?,
             Value::Func(a) => a.rotate(&by, env)?,
         }
@@ -1388,6 +1442,7 @@ impl Value {
                 |a| Ok(a.select_impl(indices_shape, &indices, env)?.into()),
                 |a| Ok(a.select_impl(indices_shape, &indices, env)?.into()),
             )?,
+            Value::Complex(a) => a.select_impl(indices_shape, &indices, env)?.into(),
             Value::Char(a) => a.select_impl(indices_shape, &indices, env)?.into(),
             Value::Func(a) => a.select_impl(indices_shape, &indices, env)?.into(),
         })
@@ -1402,6 +1457,9 @@ impl Value {
         Ok(match (self, into) {
             (Value::Num(a), Value::Num(b)) => a.unselect_impl(ind_shape, &ind, b, env)?.into(),
             (Value::Byte(a), Value::Byte(b)) => a.unselect_impl(ind_shape, &ind, b, env)?.into(),
+            (Value::Complex(a), Value::Complex(b)) => {
+                a.unselect_impl(ind_shape, &ind, b, env)?.into()
+            }
             (Value::Char(a), Value::Char(b)) => a.unselect_impl(ind_shape, &ind, b, env)?.into(),
             (Value::Func(a), Value::Func(b)) => a.unselect_impl(ind_shape, &ind, b, env)?.into(),
             (Value::Num(a), Value::Byte(b)) => {
@@ -1556,6 +1614,7 @@ impl Value {
         Ok(match from {
             Value::Num(a) => a.windows(&size_spec, env)?.into(),
             Value::Byte(a) => a.windows(&size_spec, env)?.into(),
+            Value::Complex(a) => a.windows(&size_spec, env)?.into(),
             Value::Char(a) => a.windows(&size_spec, env)?.into(),
             Value::Func(a) => a.windows(&size_spec, env)?.into(),
         })
@@ -1641,6 +1700,7 @@ impl Value {
         Ok(match (self, searched) {
             (Value::Num(a), Value::Num(b)) => a.find(b, env)?.into(),
             (Value::Byte(a), Value::Byte(b)) => a.find(b, env)?.into(),
+            (Value::Complex(a), Value::Complex(b)) => a.find(b, env)?.into(),
             (Value::Char(a), Value::Char(b)) => a.find(b, env)?.into(),
             (Value::Func(a), Value::Func(b)) => a.find(b, env)?.into(),
             (Value::Num(a), Value::Byte(b)) => a.find(&b.clone().convert(), env)?.into(),
@@ -1748,6 +1808,7 @@ impl Value {
         Ok(match (self, of) {
             (Value::Num(a), Value::Num(b)) => a.member(b, env)?.into(),
             (Value::Byte(a), Value::Byte(b)) => a.member(b, env)?.into(),
+            (Value::Complex(a), Value::Complex(b)) => a.member(b, env)?.into(),
             (Value::Char(a), Value::Char(b)) => a.member(b, env)?.into(),
             (Value::Func(a), Value::Func(b)) => a.member(b, env)?.into(),
             (Value::Num(a), Value::Byte(b)) => a.member(b, env)?.into(),
@@ -1826,6 +1887,7 @@ impl Value {
         Ok(match (self, searched_in) {
             (Value::Num(a), Value::Num(b)) => a.index_of(b, env)?.into(),
             (Value::Byte(a), Value::Byte(b)) => a.index_of(b, env)?.into(),
+            (Value::Complex(a), Value::Complex(b)) => a.index_of(b, env)?.into(),
             (Value::Char(a), Value::Char(b)) => a.index_of(b, env)?.into(),
             (Value::Func(a), Value::Func(b)) => a.index_of(b, env)?.into(),
             (Value::Num(a), Value::Byte(b)) => a.index_of(&b.clone().convert(), env)?.into(),
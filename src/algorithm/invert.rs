@@ -5,6 +5,7 @@ use std::{cell::RefCell, collections::HashMap, fmt};
 use crate::{
     check::instrs_signature,
     function::{Function, Instr},
+    optimize::unfuse,
     primitive::Primitive,
     value::Value,
 };
@@ -29,6 +30,9 @@ pub(crate) fn invert_instrs(instrs: &[Instr]) -> Option<Vec<Instr>> {
     if instrs.is_empty() {
         return Some(Vec::new());
     }
+    if let Some(unfused) = unfuse(instrs) {
+        return invert_instrs(&unfused);
+    }
 
     thread_local! {
         static INVERT_CACHE: RefCell<HashMap<Vec<Instr>, Option<Vec<Instr>>>> = RefCell::new(HashMap::new());
@@ -131,6 +135,9 @@ pub(crate) fn under_instrs(instrs: &[Instr]) -> Option<Under> {
     if instrs.is_empty() {
         return Some((Vec::new(), Vec::new()));
     }
+    if let Some(unfused) = unfuse(instrs) {
+        return under_instrs(&unfused);
+    }
 
     thread_local! {
         static UNDER_CACHE: RefCell<HashMap<Vec<Instr>, Option<Under>>> = RefCell::new(HashMap::new());
@@ -1,17 +1,21 @@
 //! Algorithms for looping modifiers
 
-use std::ops::{Add, Div, Mul, Sub};
+use std::{
+    ops::{Add, Div, Mul, Sub},
+    sync::atomic::{AtomicUsize, Ordering},
+};
 
+use rayon::prelude::*;
 use tinyvec::tiny_vec;
 
 use crate::{
-    algorithm::pervade::bin_pervade_generic,
+    algorithm::pervade::{bin_pervade_generic, kernel_threads},
     array::{Array, ArrayValue, FormatShape, Shape},
     cowslice::cowslice,
     primitive::Primitive,
     run::{ArrayArg, FunctionArg},
     value::Value,
-    Uiua, UiuaResult,
+    Uiua, UiuaError, UiuaResult,
 };
 
 fn flip<A, B, C>(f: impl Fn(A, B) -> C) -> impl Fn(B, A) -> C {
@@ -161,6 +165,46 @@ fn generic_fold1(f: Value, xs: Value, init: Option<Value>, env: &mut Uiua) -> Ui
     Ok(())
 }
 
+/// Reduce the windows of an array
+///
+/// Windows along the first axis are streamed as views of the array rather than materialized.
+pub fn reduce_windows(env: &mut Uiua) -> UiuaResult {
+    crate::profile_function!();
+    let f = env.pop(FunctionArg(1))?;
+    let size = env.pop(ArrayArg(1))?;
+    let xs = env.pop(ArrayArg(2))?;
+    let Some((n, count)) = row_window_spec(&size, &xs, env)? else {
+        env.push(size.windows(&xs, env)?);
+        env.push(f);
+        return reduce(env);
+    };
+    let mut acc = xs.window(0, n);
+    for i in 1..count {
+        env.push(xs.window(i, n));
+        env.push(acc);
+        let should_break = env.call_catch_break(f.clone())?;
+        acc = env.pop("reduced function result")?;
+        if should_break {
+            break;
+        }
+    }
+    env.push(acc);
+    Ok(())
+}
+
+/// Get the size and number of windows if a window size only slides along the first axis
+///
+/// Window sizes that would produce no windows are not streamed.
+fn row_window_spec(size: &Value, xs: &Value, env: &Uiua) -> UiuaResult<Option<(usize, usize)>> {
+    let size_spec = size.as_naturals(env, "Window size must be a list of natural numbers")?;
+    Ok(match *size_spec.as_slice() {
+        [n] if xs.rank() > 0 && (1..=xs.row_count()).contains(&n) => {
+            Some((n, xs.row_count() + 1 - n))
+        }
+        _ => None,
+    })
+}
+
 fn generic_fold_n(f: Value, env: &mut Uiua) -> UiuaResult {
     let sig = f.signature();
     if sig.args.saturating_sub(sig.outputs) != 1 {
@@ -305,6 +349,84 @@ fn generic_scan(f: Value, xs: Value, env: &mut Uiua) -> UiuaResult {
     Ok(())
 }
 
+/// The fewest iterations a loop must have to be split across threads
+const PAR_MIN_ITERATIONS: usize = 256;
+
+/// Run the iterations of a loop across multiple threads
+///
+/// This is only done if the loop is large, parallelism is enabled,
+/// and both the function and any functions in the arrays being looped over are pure.
+/// Otherwise, `None` is returned and the loop should be run sequentially.
+///
+/// `args` gets the values to push for an iteration, with the top of the stack last.
+/// If `break_message` is set, breaking is an error.
+///
+/// Iterations are split into contiguous chunks, and each chunk stops at its first break or error.
+/// Once a chunk stops, the chunks after it stop before their next iteration,
+/// because their outputs would be discarded.
+/// The returned outputs end at the first break, which is why the result matches a sequential run.
+fn par_loop(
+    f: &Value,
+    iterations: usize,
+    arrays_pure: bool,
+    args: impl Fn(usize) -> Vec<Value> + Sync,
+    break_message: Option<&str>,
+    env: &Uiua,
+) -> Option<UiuaResult<(Vec<Value>, bool)>> {
+    let threads = (env.max_threads())
+        .min(rayon::current_num_threads())
+        .min(iterations);
+    if threads <= 1 || iterations < PAR_MIN_ITERATIONS || !arrays_pure || !f.is_pure() {
+        return None;
+    }
+    let chunk_len = iterations.div_ceil(threads);
+    let envs: Vec<Uiua> = (0..threads).map(|_| env.fork(Vec::new())).collect();
+    // The index of the first chunk that broke or failed
+    let first_stopped = AtomicUsize::new(usize::MAX);
+    let chunks: Vec<(Vec<Value>, Result<bool, UiuaError>)> = (envs.into_par_iter().enumerate())
+        .map(|(c, mut env)| {
+            let range = c * chunk_len..((c + 1) * chunk_len).min(iterations);
+            let mut outputs = Vec::with_capacity(range.len());
+            for i in range {
+                if first_stopped.load(Ordering::Relaxed) < c {
+                    return (outputs, Ok(false));
+                }
+                for arg in args(i) {
+                    env.push(arg);
+                }
+                let broke = match break_message {
+                    Some(message) => env.call_error_on_break(f.clone(), message).map(|_| false),
+                    None => env.call_catch_break(f.clone()),
+                };
+                match broke.and_then(|broke| Ok((env.pop("loop function result")?, broke))) {
+                    Ok((output, broke)) => {
+                        outputs.push(output);
+                        if broke {
+                            first_stopped.fetch_min(c, Ordering::Relaxed);
+                            return (outputs, Ok(true));
+                        }
+                    }
+                    Err(e) => {
+                        first_stopped.fetch_min(c, Ordering::Relaxed);
+                        return (outputs, Err(e));
+                    }
+                }
+            }
+            (outputs, Ok(false))
+        })
+        .collect();
+    let mut outputs = Vec::with_capacity(iterations);
+    for (chunk_outputs, status) in chunks {
+        outputs.extend(chunk_outputs);
+        match status {
+            Ok(false) => {}
+            Ok(true) => return Some(Ok((outputs, true))),
+            Err(e) => return Some(Err(e)),
+        }
+    }
+    Some(Ok((outputs, false)))
+}
+
 pub fn each(env: &mut Uiua) -> UiuaResult {
     crate::profile_function!();
     let f = env.pop(FunctionArg(1))?;
@@ -353,20 +475,38 @@ pub fn each(env: &mut Uiua) -> UiuaResult {
 }
 
 fn each1_1(f: Value, xs: Value, env: &mut Uiua) -> UiuaResult {
-    let mut new_values = Vec::with_capacity(xs.flat_len());
     let mut new_shape = Shape::from(xs.shape());
-    let mut old_values = xs.into_flat_values();
-    for val in old_values.by_ref() {
-        env.push(val);
-        let broke = env.call_catch_break(f.clone())?;
-        new_values.push(env.pop("each's function result")?);
+    let pure = xs.is_pure();
+    let old_values: Vec<_> = xs.into_flat_values().collect();
+    let par = par_loop(
+        &f,
+        old_values.len(),
+        pure,
+        |i| vec![old_values[i].clone()],
+        None,
+        env,
+    );
+    let new_values = if let Some(res) = par {
+        let (mut new_values, broke) = res?;
         if broke {
-            for row in old_values {
-                new_values.push(row);
+            let done = new_values.len();
+            new_values.extend(old_values.into_iter().skip(done));
+        }
+        new_values
+    } else {
+        let mut new_values = Vec::with_capacity(old_values.len());
+        let mut old_values = old_values.into_iter();
+        for val in old_values.by_ref() {
+            env.push(val);
+            let broke = env.call_catch_break(f.clone())?;
+            new_values.push(env.pop("each's function result")?);
+            if broke {
+                new_values.extend(old_values);
+                break;
             }
-            break;
         }
-    }
+        new_values
+    };
     let mut eached = Value::from_row_values(new_values, env)?;
     new_shape.extend_from_slice(&eached.shape()[1..]);
     *eached.shape_mut() = new_shape;
@@ -388,21 +528,38 @@ fn each1_0(f: Value, xs: Value, env: &mut Uiua) -> UiuaResult {
 fn each2_1(f: Value, xs: Value, ys: Value, env: &mut Uiua) -> UiuaResult {
     let xs_shape = xs.shape().to_vec();
     let ys_shape = ys.shape().to_vec();
+    let pure = xs.is_pure() && ys.is_pure();
     let xs_values: Vec<_> = xs.into_flat_values().collect();
     let ys_values: Vec<_> = ys.into_flat_values().collect();
-    let (mut shape, values) = bin_pervade_generic(
-        &xs_shape,
-        xs_values,
-        &ys_shape,
-        ys_values,
-        env,
-        |x, y, env| {
-            env.push(y);
-            env.push(x);
-            env.call_error_on_break(f.clone(), "break is not allowed in multi-argument each")?;
-            env.pop("each's function result")
-        },
-    )?;
+    let par = if xs_shape == ys_shape {
+        par_loop(
+            &f,
+            xs_values.len(),
+            pure,
+            |i| vec![ys_values[i].clone(), xs_values[i].clone()],
+            Some("break is not allowed in multi-argument each"),
+            env,
+        )
+    } else {
+        None
+    };
+    let (mut shape, values) = if let Some(res) = par {
+        (Shape::from(xs_shape.as_slice()), res?.0)
+    } else {
+        bin_pervade_generic(
+            &xs_shape,
+            xs_values,
+            &ys_shape,
+            ys_values,
+            env,
+            |x, y, env| {
+                env.push(y);
+                env.push(x);
+                env.call_error_on_break(f.clone(), "break is not allowed in multi-argument each")?;
+                env.pop("each's function result")
+            },
+        )?
+    };
     let mut eached = Value::from_row_values(values, env)?;
     shape.extend_from_slice(&eached.shape()[1..]);
     *eached.shape_mut() = shape;
@@ -443,15 +600,32 @@ fn eachn_1(f: Value, args: Vec<Value>, env: &mut Uiua) -> UiuaResult {
         }
     }
     let elem_count = args[0].flat_len();
-    let mut arg_elems: Vec<_> = args.into_iter().map(|v| v.into_flat_values()).collect();
-    let mut new_values = Vec::new();
-    for _ in 0..elem_count {
-        for arg in arg_elems.iter_mut().rev() {
-            env.push(arg.next().unwrap());
+    let pure = args.iter().all(Value::is_pure);
+    let arg_elems: Vec<Vec<_>> = (args.into_iter())
+        .map(|v| v.into_flat_values().collect())
+        .collect();
+    let par = par_loop(
+        &f,
+        elem_count,
+        pure,
+        |i| arg_elems.iter().rev().map(|arg| arg[i].clone()).collect(),
+        Some("break is not allowed in multi-argument each"),
+        env,
+    );
+    let new_values = if let Some(res) = par {
+        res?.0
+    } else {
+        let mut arg_elems: Vec<_> = arg_elems.into_iter().map(Vec::into_iter).collect();
+        let mut new_values = Vec::new();
+        for _ in 0..elem_count {
+            for arg in arg_elems.iter_mut().rev() {
+                env.push(arg.next().unwrap());
+            }
+            env.call_error_on_break(f.clone(), "break is not allowed in multi-argument each")?;
+            new_values.push(env.pop("each's function result")?);
         }
-        env.call_error_on_break(f.clone(), "break is not allowed in multi-argument each")?;
-        new_values.push(env.pop("each's function result")?);
-    }
+        new_values
+    };
     let eached = Value::from_row_values(new_values, env)?;
     env.push(eached);
     Ok(())
@@ -528,6 +702,28 @@ pub fn rows(env: &mut Uiua) -> UiuaResult {
 
 fn rows1_1(f: Value, xs: Value, env: &mut Uiua) -> UiuaResult {
     let mut new_rows = Value::builder(xs.row_count());
+    let par = par_loop(
+        &f,
+        xs.row_count(),
+        xs.is_pure(),
+        |i| vec![xs.row(i)],
+        None,
+        env,
+    );
+    if let Some(res) = par {
+        let (outputs, broke) = res?;
+        let done = outputs.len();
+        for row in outputs {
+            new_rows.add_row(row, &env)?;
+        }
+        if broke {
+            for row in xs.into_rows().skip(done) {
+                new_rows.add_row(row, &env)?;
+            }
+        }
+        env.push(new_rows.finish());
+        return Ok(());
+    }
     let mut old_rows = xs.into_rows();
     for row in old_rows.by_ref() {
         env.push(row);
@@ -563,15 +759,28 @@ fn rows2_1(f: Value, xs: Value, ys: Value, env: &mut Uiua) -> UiuaResult {
             ys.row_count()
         )));
     }
-    let mut new_rows = Vec::with_capacity(xs.row_count());
-    let x_rows = xs.into_rows();
-    let y_rows = ys.into_rows();
-    for (x, y) in x_rows.into_iter().zip(y_rows) {
-        env.push(y);
-        env.push(x);
-        env.call_error_on_break(f.clone(), "break is not allowed in multi-argument rows")?;
-        new_rows.push(env.pop("rows's function result")?);
-    }
+    let par = par_loop(
+        &f,
+        xs.row_count(),
+        xs.is_pure() && ys.is_pure(),
+        |i| vec![ys.row(i), xs.row(i)],
+        Some("break is not allowed in multi-argument rows"),
+        env,
+    );
+    let new_rows = if let Some(res) = par {
+        res?.0
+    } else {
+        let mut new_rows = Vec::with_capacity(xs.row_count());
+        let x_rows = xs.into_rows();
+        let y_rows = ys.into_rows();
+        for (x, y) in x_rows.into_iter().zip(y_rows) {
+            env.push(y);
+            env.push(x);
+            env.call_error_on_break(f.clone(), "break is not allowed in multi-argument rows")?;
+            new_rows.push(env.pop("rows's function result")?);
+        }
+        new_rows
+    };
     env.push(Value::from_row_values(new_rows, env)?);
     Ok(())
 }
@@ -606,15 +815,28 @@ fn rowsn_1(f: Value, args: Vec<Value>, env: &mut Uiua) -> UiuaResult {
         }
     }
     let row_count = args[0].row_count();
-    let mut arg_elems: Vec<_> = args.into_iter().map(|v| v.into_rows()).collect();
-    let mut new_values = Vec::new();
-    for _ in 0..row_count {
-        for arg in arg_elems.iter_mut().rev() {
-            env.push(arg.next().unwrap());
+    let par = par_loop(
+        &f,
+        row_count,
+        args.iter().all(Value::is_pure),
+        |i| args.iter().rev().map(|arg| arg.row(i)).collect(),
+        Some("break is not allowed in multi-argument each"),
+        env,
+    );
+    let new_values = if let Some(res) = par {
+        res?.0
+    } else {
+        let mut arg_elems: Vec<_> = args.into_iter().map(|v| v.into_rows()).collect();
+        let mut new_values = Vec::new();
+        for _ in 0..row_count {
+            for arg in arg_elems.iter_mut().rev() {
+                env.push(arg.next().unwrap());
+            }
+            env.call_error_on_break(f.clone(), "break is not allowed in multi-argument each")?;
+            new_values.push(env.pop("each's function result")?);
         }
-        env.call_error_on_break(f.clone(), "break is not allowed in multi-argument each")?;
-        new_values.push(env.pop("each's function result")?);
-    }
+        new_values
+    };
     let eached = Value::from_row_values(new_values, env)?;
     env.push(eached);
     Ok(())
@@ -632,6 +854,57 @@ fn rowsn_0(f: Value, args: Vec<Value>, env: &mut Uiua) -> UiuaResult {
     Ok(())
 }
 
+/// Apply a function to each window of an array
+///
+/// Windows along the first axis are streamed as views of the array rather than materialized.
+pub fn rows_windows(env: &mut Uiua) -> UiuaResult {
+    crate::profile_function!();
+    let f = env.pop(FunctionArg(1))?;
+    let size = env.pop(ArrayArg(1))?;
+    let xs = env.pop(ArrayArg(2))?;
+    let Some((n, count)) = row_window_spec(&size, &xs, env)? else {
+        env.push(size.windows(&xs, env)?);
+        env.push(f);
+        return rows(env);
+    };
+    let mut new_rows = Value::builder(count);
+    let par = par_loop(
+        &f,
+        count,
+        xs.is_pure(),
+        |i| vec![xs.window(i, n)],
+        None,
+        env,
+    );
+    if let Some(res) = par {
+        let (outputs, broke) = res?;
+        let done = outputs.len();
+        for row in outputs {
+            new_rows.add_row(row, &env)?;
+        }
+        if broke {
+            for i in done..count {
+                new_rows.add_row(xs.window(i, n), &env)?;
+            }
+        }
+        env.push(new_rows.finish());
+        return Ok(());
+    }
+    for i in 0..count {
+        env.push(xs.window(i, n));
+        let broke = env.call_catch_break(f.clone())?;
+        new_rows.add_row(env.pop("rows' function result")?, &env)?;
+        if broke {
+            for i in i + 1..count {
+                new_rows.add_row(xs.window(i, n), &env)?;
+            }
+            break;
+        }
+    }
+    env.push(new_rows.finish());
+    Ok(())
+}
+
 pub fn distribute(env: &mut Uiua) -> UiuaResult {
     crate::profile_function!();
     let f = env.pop(FunctionArg(1))?;
@@ -735,32 +1008,53 @@ pub fn table(env: &mut Uiua) -> UiuaResult {
                 return generic_table(f, Value::Num(xs), Value::Num(ys), env);
             }
         }
-        (Some((prim, flipped)), Value::Byte(xs), Value::Byte(ys)) => match prim {
-            Primitive::Eq => env.push(fast_table(xs, ys, bin_bool(|x, y| x == y))),
-            Primitive::Ne => env.push(fast_table(xs, ys, bin_bool(|x, y| x != y))),
-            Primitive::Lt if flipped => env.push(fast_table(xs, ys, bin_bool(|x, y| x < y))),
-            Primitive::Lt => env.push(fast_table(xs, ys, bin_bool(|x, y| y < x))),
-            Primitive::Gt if flipped => env.push(fast_table(xs, ys, bin_bool(|x, y| x > y))),
-            Primitive::Gt => env.push(fast_table(xs, ys, bin_bool(|x, y| y > x))),
-            Primitive::Le if flipped => env.push(fast_table(xs, ys, bin_bool(|x, y| x <= y))),
-            Primitive::Le => env.push(fast_table(xs, ys, bin_bool(|x, y| y <= x))),
-            Primitive::Ge if flipped => env.push(fast_table(xs, ys, bin_bool(|x, y| x >= y))),
-            Primitive::Ge => env.push(fast_table(xs, ys, bin_bool(|x, y| y >= x))),
-            Primitive::Add => env.push(fast_table(xs, ys, |a, b| f64::from(a) + f64::from(b))),
-            Primitive::Sub if flipped => {
-                env.push(fast_table(xs, ys, |a, b| f64::from(a) - f64::from(b)))
-            }
-            Primitive::Sub => env.push(fast_table(xs, ys, |a, b| f64::from(b) - f64::from(a))),
-            Primitive::Mul => env.push(fast_table(xs, ys, |a, b| f64::from(a) * f64::from(b))),
-            Primitive::Div if flipped => {
-                env.push(fast_table(xs, ys, |a, b| f64::from(a) / f64::from(b)))
+        (Some((prim, flipped)), Value::Byte(xs), Value::Byte(ys)) => {
+            let threads = kernel_threads(xs.data.len() * ys.data.len(), env);
+            match prim {
+                Primitive::Eq => env.push(fast_table(xs, ys, threads, bin_bool(|x, y| x == y))),
+                Primitive::Ne => env.push(fast_table(xs, ys, threads, bin_bool(|x, y| x != y))),
+                Primitive::Lt if flipped => {
+                    env.push(fast_table(xs, ys, threads, bin_bool(|x, y| x < y)))
+                }
+                Primitive::Lt => env.push(fast_table(xs, ys, threads, bin_bool(|x, y| y < x))),
+                Primitive::Gt if flipped => {
+                    env.push(fast_table(xs, ys, threads, bin_bool(|x, y| x > y)))
+                }
+                Primitive::Gt => env.push(fast_table(xs, ys, threads, bin_bool(|x, y| y > x))),
+                Primitive::Le if flipped => {
+                    env.push(fast_table(xs, ys, threads, bin_bool(|x, y| x <= y)))
+                }
+                Primitive::Le => env.push(fast_table(xs, ys, threads, bin_bool(|x, y| y <= x))),
+                Primitive::Ge if flipped => {
+                    env.push(fast_table(xs, ys, threads, bin_bool(|x, y| x >= y)))
+                }
+                Primitive::Ge => env.push(fast_table(xs, ys, threads, bin_bool(|x, y| y >= x))),
+                Primitive::Add => env.push(fast_table(xs, ys, threads, |a, b| {
+                    f64::from(a) + f64::from(b)
+                })),
+                Primitive::Sub if flipped => env.push(fast_table(xs, ys, threads, |a, b| {
+                    f64::from(a) - f64::from(b)
+                })),
+                Primitive::Sub => env.push(fast_table(xs, ys, threads, |a, b| {
+                    f64::from(b) - f64::from(a)
+                })),
+                Primitive::Mul => env.push(fast_table(xs, ys, threads, |a, b| {
+                    f64::from(a) * f64::from(b)
+                })),
+                Primitive::Div if flipped => env.push(fast_table(xs, ys, threads, |a, b| {
+                    f64::from(a) / f64::from(b)
+                })),
+                Primitive::Div => env.push(fast_table(xs, ys, threads, |a, b| {
+                    f64::from(b) / f64::from(a)
+                })),
+                Primitive::Min => env.push(fast_table(xs, ys, threads, u8::min)),
+                Primitive::Max => env.push(fast_table(xs, ys, threads, u8::max)),
+                Primitive::Join | Primitive::Couple => {
+                    env.push(fast_table_join_or_couple(xs, ys, threads))
+                }
+                _ => generic_table(f, Value::Byte(xs), Value::Byte(ys), env)?,
             }
-            Primitive::Div => env.push(fast_table(xs, ys, |a, b| f64::from(b) / f64::from(a))),
-            Primitive::Min => env.push(fast_table(xs, ys, u8::min)),
-            Primitive::Max => env.push(fast_table(xs, ys, u8::max)),
-            Primitive::Join | Primitive::Couple => env.push(fast_table_join_or_couple(xs, ys)),
-            _ => generic_table(f, Value::Byte(xs), Value::Byte(ys), env)?,
-        },
+        }
         (_, xs, ys) => generic_table(f, xs, ys, env)?,
     }
     Ok(())
@@ -774,26 +1068,27 @@ fn table_nums(
     ys: Array<f64>,
     env: &mut Uiua,
 ) -> Result<(), (Array<f64>, Array<f64>)> {
+    let threads = kernel_threads(xs.data.len() * ys.data.len(), env);
     match prim {
-        Primitive::Eq => env.push(fast_table(xs, ys, bin_bool(|x, y| x == y))),
-        Primitive::Ne => env.push(fast_table(xs, ys, bin_bool(|x, y| x != y))),
-        Primitive::Lt if flipped => env.push(fast_table(xs, ys, bin_bool(|x, y| x < y))),
-        Primitive::Lt => env.push(fast_table(xs, ys, bin_bool(|x, y| y < x))),
-        Primitive::Gt if flipped => env.push(fast_table(xs, ys, bin_bool(|x, y| x > y))),
-        Primitive::Gt => env.push(fast_table(xs, ys, bin_bool(|x, y| y > x))),
-        Primitive::Le if flipped => env.push(fast_table(xs, ys, bin_bool(|x, y| x <= y))),
-        Primitive::Le => env.push(fast_table(xs, ys, bin_bool(|x, y| y <= x))),
-        Primitive::Ge if flipped => env.push(fast_table(xs, ys, bin_bool(|x, y| x >= y))),
-        Primitive::Ge => env.push(fast_table(xs, ys, bin_bool(|x, y| y >= x))),
-        Primitive::Add => env.push(fast_table(xs, ys, Add::add)),
-        Primitive::Sub if flipped => env.push(fast_table(xs, ys, Sub::sub)),
-        Primitive::Sub => env.push(fast_table(xs, ys, flip(Sub::sub))),
-        Primitive::Mul => env.push(fast_table(xs, ys, Mul::mul)),
-        Primitive::Div if flipped => env.push(fast_table(xs, ys, Div::div)),
-        Primitive::Div => env.push(fast_table(xs, ys, flip(Div::div))),
-        Primitive::Min => env.push(fast_table(xs, ys, f64::min)),
-        Primitive::Max => env.push(fast_table(xs, ys, f64::max)),
-        Primitive::Join | Primitive::Couple => env.push(fast_table_join_or_couple(xs, ys)),
+        Primitive::Eq => env.push(fast_table(xs, ys, threads, bin_bool(|x, y| x == y))),
+        Primitive::Ne => env.push(fast_table(xs, ys, threads, bin_bool(|x, y| x != y))),
+        Primitive::Lt if flipped => env.push(fast_table(xs, ys, threads, bin_bool(|x, y| x < y))),
+        Primitive::Lt => env.push(fast_table(xs, ys, threads, bin_bool(|x, y| y < x))),
+        Primitive::Gt if flipped => env.push(fast_table(xs, ys, threads, bin_bool(|x, y| x > y))),
+        Primitive::Gt => env.push(fast_table(xs, ys, threads, bin_bool(|x, y| y > x))),
+        Primitive::Le if flipped => env.push(fast_table(xs, ys, threads, bin_bool(|x, y| x <= y))),
+        Primitive::Le => env.push(fast_table(xs, ys, threads, bin_bool(|x, y| y <= x))),
+        Primitive::Ge if flipped => env.push(fast_table(xs, ys, threads, bin_bool(|x, y| x >= y))),
+        Primitive::Ge => env.push(fast_table(xs, ys, threads, bin_bool(|x, y| y >= x))),
+        Primitive::Add => env.push(fast_table(xs, ys, threads, Add::add)),
+        Primitive::Sub if flipped => env.push(fast_table(xs, ys, threads, Sub::sub)),
+        Primitive::Sub => env.push(fast_table(xs, ys, threads, flip(Sub::sub))),
+        Primitive::Mul => env.push(fast_table(xs, ys, threads, Mul::mul)),
+        Primitive::Div if flipped => env.push(fast_table(xs, ys, threads, Div::div)),
+        Primitive::Div => env.push(fast_table(xs, ys, threads, flip(Div::div))),
+        Primitive::Min => env.push(fast_table(xs, ys, threads, f64::min)),
+        Primitive::Max => env.push(fast_table(xs, ys, threads, f64::max)),
+        Primitive::Join | Primitive::Couple => env.push(fast_table_join_or_couple(xs, ys, threads)),
         _ => return Err((xs, ys)),
     }
     Ok(())
@@ -802,27 +1097,40 @@ fn table_nums(
 fn fast_table<A: ArrayValue, B: ArrayValue, C: ArrayValue>(
     a: Array<A>,
     b: Array<B>,
-    f: impl Fn(A, B) -> C,
+    threads: usize,
+    f: impl Fn(A, B) -> C + Sync,
 ) -> Array<C> {
-    let mut new_data = Vec::with_capacity(a.data.len() * b.data.len());
-    for x in a.data {
-        for y in b.data.iter().cloned() {
-            new_data.push(f(x.clone(), y));
-        }
-    }
+    let (f, ys) = (&f, &b.data);
+    let row = move |x: &A| {
+        let x = x.clone();
+        ys.iter().map(move |y| f(x.clone(), y.clone()))
+    };
+    let new_data: Vec<C> = if threads <= 1 {
+        a.data.iter().flat_map(row).collect()
+    } else {
+        (a.data.par_iter())
+            .with_min_len(a.data.len().div_ceil(threads))
+            .flat_map_iter(row)
+            .collect()
+    };
     let mut new_shape = a.shape;
     new_shape.extend_from_slice(&b.shape);
     Array::new(new_shape, new_data)
 }
-
-fn fast_table_join_or_couple<T: ArrayValue>(a: Array<T>, b: Array<T>) -> Array<T> {
-    let mut new_data = Vec::with_capacity(a.data.len() * b.data.len() * 2);
-    for x in a.data {
-        for y in b.data.iter().cloned() {
-            new_data.push(x.clone());
-            new_data.push(y);
-        }
-    }
+fn fast_table_join_or_couple<T: ArrayValue>(a: Array<T>, b: Array<T>, threads: usize) -> Array<T> {
+    let ys = &b.data;
+    let row = move |x: &T| {
+        let x = x.clone();
+        ys.iter().flat_map(move |y| [x.clone(), y.clone()])
+    };
+    let new_data: Vec<T> = if threads <= 1 {
+        a.data.iter().flat_map(row).collect()
+    } else {
+        (a.data.par_iter())
+            .with_min_len(a.data.len().div_ceil(threads))
+            .flat_map_iter(row)
+            .collect()
+    };
     let mut new_shape = a.shape;
     new_shape.extend_from_slice(&b.shape);
     new_shape.push(2);
@@ -840,16 +1148,38 @@ fn generic_table(f: Value, xs: Value, ys: Value, env: &mut Uiua) -> UiuaResult {
     let mut new_shape = Shape::from(xs.shape());
     new_shape.extend_from_slice(ys.shape());
     let mut items = Value::builder(xs.flat_len() * ys.flat_len());
+    let pure = xs.is_pure() && ys.is_pure();
+    let x_values = xs.into_flat_values().collect::<Vec<_>>();
     let y_values = ys.into_flat_values().collect::<Vec<_>>();
-    for x in xs.into_flat_values() {
-        for y in y_values.iter().cloned() {
-            env.push(y);
-            env.push(x.clone());
-            env.call_error_on_break(f.clone(), "break is not allowed in table")?;
-            let item = env.pop("tabled function result")?;
+    let par = par_loop(
+        &f,
+        x_values.len() * y_values.len(),
+        pure,
+        |i| {
+            vec![
+                y_values[i % y_values.len()].clone(),
+                x_values[i / y_values.len()].clone(),
+            ]
+        },
+        Some("break is not allowed in table"),
+        env,
+    );
+    if let Some(res) = par {
+        for item in res?.0 {
             item.validate_shape();
             items.add_row(item, &env)?;
         }
+    } else {
+        for x in x_values {
+            for y in y_values.iter().cloned() {
+                env.push(y);
+                env.push(x.clone());
+                env.call_error_on_break(f.clone(), "break is not allowed in table")?;
+                let item = env.pop("tabled function result")?;
+                item.validate_shape();
+                items.add_row(item, &env)?;
+            }
+        }
     }
     let mut tabled = items.finish();
     new_shape.extend_from_slice(&tabled.shape()[1..]);
@@ -1200,6 +1530,10 @@ impl Value {
                 .partition_groups(markers, env)?
                 .map(Into::into)
                 .collect(),
+            Value::Complex(arr) => arr
+                .partition_groups(markers, env)?
+                .map(Into::into)
+                .collect(),
             Value::Char(arr) => arr
                 .partition_groups(markers, env)?
                 .map(Into::into)
@@ -1255,6 +1589,7 @@ impl Value {
         Ok(match self {
             Value::Num(arr) => arr.group_groups(indices, env)?.map(Into::into).collect(),
             Value::Byte(arr) => arr.group_groups(indices, env)?.map(Into::into).collect(),
+            Value::Complex(arr) => arr.group_groups(indices, env)?.map(Into::into).collect(),
             Value::Char(arr) => arr.group_groups(indices, env)?.map(Into::into).collect(),
             Value::Func(arr) => arr.group_groups(indices, env)?.map(Into::into).collect(),
         })
@@ -1338,3 +1673,68 @@ fn collapse_groups(
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::algorithm::run_threaded;
+
+    #[test]
+    fn parallel_loops_match_sequential() {
+        for code in [
+            "≡(/+⇡) ⇡1000",
+            "≡(⎋>500.) ⇡1000",
+            "≡(⍤\"too big\" <900.) ⇡1000",
+            "≡⊂ ⇡1000 ⇡1000",
+            "≡(⊂⊂) ⇡1000 ⇡1000 ⇡1000",
+            "∵(×2) ↯10_100⇡1000",
+            "∵(⎋>700.) ⇡1000",
+            "∵(+1×) ⇡1000 ⇡1000",
+            "∵(++) ⇡1000 ⇡1000 ⇡1000",
+            "⊞(⊂+) ⇡50 ⇡50",
+            "⊞(⎋1) ⇡50 ⇡50",
+            "≡(⇡↥1) {⇡1000}",
+            "⊞- ⇡300 ⇡300",
+            "⊞↥ ⇡300 ÷2⇡300",
+            "⊞≥ =0◿3⇡300 =0◿5⇡300",
+            "⊞⊟ ⇡300 ⇡300",
+        ] {
+            let parallel = run_threaded(code, 4);
+            assert_eq!(run_threaded(code, 1), parallel, "{code}");
+        }
+    }
+
+    #[test]
+    fn parallel_loops_stop_after_break() {
+        // Every iteration after the break is slow, so finishing them would time out
+        let code = "≡(⎋=5. ?(⍥(+1)100000)∘ >300.) ⇡1000";
+        assert_eq!(run_threaded(code, 1), run_threaded(code, 4));
+    }
+
+    #[test]
+    fn row_windows_match_materialized() {
+        for (fused, materialized) in [
+            ("≡(÷3/+)◫3 ⇡1000", "≡(÷3/+)∘◫3 ⇡1000"),
+            ("≡/+◫2 ↯500_2⇡1000", "≡/+∘◫2 ↯500_2⇡1000"),
+            ("≡(⎋>500⊢.)◫1 ⇡1000", "≡(⎋>500⊢.)∘◫1 ⇡1000"),
+            ("≡⊢◫2_2 ↯3_3⇡9", "≡⊢∘◫2_2 ↯3_3⇡9"),
+            ("≡□◫5 [1 2 3]", "≡□∘◫5 [1 2 3]"),
+            ("/⊂◫2 ⇡100", "/⊂∘◫2 ⇡100"),
+            ("/(⎋>10⊢.+)◫2 ⇡100", "/(⎋>10⊢.+)∘◫2 ⇡100"),
+            ("/+◫4 [1 2 3]", "/+∘◫4 [1 2 3]"),
+        ] {
+            let parallel = run_threaded(fused, 4);
+            assert_eq!(
+                run_threaded(materialized, 1),
+                run_threaded(fused, 1),
+                "{fused}"
+            );
+            assert_eq!(run_threaded(fused, 1), parallel, "{fused}");
+        }
+        // Make sure the fused versions are actually used
+        for (code, fused) in [("≡(⍤\"no\" 0 ⊢)◫1 ⇡3", "≡◫"), ("/(⍤\"no\" 0 +)◫1 ⇡3", "/◫")]
+        {
+            let error = run_threaded(code, 1).unwrap_err();
+            assert!(error.contains(&format!("in {fused} ")), "{error}");
+        }
+    }
+}
@@ -7,12 +7,14 @@ use crate::{
     Uiua, UiuaError, UiuaResult,
 };
 
+mod datetime;
 mod dyadic;
 pub mod fork;
 pub(crate) mod invert;
 pub mod loops;
 mod monadic;
 pub mod pervade;
+mod regex;
 
 fn max_shape(a: &[usize], b: &[usize]) -> Shape {
     let shape_len = a.len().max(b.len());
@@ -145,3 +147,21 @@ fn op2_bytes_retry_fill<T, C: FillContext>(
         }
     }
 }
+
+/// Run code on a pool with `threads` threads, even if there is only one core
+///
+/// The execution limit makes a runaway parallel loop fail instead of hanging.
+#[cfg(test)]
+pub(crate) fn run_threaded(code: &str, threads: usize) -> Result<Vec<crate::value::Value>, String> {
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(threads)
+        .build()
+        .unwrap();
+    pool.install(|| {
+        let mut env = Uiua::with_native_sys()
+            .with_max_threads(threads)
+            .with_execution_limit(std::time::Duration::from_secs(10));
+        env.load_str(code).map_err(|e| e.to_string())?;
+        Ok(env.take_stack())
+    })
+}
@@ -19,6 +19,7 @@ impl Value {
             Array::deshape,
             Array::deshape,
             Array::deshape,
+            Array::deshape,
         )
     }
     pub fn parse_num(&self, env: &Uiua) -> UiuaResult<Self> {
@@ -102,6 +103,7 @@ impl Value {
             |a| a.first(env).map(Into::into),
             |a| a.first(env).map(Into::into),
             |a| a.first(env).map(Into::into),
+            |a| a.first(env).map(Into::into),
         )
     }
     pub fn last(self, env: &Uiua) -> UiuaResult<Self> {
@@ -110,6 +112,7 @@ impl Value {
             |a| a.last(env).map(Into::into),
             |a| a.last(env).map(Into::into),
             |a| a.last(env).map(Into::into),
+            |a| a.last(env).map(Into::into),
         )
     }
 }
@@ -147,6 +150,7 @@ impl Value {
             Array::reverse,
             Array::reverse,
             Array::reverse,
+            Array::reverse,
         )
     }
 }
@@ -177,6 +181,7 @@ impl Value {
             Array::transpose,
             Array::transpose,
             Array::transpose,
+            Array::transpose,
         )
     }
     pub fn inv_transpose(&mut self) {
@@ -185,6 +190,7 @@ impl Value {
             Array::inv_transpose,
             Array::inv_transpose,
             Array::inv_transpose,
+            Array::inv_transpose,
         )
     }
 }
@@ -234,12 +240,39 @@ impl<T: ArrayValue> Array<T> {
 
 impl Value {
     pub fn rise(&self, env: &Uiua) -> UiuaResult<Self> {
-        self.generic_ref_env_deep(Array::rise, Array::rise, Array::rise, Array::rise, env)
-            .map(Self::from_iter)
+        self.generic_ref_env_deep(
+            Array::rise,
+            Array::rise,
+            Array::rise,
+            Array::rise,
+            Array::rise,
+            env,
+        )
+        .map(Self::from_iter)
+    }
+    /// Get the [`Value::first`] of the [`Value::rise`] without building the rise
+    ///
+    /// Returns `None` if the rise has no first row.
+    pub fn first_rise(&self) -> Option<Self> {
+        let index = self.generic_ref_deep(
+            Array::first_rise,
+            Array::first_rise,
+            Array::first_rise,
+            Array::first_rise,
+            Array::first_rise,
+        );
+        index.map(Into::into)
     }
     pub fn fall(&self, env: &Uiua) -> UiuaResult<Self> {
-        self.generic_ref_env_deep(Array::fall, Array::fall, Array::fall, Array::fall, env)
-            .map(Self::from_iter)
+        self.generic_ref_env_deep(
+            Array::fall,
+            Array::fall,
+            Array::fall,
+            Array::fall,
+            Array::fall,
+            env,
+        )
+        .map(Self::from_iter)
     }
     pub fn classify(&self, env: &Uiua) -> UiuaResult<Self> {
         self.generic_ref_env_deep(
@@ -247,6 +280,7 @@ impl Value {
             Array::classify,
             Array::classify,
             Array::classify,
+            Array::classify,
             env,
         )
         .map(Self::from_iter)
@@ -257,6 +291,7 @@ impl Value {
             Array::deduplicate,
             Array::deduplicate,
             Array::deduplicate,
+            Array::deduplicate,
         )
     }
 }
@@ -270,16 +305,18 @@ impl<T: ArrayValue> Array<T> {
             return Ok(Vec::new());
         }
         let mut indices = (0..self.row_count()).collect::<Vec<_>>();
-        indices.par_sort_by(|&a, &b| {
-            self.row_slice(a)
-                .iter()
-                .zip(self.row_slice(b))
-                .map(|(a, b)| a.array_cmp(b))
-                .find(|x| x != &Ordering::Equal)
-                .unwrap_or(Ordering::Equal)
-        });
+        indices.par_sort_by(|&a, &b| self.cmp_rows(a, b));
         Ok(indices)
     }
+    /// Get the index of the first minimal row, which is the first index [`Array::rise`] would give
+    ///
+    /// Returns `None` if [`Array::rise`] would fail or give no indices.
+    pub fn first_rise(&self) -> Option<usize> {
+        if self.rank() == 0 || self.flat_len() == 0 {
+            return None;
+        }
+        (0..self.row_count()).min_by(|&a, &b| self.cmp_rows(a, b))
+    }
     pub fn fall(&self, env: &Uiua) -> UiuaResult<Vec<usize>> {
         if self.rank() == 0 {
             return Err(env.error("Cannot fall a scalar"));
@@ -288,16 +325,17 @@ impl<T: ArrayValue> Array<T> {
             return Ok(Vec::new());
         }
         let mut indices = (0..self.row_count()).collect::<Vec<_>>();
-        indices.par_sort_by(|&a, &b| {
-            self.row_slice(a)
-                .iter()
-                .zip(self.row_slice(b))
-                .map(|(a, b)| b.array_cmp(a))
-                .find(|x| x != &Ordering::Equal)
-                .unwrap_or(Ordering::Equal)
-        });
+        indices.par_sort_by(|&a, &b| self.cmp_rows(b, a));
         Ok(indices)
     }
+    fn cmp_rows(&self, a: usize, b: usize) -> Ordering {
+        self.row_slice(a)
+            .iter()
+            .zip(self.row_slice(b))
+            .map(|(a, b)| a.array_cmp(b))
+            .find(|x| x != &Ordering::Equal)
+            .unwrap_or(Ordering::Equal)
+    }
     pub fn classify(&self, env: &Uiua) -> UiuaResult<Vec<usize>> {
         if self.rank() == 0 {
             return Err(env.error("Cannot classify a rank-0 array"));
@@ -8,7 +8,18 @@ use std::{
     slice::{self, Chunks},
 };
 
-use crate::{array::*, Uiua, UiuaError, UiuaResult};
+use rayon::prelude::*;
+
+use crate::{
+    array::*,
+    complex::Complex,
+    function::Instr,
+    optimize::run_unfused,
+    primitive::Primitive,
+    run::{ArrayArg, FunctionArg},
+    value::Value,
+    Uiua, UiuaError, UiuaResult,
+};
 
 use super::max_shape;
 
@@ -123,6 +134,190 @@ where
     }
 }
 
+/// The fewest elements a pervasive operation must have to be split across threads
+const PAR_MIN_LEN: usize = 1 << 15;
+
+/// Get how many threads a pervasive operation on some number of elements should be split across
+pub fn kernel_threads(len: usize, env: &Uiua) -> usize {
+    if len < PAR_MIN_LEN {
+        1
+    } else {
+        env.max_threads().min(rayon::current_num_threads())
+    }
+}
+
+/// Apply a function to every element of a slice
+///
+/// The loop is simple enough to be vectorized.
+/// If there are multiple threads, each one handles a contiguous chunk.
+pub fn map_kernel<A, C>(a: &[A], threads: usize, f: impl Fn(A) -> C + Sync) -> Vec<C>
+where
+    A: ArrayValue,
+    C: ArrayValue,
+{
+    if threads <= 1 {
+        a.iter().map(|a| f(a.clone())).collect()
+    } else {
+        (a.par_iter())
+            .with_min_len(a.len().div_ceil(threads))
+            .map(|a| f(a.clone()))
+            .collect()
+    }
+}
+
+/// Apply a function to corresponding elements of two slices of the same length
+///
+/// The loop is simple enough to be vectorized.
+/// If there are multiple threads, each one handles a contiguous chunk.
+pub fn zip_kernel<A, B, C>(a: &[A], b: &[B], threads: usize, f: impl Fn(A, B) -> C + Sync) -> Vec<C>
+where
+    A: ArrayValue,
+    B: ArrayValue,
+    C: ArrayValue,
+{
+    debug_assert_eq!(a.len(), b.len());
+    if threads <= 1 {
+        (a.iter().zip(b))
+            .map(|(a, b)| f(a.clone(), b.clone()))
+            .collect()
+    } else {
+        (a.par_iter().zip(b))
+            .with_min_len(a.len().div_ceil(threads))
+            .map(|(a, b)| f(a.clone(), b.clone()))
+            .collect()
+    }
+}
+
+/// Apply an infallible pervasive function to two arrays
+///
+/// Arrays with the same shape, or where one is a scalar, are handled by the kernels.
+/// All other arrays go through [`bin_pervade`].
+pub fn bin_pervade_simple<A, B, C>(
+    a: &Array<A>,
+    b: &Array<B>,
+    env: &Uiua,
+    f: impl Fn(A, B) -> C + Copy + Sync,
+) -> UiuaResult<Array<C>>
+where
+    A: ArrayValue,
+    B: ArrayValue,
+    C: ArrayValue,
+{
+    let threads = kernel_threads(a.flat_len().max(b.flat_len()), env);
+    if a.shape == b.shape {
+        let data = zip_kernel(&a.data, &b.data, threads, f);
+        Ok(Array::new(a.shape.clone(), data))
+    } else if let Some(a) = a.as_scalar() {
+        let data = map_kernel(&b.data, threads, |b| f(a.clone(), b));
+        Ok(Array::new(b.shape.clone(), data))
+    } else if let Some(b) = b.as_scalar() {
+        let data = map_kernel(&a.data, threads, |a| f(a, b.clone()));
+        Ok(Array::new(a.shape.clone(), data))
+    } else {
+        bin_pervade(a, b, env, InfalliblePervasiveFn::new(f))
+    }
+}
+
+/// A step in a chain of pervasive operations on numbers
+#[derive(Clone, Copy)]
+pub(crate) enum ChainStep {
+    Monadic(fn(f64) -> f64),
+    /// A dyadic function with a constant first argument
+    Dyadic(fn(f64, f64) -> f64, f64),
+}
+
+impl ChainStep {
+    /// Get the step at the start of some instructions, along with how many instructions it spans
+    pub(crate) fn parse(instrs: &[Instr]) -> Option<(Self, usize)> {
+        use Primitive::*;
+        match instrs {
+            [Instr::Prim(prim, _), ..] => {
+                let f: fn(f64) -> f64 = match prim {
+                    Not => not::num,
+                    Neg => neg::num,
+                    Abs => abs::num,
+                    Sign => sign::num,
+                    Sqrt => sqrt::num,
+                    Sin => sin::num,
+                    Cos => cos::num,
+                    Asin => asin::num,
+                    Acos => acos::num,
+                    Floor => floor::num,
+                    Ceil => ceil::num,
+                    Round => round::num,
+                    _ => return None,
+                };
+                Some((ChainStep::Monadic(f), 1))
+            }
+            [Instr::Push(val), Instr::Prim(prim, _), ..] => {
+                let Value::Num(a) = &**val else {
+                    return None;
+                };
+                if a.rank() != 0 {
+                    return None;
+                }
+                let f: fn(f64, f64) -> f64 = match prim {
+                    Add => add::num_num,
+                    Sub => sub::num_num,
+                    Mul => mul::num_num,
+                    Div => div::num_num,
+                    Mod => modulus::num_num,
+                    Pow => pow::num_num,
+                    Log => log::num_num,
+                    Min => min::num_num,
+                    Max => max::num_num,
+                    Atan => atan2::num_num,
+                    _ => return None,
+                };
+                Some((ChainStep::Dyadic(f, a.data[0]), 2))
+            }
+            _ => None,
+        }
+    }
+    fn apply(self, x: f64) -> f64 {
+        match self {
+            ChainStep::Monadic(f) => f(x),
+            ChainStep::Dyadic(f, a) => f(a, x),
+        }
+    }
+}
+
+/// Split instructions into a chain of pervasive steps on numbers
+pub(crate) fn chain_steps(mut instrs: &[Instr]) -> Option<Vec<ChainStep>> {
+    let mut steps = Vec::new();
+    while !instrs.is_empty() {
+        let (step, len) = ChainStep::parse(instrs)?;
+        steps.push(step);
+        instrs = &instrs[len..];
+    }
+    Some(steps)
+}
+
+/// Run a function made of a chain of pervasive steps
+///
+/// Number arrays are processed in a single pass.
+/// Other values are passed to the function as normal.
+pub fn pervasive_chain(env: &mut Uiua) -> UiuaResult {
+    crate::profile_function!();
+    let f = env.pop(FunctionArg(1))?;
+    let x = env.pop(ArrayArg(1))?;
+    let steps = f.as_function().and_then(|f| chain_steps(&f.instrs));
+    match (steps, x) {
+        (Some(steps), Value::Num(arr)) => {
+            let threads = kernel_threads(arr.flat_len(), env);
+            let data = map_kernel(&arr.data, threads, |x| {
+                steps.iter().fold(x, |x, step| step.apply(x))
+            });
+            env.push(Array::new(arr.shape, data));
+        }
+        (_, x) => {
+            env.push(x);
+            run_unfused(Primitive::PervasiveChain, f, env)?;
+        }
+    }
+    Ok(())
+}
+
 pub fn bin_pervade<A, B, C, F>(a: &Array<A>, b: &Array<B>, env: &Uiua, f: F) -> UiuaResult<Array<C>>
 where
     A: ArrayValue,
@@ -274,6 +469,9 @@ pub mod neg {
     pub fn byte(a: u8) -> f64 {
         -f64::from(a)
     }
+    pub fn com(a: Complex) -> Complex {
+        -a
+    }
     pub fn error<T: Display>(a: T, env: &Uiua) -> UiuaError {
         env.error(format!("Cannot negate {a}"))
     }
@@ -286,6 +484,9 @@ pub mod abs {
     pub fn byte(a: u8) -> u8 {
         a
     }
+    pub fn com(a: Complex) -> f64 {
+        a.abs()
+    }
     pub fn error<T: Display>(a: T, env: &Uiua) -> UiuaError {
         env.error(format!("Cannot take the absolute value of {a}"))
     }
@@ -304,6 +505,9 @@ pub mod sign {
     pub fn byte(a: u8) -> u8 {
         (a > 0) as u8
     }
+    pub fn com(a: Complex) -> Complex {
+        a.normalize()
+    }
     pub fn error<T: Display>(a: T, env: &Uiua) -> UiuaError {
         env.error(format!("Cannot get the sign of {a}"))
     }
@@ -316,6 +520,9 @@ pub mod sqrt {
     pub fn byte(a: u8) -> f64 {
         f64::from(a).sqrt()
     }
+    pub fn com(a: Complex) -> Complex {
+        a.sqrt()
+    }
     pub fn error<T: Display>(a: T, env: &Uiua) -> UiuaError {
         env.error(format!("Cannot take the square root of {a}"))
     }
@@ -439,6 +646,9 @@ macro_rules! cmp_impl {
             pub fn generic<T: Ord>(a: T, b: T) -> u8 {
                 (b.cmp(&a) $eq $ordering).into()
             }
+            pub fn com_x(a: impl Into<Complex>, b: impl Into<Complex>) -> u8 {
+                (b.into().array_cmp(&a.into()) $eq $ordering) as u8
+            }
             pub fn error<T: Display>(a: T, b: T, _env: &Uiua) -> UiuaError {
                 unreachable!("Comparisons cannot fail, failed to compare {a} and {b}")
             }
@@ -480,6 +690,9 @@ pub mod add {
     pub fn char_byte(a: char, b: u8) -> char {
         char::from_u32((b as i64 + a as i64) as u32).unwrap_or('\0')
     }
+    pub fn com_x(a: impl Into<Complex>, b: impl Into<Complex>) -> Complex {
+        b.into() + a.into()
+    }
     pub fn error<T: Display>(a: T, b: T, env: &Uiua) -> UiuaError {
         env.error(format!("Cannot add {a} and {b}"))
     }
@@ -508,6 +721,9 @@ pub mod sub {
     pub fn byte_char(a: u8, b: char) -> char {
         char::from_u32(((b as i64) - (a as i64)) as u32).unwrap_or('\0')
     }
+    pub fn com_x(a: impl Into<Complex>, b: impl Into<Complex>) -> Complex {
+        b.into() - a.into()
+    }
     pub fn error<T: Display>(a: T, b: T, env: &Uiua) -> UiuaError {
         env.error(format!("Cannot subtract {a} from {b}"))
     }
@@ -527,6 +743,9 @@ pub mod mul {
     pub fn num_byte(a: f64, b: u8) -> f64 {
         f64::from(b) * a
     }
+    pub fn com_x(a: impl Into<Complex>, b: impl Into<Complex>) -> Complex {
+        b.into() * a.into()
+    }
     pub fn error<T: Display>(a: T, b: T, env: &Uiua) -> UiuaError {
         env.error(format!("Cannot multiply {a} and {b}"))
     }
@@ -546,6 +765,9 @@ pub mod div {
     pub fn num_byte(a: f64, b: u8) -> f64 {
         f64::from(b) / a
     }
+    pub fn com_x(a: impl Into<Complex>, b: impl Into<Complex>) -> Complex {
+        b.into() / a.into()
+    }
     pub fn error<T: Display>(a: T, b: T, env: &Uiua) -> UiuaError {
         env.error(format!("Cannot divide {a} by {b}"))
     }
@@ -595,6 +817,9 @@ pub mod pow {
     pub fn num_byte(a: f64, b: u8) -> f64 {
         f64::from(b).powf(a)
     }
+    pub fn com_x(a: impl Into<Complex>, b: impl Into<Complex>) -> Complex {
+        b.into().powc(a.into())
+    }
     pub fn error<T: Display>(a: T, b: T, env: &Uiua) -> UiuaError {
         env.error(format!("Cannot get the power of {a} to {b}"))
     }
@@ -614,11 +839,24 @@ pub mod log {
     pub fn num_byte(a: f64, b: u8) -> f64 {
         f64::from(b).log(a)
     }
+    pub fn com_x(a: impl Into<Complex>, b: impl Into<Complex>) -> Complex {
+        b.into().log(a.into())
+    }
     pub fn error<T: Display>(a: T, b: T, env: &Uiua) -> UiuaError {
         env.error(format!("Cannot get the log base {b} of {a}"))
     }
 }
 
+pub mod complex {
+    use super::*;
+    pub fn com_x(a: impl Into<Complex>, b: impl Into<Complex>) -> Complex {
+        b.into() + a.into() * Complex::I
+    }
+    pub fn error<T: Display>(a: T, b: T, env: &Uiua) -> UiuaError {
+        env.error(format!("Cannot make a complex number from {a} and {b}"))
+    }
+}
+
 pub mod max {
     use super::*;
     pub fn num_num(a: f64, b: f64) -> f64 {
@@ -636,6 +874,14 @@ pub mod max {
     pub fn byte_num(a: u8, b: f64) -> f64 {
         num_num(a.into(), b)
     }
+    pub fn com_x(a: impl Into<Complex>, b: impl Into<Complex>) -> Complex {
+        let (a, b) = (a.into(), b.into());
+        if a.array_cmp(&b) == Ordering::Greater {
+            a
+        } else {
+            b
+        }
+    }
     pub fn error<T: Display>(a: T, b: T, env: &Uiua) -> UiuaError {
         env.error(format!("Cannot get the max of {a} and {b}"))
     }
@@ -658,6 +904,14 @@ pub mod min {
     pub fn byte_num(a: u8, b: f64) -> f64 {
         num_num(a.into(), b)
     }
+    pub fn com_x(a: impl Into<Complex>, b: impl Into<Complex>) -> Complex {
+        let (a, b) = (a.into(), b.into());
+        if a.array_cmp(&b) == Ordering::Less {
+            a
+        } else {
+            b
+        }
+    }
     pub fn error<T: Display>(a: T, b: T, env: &Uiua) -> UiuaError {
         env.error(format!("Cannot get the min of {a} and {b}"))
     }
@@ -844,3 +1098,27 @@ fn bin_pervade_recursive_generic<A: PervasiveInput, B: PervasiveInput, C>(
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::algorithm::run_threaded;
+
+    #[test]
+    fn parallel_kernels_match_sequential() {
+        for code in [
+            "+1 ⇡100000",
+            "×⇡100000 ⇡100000",
+            "÷ ⇡100000 2",
+            "=0 ◿3 ⇡100000",
+            "<⇌.⇡100000",
+            "↥⇌.◿256 ⇡100000",
+            "- +@a ◿26 ⇡100000 @z",
+            "sin ⇡100000",
+            "¬ ◿2 ⇡100000",
+            "+ ↯100000 {1} 2",
+        ] {
+            let parallel = run_threaded(code, 4).unwrap();
+            assert_eq!(run_threaded(code, 1).unwrap(), parallel, "{code}");
+        }
+    }
+}
@@ -0,0 +1,112 @@
+//! Regular expression algorithms
+
+use std::{cell::RefCell, collections::HashMap, sync::Arc};
+
+use ::regex::Regex;
+use tinyvec::tiny_vec;
+
+use crate::{array::Array, function::Function, value::Value, Uiua, UiuaResult};
+
+/// The most patterns that will be cached per thread
+///
+/// When the cache is full, one pattern is evicted for each new one,
+/// so a loop over more patterns than this still mostly hits the cache.
+const CACHE_LIMIT: usize = 256;
+
+thread_local! {
+    static REGEX_CACHE: RefCell<HashMap<String, Regex>> = RefCell::new(HashMap::new());
+}
+
+/// Compile a pattern, or get it from the cache if it was already compiled on this thread
+fn with_regex<T>(pattern: &Value, env: &Uiua, f: impl FnOnce(&Regex) -> T) -> UiuaResult<T> {
+    let pattern = string_arg(pattern, env, "Pattern must be a string")?;
+    REGEX_CACHE.with(|cache| {
+        let mut cache = cache.borrow_mut();
+        if let Some(regex) = cache.get(&pattern) {
+            return Ok(f(regex));
+        }
+        let regex = Regex::new(&pattern).map_err(|e| env.error(format!("Invalid pattern: {e}")))?;
+        if cache.len() >= CACHE_LIMIT {
+            let evicted = cache.keys().next().cloned();
+            if let Some(evicted) = evicted {
+                cache.remove(&evicted);
+            }
+        }
+        Ok(f(cache.entry(pattern).or_insert(regex)))
+    })
+}
+
+/// Get a string argument, which may be boxed
+fn string_arg(value: &Value, env: &Uiua, requirement: &'static str) -> UiuaResult<String> {
+    match value.as_function().and_then(|f| f.as_boxed()) {
+        Some(value) => value.as_string(env, requirement),
+        None => value.as_string(env, requirement),
+    }
+}
+
+fn boxed_str(s: &str) -> Arc<Function> {
+    Arc::new(Function::constant(s.chars().collect::<Array<char>>()))
+}
+
+impl Value {
+    /// Find all matches of a pattern
+    ///
+    /// Each row is a match, with the whole match first followed by each capture group.
+    pub fn regex_match(&self, string: &Self, env: &Uiua) -> UiuaResult<Self> {
+        let string = string_arg(string, env, "Matched value must be a string")?;
+        with_regex(self, env, |regex| {
+            let width = regex.captures_len();
+            let mut cells = Vec::new();
+            for captures in regex.captures_iter(&string) {
+                for group in captures.iter() {
+                    cells.push(boxed_str(group.map_or("", |m| m.as_str())));
+                }
+            }
+            let height = cells.len() / width;
+            Array::new(tiny_vec![height, width], cells).into()
+        })
+    }
+    /// Split a string at every match of a pattern
+    pub fn regex_split(&self, string: &Self, env: &Uiua) -> UiuaResult<Self> {
+        let string = string_arg(string, env, "Split value must be a string")?;
+        with_regex(self, env, |regex| {
+            let parts: Vec<_> = regex.split(&string).map(boxed_str).collect();
+            Array::from(parts).into()
+        })
+    }
+    /// Replace every match of a pattern
+    pub fn regex_replace(&self, replacement: &Self, string: &Self, env: &Uiua) -> UiuaResult<Self> {
+        let replacement = string_arg(replacement, env, "Replacement must be a string")?;
+        let string = string_arg(string, env, "Replaced value must be a string")?;
+        with_regex(self, env, |regex| {
+            regex
+                .replace_all(&string, replacement.as_str())
+                .into_owned()
+                .into()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cache() {
+        let mut env = Uiua::with_native_sys().with_max_threads(1);
+        // A cached pattern is used instead of being compiled again
+        REGEX_CACHE.with(|cache| {
+            let mut cache = cache.borrow_mut();
+            cache.clear();
+            cache.insert("x".into(), Regex::new("y").unwrap());
+        });
+        env.load_str("≡(⧻regex \"x\") {\"xy\" \"yy\"}").unwrap();
+        assert_eq!(env.take_stack(), [Value::from_iter([1.0, 2.0])]);
+        // A full cache only evicts one pattern at a time
+        for i in 0..CACHE_LIMIT {
+            with_regex(&i.to_string().into(), &env, |_| ()).unwrap();
+        }
+        let len = REGEX_CACHE.with(|cache| cache.borrow().len());
+        assert_eq!(len, CACHE_LIMIT);
+    }
+}
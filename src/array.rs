@@ -8,6 +8,7 @@ use std::{
 use tinyvec::{tiny_vec, TinyVec};
 
 use crate::{
+    complex::Complex,
     cowslice::{cowslice, CowSlice},
     function::Function,
     grid_fmt::GridFmt,
@@ -162,6 +163,19 @@ impl<T: ArrayValue> Array<T> {
         let end = start + row_len;
         Self::new(&self.shape[1..], self.data.slice(start..end))
     }
+    /// Get a view of `size` consecutive rows, starting at row `start`
+    ///
+    /// The view shares its data with the array.
+    #[track_caller]
+    pub fn window(&self, start: usize, size: usize) -> Self {
+        let row_len = self.row_len();
+        let mut shape = self.shape.clone();
+        shape[0] = size;
+        Self::new(
+            shape,
+            self.data.slice(start * row_len..(start + size) * row_len),
+        )
+    }
     pub fn convert<U>(self) -> Array<U>
     where
         T: Into<U>,
@@ -407,6 +421,17 @@ impl ArrayValue for u8 {
     }
 }
 
+impl ArrayValue for Complex {
+    const NAME: &'static str = "complex";
+    fn get_fill(env: &Uiua) -> Option<Self> {
+        env.complex_fill()
+    }
+    fn array_hash<H: Hasher>(&self, hasher: &mut H) {
+        self.re.array_hash(hasher);
+        self.im.array_hash(hasher);
+    }
+}
+
 impl ArrayValue for char {
     const NAME: &'static str = "character";
     fn get_fill(env: &Uiua) -> Option<Self> {
@@ -471,6 +496,12 @@ impl ArrayCmp for u8 {
     }
 }
 
+impl ArrayCmp for Complex {
+    fn array_cmp(&self, other: &Self) -> Ordering {
+        (self.re.array_cmp(&other.re)).then_with(|| self.im.array_cmp(&other.im))
+    }
+}
+
 impl ArrayCmp for char {
     fn array_cmp(&self, other: &Self) -> Ordering {
         self.cmp(other)
@@ -11,6 +11,7 @@ use crate::{
     check::instrs_signature,
     function::*,
     lex::{CodeSpan, Sp, Span},
+    optimize,
     primitive::Primitive,
     run::RunMode,
     value::Value,
@@ -67,8 +68,14 @@ impl Uiua {
                     RunMode::All => true,
                 };
                 if can_run || words_have_import(&words) || words_are_export(&words) {
-                    let instrs = self.compile_words(words, true)?;
-                    self.exec_global_instrs(instrs)?;
+                    let uses = self.placeholder_uses;
+                    match self.compile_words(words, true) {
+                        Ok(instrs) if !self.compile_only => self.exec_global_instrs(instrs)?,
+                        Ok(_) => {}
+                        // Errors in code that uses placeholders may not happen when it is run
+                        Err(_) if self.placeholder_uses > uses => {}
+                        Err(e) => return Err(e),
+                    }
                 }
             }
             Item::Binding(binding) => {
@@ -91,12 +98,36 @@ impl Uiua {
         idx
     }
     fn binding(&mut self, binding: Binding) -> UiuaResult {
+        let name = binding.name.value.clone();
+        let uses = self.placeholder_uses;
+        let val = match self.binding_value(binding) {
+            Ok(val) => val,
+            // Errors in code that uses placeholders may not happen when it is run
+            Err(_) if self.placeholder_uses > uses => None,
+            Err(e) => return Err(e),
+        };
+        let mut globals = self.globals.lock();
+        let idx = globals.len();
+        if let Some(mut val) = val {
+            val.compress();
+            globals.push(val);
+        } else {
+            globals.push(Value::default());
+            self.placeholders.insert(idx);
+        }
+        self.scope.names.insert(name, idx);
+        Ok(())
+    }
+    /// Get the value of a binding
+    ///
+    /// Returns `None` if only compiling and the value is only known by running code.
+    fn binding_value(&mut self, binding: Binding) -> UiuaResult<Option<Value>> {
         let instrs = self.compile_words(binding.words, true)?;
         let make_fn = |instrs: Vec<Instr>, sig: Signature| {
             let func = Function::new(FunctionId::Named(binding.name.value.clone()), instrs, sig);
             Value::from(func)
         };
-        let mut val = match instrs_signature(&instrs) {
+        let val = match instrs_signature(&instrs) {
             Ok(mut sig) => {
                 if let Some(declared_sig) = &binding.signature {
                     if declared_sig.value == sig {
@@ -113,8 +144,18 @@ impl Uiua {
                 }
 
                 if sig.args == 0 {
-                    self.exec_global_instrs(instrs)?;
-                    if let Some(value) = self.stack.pop() {
+                    let value = if self.compile_only {
+                        // Without running the code, only a constant's value is known
+                        match instrs.as_slice() {
+                            [] => None,
+                            [Instr::Push(val)] => Some((**val).clone()),
+                            _ => return Ok(None),
+                        }
+                    } else {
+                        self.exec_global_instrs(instrs)?;
+                        self.stack.pop()
+                    };
+                    if let Some(value) = value {
                         match value {
                             Value::Func(fs) => match fs.into_scalar() {
                                 Ok(mut f) => {
@@ -149,12 +190,7 @@ impl Uiua {
                 }
             }
         };
-        val.compress();
-        let mut globals = self.globals.lock();
-        let idx = globals.len();
-        globals.push(val);
-        self.scope.names.insert(binding.name.value, idx);
-        Ok(())
+        Ok(Some(val))
     }
     fn compile_words(&mut self, words: Vec<Sp<Word>>, call: bool) -> UiuaResult<Vec<Instr>> {
         self.new_functions.push(Vec::new());
@@ -164,9 +200,17 @@ impl Uiua {
                 eprintln!("{}", diagnostic.show(true));
             }
         }
-        let instrs = self.new_functions.pop().unwrap();
+        let mut instrs = self.new_functions.pop().unwrap();
+        if self.optimize() {
+            instrs = optimize::optimize_instrs(instrs, self);
+        }
         Ok(instrs)
     }
+    /// Infer the signature of some words without running them
+    pub(crate) fn words_signature(&mut self, words: Vec<Sp<Word>>) -> Option<Signature> {
+        let instrs = self.compile_words(words, true).ok()?;
+        instrs_signature(&instrs).ok()
+    }
     fn compile_operand_words(
         &mut self,
         words: Vec<Sp<Word>>,
@@ -198,21 +242,12 @@ impl Uiua {
     /// Also performs some optimizations if the instruction and the previous
     /// instruction form some known pattern
     fn push_instr(&mut self, instr: Instr) {
-        use Primitive::*;
+        let optimize = self.optimize();
         let instrs = self.new_functions.last_mut().unwrap();
-        // Optimizations
-        match (instrs.as_mut_slice(), instr) {
-            // Cosine
-            ([.., Instr::Prim(Eta, _), Instr::Prim(Add, _)], Instr::Prim(Sin, span)) => {
-                instrs.pop();
-                instrs.pop();
-                instrs.push(Instr::Prim(Cos, span));
-            }
-            // First reverse = last
-            ([.., Instr::Prim(top @ Reverse, _)], Instr::Prim(First, _)) => *top = Last,
-            // // Coalesce inline stack ops
-            // ([.., Instr::])
-            (_, instr) => instrs.push(instr),
+        if optimize {
+            optimize::push_instr(instrs, instr);
+        } else {
+            instrs.push(instr);
         }
     }
     fn word(&mut self, word: Sp<Word>, call: bool) -> UiuaResult {
@@ -378,6 +413,9 @@ impl Uiua {
                 .get(&ident)
         }) {
             // Name exists in scope
+            if self.placeholders.contains(idx) {
+                self.placeholder_uses += 1;
+            }
             let value = self.globals.lock()[*idx].clone();
             let should_call = matches!(&value, Value::Func(f) if f.shape.is_empty());
             self.push_instr(Instr::push(value));
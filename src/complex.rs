@@ -0,0 +1,226 @@
+//! The complex number element type
+
+use std::{
+    f64::consts::E,
+    fmt,
+    ops::{Add, Div, Mul, Neg, Sub},
+};
+
+/// A complex number
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Complex {
+    /// The real part
+    pub re: f64,
+    /// The imaginary part
+    pub im: f64,
+}
+
+impl Complex {
+    pub const ZERO: Self = Self::new(0.0, 0.0);
+    pub const ONE: Self = Self::new(1.0, 0.0);
+    pub const I: Self = Self::new(0.0, 1.0);
+    pub const fn new(re: f64, im: f64) -> Self {
+        Self { re, im }
+    }
+    /// Make a complex number from a magnitude and an angle
+    pub fn from_polar(r: f64, theta: f64) -> Self {
+        Self::new(r * theta.cos(), r * theta.sin())
+    }
+    /// Get the magnitude
+    pub fn abs(self) -> f64 {
+        self.re.hypot(self.im)
+    }
+    /// Get the angle
+    pub fn arg(self) -> f64 {
+        self.im.atan2(self.re)
+    }
+    /// Get the number with the same angle and a magnitude of 1, or 0 if this is 0
+    pub fn normalize(self) -> Self {
+        let abs = self.abs();
+        if abs == 0.0 {
+            Self::ZERO
+        } else {
+            Self::new(self.re / abs, self.im / abs)
+        }
+    }
+    /// Get the principal square root
+    pub fn sqrt(self) -> Self {
+        let abs = self.abs();
+        let re = ((abs + self.re) / 2.0).sqrt();
+        let im = ((abs - self.re) / 2.0).sqrt().copysign(self.im);
+        Self::new(re, im)
+    }
+    /// Get the principal natural logarithm
+    pub fn ln(self) -> Self {
+        Self::new(self.abs().ln(), self.arg())
+    }
+    /// Get the logarithm with some base
+    pub fn log(self, base: Self) -> Self {
+        self.ln() / base.ln()
+    }
+    /// Raise `e` to this power
+    pub fn exp(self) -> Self {
+        Self::from_polar(E.powf(self.re), self.im)
+    }
+    /// Raise this number to some power
+    pub fn powc(self, power: Self) -> Self {
+        if power == Self::ZERO {
+            Self::ONE
+        } else if self == Self::ZERO && power.re > 0.0 {
+            Self::ZERO
+        } else if self == Self::ZERO && power.re < 0.0 {
+            // Like real powers, zero to a negative power is infinite
+            Self::new(f64::INFINITY, 0.0)
+        } else if power.im == 0.0 && power.re.fract() == 0.0 && power.re.abs() <= 64.0 {
+            // Repeated multiplication is exact for small integer powers
+            let mut n = power.re.abs() as u32;
+            let (mut base, mut acc) = (self, Self::ONE);
+            while n > 0 {
+                if n & 1 == 1 {
+                    acc = acc * base;
+                }
+                base = base * base;
+                n >>= 1;
+            }
+            if power.re < 0.0 {
+                Self::ONE / acc
+            } else {
+                acc
+            }
+        } else {
+            (power * self.ln()).exp()
+        }
+    }
+}
+
+impl From<f64> for Complex {
+    fn from(re: f64) -> Self {
+        Self::new(re, 0.0)
+    }
+}
+
+impl From<u8> for Complex {
+    fn from(re: u8) -> Self {
+        Self::new(re.into(), 0.0)
+    }
+}
+
+impl Add for Complex {
+    type Output = Self;
+    fn add(self, other: Self) -> Self {
+        Self::new(self.re + other.re, self.im + other.im)
+    }
+}
+
+impl Sub for Complex {
+    type Output = Self;
+    fn sub(self, other: Self) -> Self {
+        Self::new(self.re - other.re, self.im - other.im)
+    }
+}
+
+impl Mul for Complex {
+    type Output = Self;
+    fn mul(self, other: Self) -> Self {
+        Self::new(
+            self.re * other.re - self.im * other.im,
+            self.re * other.im + self.im * other.re,
+        )
+    }
+}
+
+impl Div for Complex {
+    type Output = Self;
+    fn div(self, other: Self) -> Self {
+        let denom = other.re * other.re + other.im * other.im;
+        Self::new(
+            (self.re * other.re + self.im * other.im) / denom,
+            (self.im * other.re - self.re * other.im) / denom,
+        )
+    }
+}
+
+impl Neg for Complex {
+    type Output = Self;
+    fn neg(self) -> Self {
+        Self::new(-self.re, -self.im)
+    }
+}
+
+impl fmt::Display for Complex {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.im.is_sign_negative() {
+            write!(f, "{}-{}i", self.re, -self.im)
+        } else {
+            write!(f, "{}+{}i", self.re, self.im)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::{LN_2, PI};
+
+    use super::*;
+
+    fn close(a: Complex, b: Complex) -> bool {
+        (a - b).abs() < 1e-12
+    }
+
+    #[test]
+    fn zero_powers() {
+        let zero = Complex::ZERO;
+        assert_eq!(zero.powc(Complex::ZERO), Complex::ONE);
+        assert_eq!(zero.powc(Complex::new(2.0, 0.0)), Complex::ZERO);
+        assert_eq!(zero.powc(Complex::new(0.5, 3.0)), Complex::ZERO);
+        assert_eq!(zero.powc(Complex::new(-1.0, 0.0)).re, f64::INFINITY);
+        assert_eq!(zero.powc(Complex::new(-0.5, 3.0)).re, f64::INFINITY);
+        let undefined = zero.powc(Complex::I);
+        assert!(undefined.re.is_nan() && undefined.im.is_nan());
+    }
+
+    #[test]
+    fn branch_cuts() {
+        // The principal logarithm's imaginary part is in (-π, π]
+        let minus_one = Complex::new(-1.0, 0.0);
+        assert!(close(minus_one.ln(), Complex::new(0.0, PI)));
+        assert!(close(Complex::new(-1.0, -0.0).ln(), Complex::new(0.0, -PI)));
+        assert!(close(
+            Complex::new(-1.0, 1e-300).ln(),
+            Complex::new(0.0, PI)
+        ));
+        assert!(close(
+            Complex::new(-1.0, -1e-300).ln(),
+            Complex::new(0.0, -PI)
+        ));
+        assert!(close(Complex::I.ln(), Complex::new(0.0, PI / 2.0)));
+        assert!(close(
+            Complex::new(-8.0, 0.0).log(2.0.into()),
+            Complex::new(3.0, PI / LN_2)
+        ));
+        // Non-integer powers use the principal logarithm
+        assert!(close(minus_one.powc(0.5.into()), Complex::I));
+        assert!(close(
+            Complex::new(-1.0, -0.0).powc(0.5.into()),
+            -Complex::I
+        ));
+        assert!(close(
+            Complex::new(-4.0, 0.0).sqrt(),
+            Complex::new(0.0, 2.0)
+        ));
+        assert!(close(
+            Complex::new(-4.0, -0.0).sqrt(),
+            Complex::new(0.0, -2.0)
+        ));
+        assert!(close(
+            Complex::I.powc(Complex::I),
+            Complex::new((-PI / 2.0).exp(), 0.0)
+        ));
+        // Integer powers are exact
+        assert_eq!(Complex::I.powc(2.0.into()), minus_one);
+        assert_eq!(
+            Complex::new(1.0, 1.0).powc((-2.0).into()),
+            Complex::new(0.0, -0.5)
+        );
+    }
+}
@@ -0,0 +1,299 @@
+//! Compression and zip archives
+//!
+//! Zip archives are read and written without ZIP64 extensions,
+//! so entries and archives are limited to 4GB.
+//! Only stored and deflated entries can be read.
+//!
+//! Decompressed data is limited to [`MAX_DECOMPRESSED`] bytes,
+//! so that small malicious inputs cannot use up all memory.
+
+use std::io::{Read, Write};
+
+use flate2::{
+    read::{DeflateDecoder, GzDecoder, ZlibDecoder},
+    write::{DeflateEncoder, GzEncoder, ZlibEncoder},
+    Compression,
+};
+
+/// The largest number of bytes that data may decompress to
+pub(crate) const MAX_DECOMPRESSED: usize = 1 << 30;
+
+/// Read all of a decoder's output, failing if it is longer than `limit` bytes
+fn read_limited(decoder: impl Read, limit: usize) -> std::io::Result<Result<Vec<u8>, String>> {
+    let mut bytes = Vec::new();
+    decoder.take(limit as u64 + 1).read_to_end(&mut bytes)?;
+    Ok(if bytes.len() > limit {
+        Err(format!(
+            "Decompressed data is larger than the limit of {limit} bytes"
+        ))
+    } else {
+        Ok(bytes)
+    })
+}
+
+/// Compress bytes with gzip
+pub(crate) fn gzip_compress(bytes: &[u8]) -> Result<Vec<u8>, String> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(bytes).map_err(|e| e.to_string())?;
+    encoder.finish().map_err(|e| e.to_string())
+}
+
+/// Decompress gzipped bytes
+pub(crate) fn gzip_decompress(bytes: &[u8]) -> Result<Vec<u8>, String> {
+    read_limited(GzDecoder::new(bytes), MAX_DECOMPRESSED)
+        .map_err(|e| format!("Invalid gzip data: {e}"))?
+}
+
+/// Compress bytes with zlib
+pub(crate) fn zlib_compress(bytes: &[u8]) -> Result<Vec<u8>, String> {
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(bytes).map_err(|e| e.to_string())?;
+    encoder.finish().map_err(|e| e.to_string())
+}
+
+/// Decompress zlib-compressed bytes
+pub(crate) fn zlib_decompress(bytes: &[u8]) -> Result<Vec<u8>, String> {
+    read_limited(ZlibDecoder::new(bytes), MAX_DECOMPRESSED)
+        .map_err(|e| format!("Invalid zlib data: {e}"))?
+}
+
+/// Compress bytes with raw deflate, without a header or checksum
+pub(crate) fn deflate_compress(bytes: &[u8]) -> Result<Vec<u8>, String> {
+    let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(bytes).map_err(|e| e.to_string())?;
+    encoder.finish().map_err(|e| e.to_string())
+}
+
+/// Decompress raw deflated bytes
+pub(crate) fn deflate_decompress(bytes: &[u8]) -> Result<Vec<u8>, String> {
+    read_limited(DeflateDecoder::new(bytes), MAX_DECOMPRESSED)
+        .map_err(|e| format!("Invalid deflate data: {e}"))?
+}
+
+const LOCAL_HEADER: u32 = 0x04034b50;
+const CENTRAL_HEADER: u32 = 0x02014b50;
+const END_OF_CENTRAL_DIRECTORY: u32 = 0x06054b50;
+/// The size of the end of central directory record, not including its comment
+const END_SIZE: usize = 22;
+/// The general purpose flag that marks names as UTF-8
+const UTF8_FLAG: u16 = 1 << 11;
+const STORED: u16 = 0;
+const DEFLATED: u16 = 8;
+/// The earliest date a zip entry can have, 1980-01-01
+const MIN_DOS_DATE: u16 = (1 << 5) | 1;
+
+/// An entry in a zip archive's central directory
+struct ZipEntry {
+    name: String,
+    method: u16,
+    crc: u32,
+    compressed_size: usize,
+    size: usize,
+    header_offset: usize,
+}
+
+/// List the names of the entries in a zip archive
+pub(crate) fn zip_list(archive: &[u8]) -> Result<Vec<String>, String> {
+    Ok(zip_entries(archive)?.into_iter().map(|e| e.name).collect())
+}
+
+/// Read an entry from a zip archive
+pub(crate) fn zip_read(archive: &[u8], name: &str) -> Result<Vec<u8>, String> {
+    let entry = zip_entries(archive)?
+        .into_iter()
+        .find(|e| e.name == name)
+        .ok_or_else(|| format!("Zip archive has no entry named {name:?}"))?;
+    let header = archive
+        .get(entry.header_offset..)
+        .filter(|header| header.len() >= 30 && u32_at(header, 0) == LOCAL_HEADER)
+        .ok_or_else(|| format!("Zip entry {name:?} has an invalid header"))?;
+    let data_start = 30 + u16_at(header, 26) as usize + u16_at(header, 28) as usize;
+    let data = header
+        .get(data_start..)
+        .and_then(|data| data.get(..entry.compressed_size))
+        .ok_or_else(|| format!("Zip entry {name:?} is truncated"))?;
+    let bytes = match entry.method {
+        STORED => data.to_vec(),
+        // Reading one more byte than the entry's size is enough to know if it is corrupted
+        DEFLATED => read_limited(DeflateDecoder::new(data), entry.size.min(MAX_DECOMPRESSED))
+            .map_err(|e| format!("Zip entry {name:?} has invalid data: {e}"))?
+            .map_err(|_| format!("Zip entry {name:?} is corrupted or too large"))?,
+        method => {
+            return Err(format!(
+                "Zip entry {name:?} uses compression method {method}, \
+                which is not supported"
+            ))
+        }
+    };
+    if bytes.len() != entry.size || crc32fast::hash(&bytes) != entry.crc {
+        return Err(format!("Zip entry {name:?} is corrupted"));
+    }
+    Ok(bytes)
+}
+
+/// Write a zip archive from name-content pairs
+///
+/// Entries are deflated unless that would make them larger.
+pub(crate) fn zip_write<'a>(
+    entries: impl IntoIterator<Item = (&'a str, &'a [u8])>,
+) -> Result<Vec<u8>, String> {
+    let mut archive = Vec::new();
+    let mut central = Vec::new();
+    let mut count = 0usize;
+    for (name, bytes) in entries {
+        let deflated = deflate_compress(bytes)?;
+        let (method, data) = if deflated.len() < bytes.len() {
+            (DEFLATED, deflated.as_slice())
+        } else {
+            (STORED, bytes)
+        };
+        let name_len = u16::try_from(name.len())
+            .map_err(|_| format!("Zip entry name {name:?} is too long"))?;
+        let too_large = || format!("Zip entry {name:?} is too large");
+        let size = u32::try_from(bytes.len()).map_err(|_| too_large())?;
+        let compressed_size = u32::try_from(data.len()).map_err(|_| too_large())?;
+        let offset = u32::try_from(archive.len()).map_err(|_| too_large())?;
+        let crc = crc32fast::hash(bytes);
+        // The fields shared by the local and central headers
+        let mut common = Vec::with_capacity(26);
+        common.extend(20u16.to_le_bytes());
+        common.extend(UTF8_FLAG.to_le_bytes());
+        common.extend(method.to_le_bytes());
+        common.extend(0u16.to_le_bytes());
+        common.extend(MIN_DOS_DATE.to_le_bytes());
+        common.extend(crc.to_le_bytes());
+        common.extend(compressed_size.to_le_bytes());
+        common.extend(size.to_le_bytes());
+        common.extend(name_len.to_le_bytes());
+        common.extend(0u16.to_le_bytes());
+
+        archive.extend(LOCAL_HEADER.to_le_bytes());
+        archive.extend(&common);
+        archive.extend(name.as_bytes());
+        archive.extend(data);
+
+        central.extend(CENTRAL_HEADER.to_le_bytes());
+        central.extend(20u16.to_le_bytes());
+        central.extend(&common);
+        central.extend([0; 6]);
+        central.extend(0u32.to_le_bytes());
+        central.extend(offset.to_le_bytes());
+        central.extend(name.as_bytes());
+        count += 1;
+    }
+    let count = u16::try_from(count).map_err(|_| "Zip archive has too many entries")?;
+    let central_size = u32::try_from(central.len()).map_err(|_| "Zip archive is too large")?;
+    let central_offset = u32::try_from(archive.len()).map_err(|_| "Zip archive is too large")?;
+    archive.extend(central);
+    archive.extend(END_OF_CENTRAL_DIRECTORY.to_le_bytes());
+    archive.extend([0; 4]);
+    archive.extend(count.to_le_bytes());
+    archive.extend(count.to_le_bytes());
+    archive.extend(central_size.to_le_bytes());
+    archive.extend(central_offset.to_le_bytes());
+    archive.extend(0u16.to_le_bytes());
+    Ok(archive)
+}
+
+fn zip_entries(archive: &[u8]) -> Result<Vec<ZipEntry>, String> {
+    let invalid = || "Invalid zip archive".to_string();
+    // The end of central directory record is followed by a comment of up to 65535 bytes
+    let end_start = (archive.len().saturating_sub(END_SIZE + u16::MAX as usize)
+        ..=archive.len().checked_sub(END_SIZE).ok_or_else(invalid)?)
+        .rev()
+        .find(|&i| u32_at(archive, i) == END_OF_CENTRAL_DIRECTORY)
+        .ok_or_else(invalid)?;
+    let end = &archive[end_start..];
+    let count = u16_at(end, 10) as usize;
+    let central_offset = u32_at(end, 16) as usize;
+    let mut central = archive.get(central_offset..).ok_or_else(invalid)?;
+    let mut entries = Vec::with_capacity(count);
+    for _ in 0..count {
+        if central.len() < 46 || u32_at(central, 0) != CENTRAL_HEADER {
+            return Err(invalid());
+        }
+        let name_len = u16_at(central, 28) as usize;
+        let extra_len = u16_at(central, 30) as usize;
+        let comment_len = u16_at(central, 32) as usize;
+        let name = central.get(46..46 + name_len).ok_or_else(invalid)?;
+        entries.push(ZipEntry {
+            name: String::from_utf8_lossy(name).into_owned(),
+            method: u16_at(central, 10),
+            crc: u32_at(central, 16),
+            compressed_size: u32_at(central, 20) as usize,
+            size: u32_at(central, 24) as usize,
+            header_offset: u32_at(central, 42) as usize,
+        });
+        central = central
+            .get(46 + name_len + extra_len + comment_len..)
+            .ok_or_else(invalid)?;
+    }
+    Ok(entries)
+}
+
+fn u16_at(bytes: &[u8], i: usize) -> u16 {
+    u16::from_le_bytes([bytes[i], bytes[i + 1]])
+}
+
+fn u32_at(bytes: &[u8], i: usize) -> u32 {
+    u32::from_le_bytes([bytes[i], bytes[i + 1], bytes[i + 2], bytes[i + 3]])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn streams_round_trip() {
+        let text = "Uiua ".repeat(100);
+        let gzipped = gzip_compress(text.as_bytes()).unwrap();
+        assert!(gzipped.len() < text.len());
+        assert_eq!(gzip_decompress(&gzipped).unwrap(), text.as_bytes());
+        let zlibbed = zlib_compress(text.as_bytes()).unwrap();
+        assert_eq!(zlib_decompress(&zlibbed).unwrap(), text.as_bytes());
+        assert!(gzip_decompress(&zlibbed).is_err());
+        let deflated = deflate_compress(text.as_bytes()).unwrap();
+        assert_eq!(deflate_decompress(&deflated).unwrap(), text.as_bytes());
+    }
+
+    #[test]
+    fn decompression_limits() {
+        // A zip bomb of sorts
+        let zeros = vec![0; 10_000];
+        let deflated = deflate_compress(&zeros).unwrap();
+        assert!(read_limited(DeflateDecoder::new(&*deflated), 9_999)
+            .unwrap()
+            .is_err());
+        assert_eq!(
+            read_limited(DeflateDecoder::new(&*deflated), 10_000).unwrap(),
+            Ok(zeros.clone())
+        );
+
+        // A zip entry whose header claims a smaller size than its data
+        let mut archive = zip_write([("zeros", &zeros[..])]).unwrap();
+        let central = archive.len() - END_SIZE - 46 - "zeros".len();
+        archive[central + 24..][..4].copy_from_slice(&10u32.to_le_bytes());
+        assert!(zip_read(&archive, "zeros").is_err());
+    }
+
+    #[test]
+    fn zip_round_trip() {
+        let long = "repeat ".repeat(50);
+        let archive = zip_write([
+            ("a.txt", "hello".as_bytes()),
+            ("dir/long.txt", long.as_bytes()),
+            ("empty", &[][..]),
+        ])
+        .unwrap();
+        assert_eq!(
+            zip_list(&archive).unwrap(),
+            ["a.txt", "dir/long.txt", "empty"]
+        );
+        assert_eq!(zip_read(&archive, "a.txt").unwrap(), b"hello");
+        assert_eq!(zip_read(&archive, "dir/long.txt").unwrap(), long.as_bytes());
+        assert_eq!(zip_read(&archive, "empty").unwrap(), b"");
+        assert!(zip_read(&archive, "missing").is_err());
+        assert!(zip_list(&archive[1..]).is_err());
+        assert!(zip_list(b"short").is_err());
+    }
+}
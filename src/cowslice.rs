@@ -255,10 +255,14 @@ impl<T: Clone> IntoIterator for CowSlice<T> {
     type IntoIter = Take<Skip<<EcoVec<T> as IntoIterator>::IntoIter>>;
     #[allow(clippy::unnecessary_to_owned)]
     fn into_iter(self) -> Self::IntoIter {
-        self.data
-            .into_iter()
-            .skip(self.start as usize)
-            .take((self.end - self.start) as usize)
+        let len = (self.end - self.start) as usize;
+        // Skipping to the start of a small view of a large vector is slow
+        let (data, start) = if len < self.data.len() / 2 {
+            (EcoVec::from(&self[..]), 0)
+        } else {
+            (self.data, self.start as usize)
+        };
+        data.into_iter().skip(start).take(len)
     }
 }
 
@@ -0,0 +1,248 @@
+//! Conversion between values and CSV
+//!
+//! Decoded CSV is a rank 2 array of boxed cells.
+//! Rows that are shorter than the longest row are padded with empty cells.
+
+use std::sync::Arc;
+
+use tinyvec::tiny_vec;
+
+use crate::{array::Array, function::Function, value::Value};
+
+/// Decode a CSV string into a rank 2 array
+///
+/// If `parse_numbers` is set, numeric columns are parsed into numbers.
+/// A column is numeric if all of its cells, other than one in the first row, are numbers or empty.
+/// Empty cells in numeric columns become `NaN`.
+/// If every column is entirely numeric, the result is a plain number array.
+pub(crate) fn csv_to_value(csv: &str, parse_numbers: bool) -> Result<Value, String> {
+    let mut rows = parse(csv)?;
+    let width = rows.iter().map(Vec::len).max().unwrap_or(0);
+    for row in &mut rows {
+        row.resize(width, String::new());
+    }
+    let height = rows.len();
+    if !parse_numbers {
+        let cells: Vec<_> = rows.into_iter().flatten().map(boxed_string).collect();
+        return Ok(Array::new(tiny_vec![height, width], cells).into());
+    }
+    let numeric_columns: Vec<bool> = (0..width)
+        .map(|col| {
+            rows.iter()
+                .skip(1)
+                .all(|row| parse_number(&row[col]).is_some())
+        })
+        .collect();
+    let all_numeric = rows
+        .iter()
+        .flatten()
+        .all(|cell| parse_number(cell).is_some());
+    if all_numeric {
+        let nums: Vec<f64> = rows
+            .iter()
+            .flatten()
+            .map(|cell| parse_number(cell).unwrap())
+            .collect();
+        return Ok(Array::new(tiny_vec![height, width], nums).into());
+    }
+    let mut cells = Vec::with_capacity(height * width);
+    for row in rows {
+        for (cell, numeric) in row.into_iter().zip(&numeric_columns) {
+            cells.push(match parse_number(&cell) {
+                Some(n) if *numeric => Arc::new(Function::constant(n)),
+                _ => boxed_string(cell),
+            });
+        }
+    }
+    Ok(Array::new(tiny_vec![height, width], cells).into())
+}
+
+/// Encode a rank 1 or 2 array as a CSV string
+///
+/// Cells may be numbers, strings, or boxed numbers or strings.
+/// Each row of a character array is a single cell, so a string is a single cell
+/// and a rank 2 character array is a column.
+pub(crate) fn value_to_csv(value: &Value) -> Result<String, String> {
+    let (height, width) = match *value.shape() {
+        [width] => (1, width),
+        [height, width] => (height, width),
+        _ => {
+            return Err(format!(
+                "Only rank 1 and 2 arrays can be encoded as CSV, but the array is rank {}",
+                value.rank()
+            ))
+        }
+    };
+    let (cells, width): (Vec<String>, usize) = match value {
+        Value::Num(arr) => (arr.data.iter().map(|&n| number_cell(n)).collect(), width),
+        Value::Byte(arr) => (arr.data.iter().map(|b| b.to_string()).collect(), width),
+        Value::Complex(_) => return Err("Cannot encode complex numbers as CSV".into()),
+        Value::Char(_) if width == 0 => (vec![String::new(); height], 1),
+        Value::Char(arr) => (
+            (arr.data.chunks_exact(width))
+                .map(|row| row.iter().collect())
+                .collect(),
+            1,
+        ),
+        Value::Func(arr) => (
+            (arr.data.iter())
+                .map(|f| match f.as_boxed() {
+                    Some(value) => boxed_cell(value),
+                    None => Err(format!("Cannot encode function {} as CSV", f.id)),
+                })
+                .collect::<Result<_, _>>()?,
+            width,
+        ),
+    };
+    Ok(encode(&cells, height, width))
+}
+
+fn encode(cells: &[String], height: usize, width: usize) -> String {
+    let mut csv = String::new();
+    for i in 0..height {
+        for (j, cell) in cells[i * width..][..width].iter().enumerate() {
+            if j > 0 {
+                csv.push(',');
+            }
+            if cell.contains([',', '"', '\n', '\r']) {
+                csv.push('"');
+                csv.push_str(&cell.replace('"', "\"\""));
+                csv.push('"');
+            } else {
+                csv.push_str(cell);
+            }
+        }
+        csv.push('\n');
+    }
+    csv
+}
+
+fn boxed_string(s: String) -> Arc<Function> {
+    Arc::new(Function::constant(s.chars().collect::<Array<char>>()))
+}
+
+fn boxed_cell(value: &Value) -> Result<String, String> {
+    match value {
+        Value::Char(arr) if arr.rank() <= 1 => Ok(arr.data.iter().collect()),
+        Value::Num(arr) if arr.rank() == 0 => Ok(number_cell(arr.data[0])),
+        Value::Byte(arr) if arr.rank() == 0 => Ok(arr.data[0].to_string()),
+        value => Err(format!(
+            "CSV cells must be strings or scalar numbers, but a cell is a rank {} {} array",
+            value.rank(),
+            value.type_name()
+        )),
+    }
+}
+
+fn number_cell(n: f64) -> String {
+    if n.is_nan() {
+        String::new()
+    } else {
+        n.to_string()
+    }
+}
+
+/// Parse a cell as a number, with empty cells being `NaN`
+fn parse_number(cell: &str) -> Option<f64> {
+    let cell = cell.trim();
+    if cell.is_empty() {
+        return Some(f64::NAN);
+    }
+    // Rust's parser accepts things like "inf" and "NaN", which are more likely to be words
+    if !cell
+        .chars()
+        .all(|c| c.is_ascii_digit() || "+-.eE".contains(c))
+    {
+        return None;
+    }
+    cell.parse().ok()
+}
+
+fn parse(csv: &str) -> Result<Vec<Vec<String>>, String> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut cell = String::new();
+    let mut chars = csv.chars().peekable();
+    let mut line = 1;
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if cell.is_empty() => loop {
+                match chars.next() {
+                    Some('"') if chars.peek() == Some(&'"') => {
+                        chars.next();
+                        cell.push('"');
+                    }
+                    Some('"') => break,
+                    Some(c) => {
+                        if c == '\n' {
+                            line += 1;
+                        }
+                        cell.push(c);
+                    }
+                    None => return Err(format!("Unterminated quoted cell on line {line}")),
+                }
+            },
+            ',' => row.push(std::mem::take(&mut cell)),
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\n' => {
+                row.push(std::mem::take(&mut cell));
+                rows.push(std::mem::take(&mut row));
+                line += 1;
+            }
+            c => cell.push(c),
+        }
+    }
+    if !cell.is_empty() || !row.is_empty() {
+        row.push(cell);
+        rows.push(row);
+    }
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        for csv in [
+            "a,b,c\n1,2,3\n",
+            "name,quote\nUiua,\"says \"\"hi\"\"\"\nAPL,\"one, two\"\n",
+            "\"multi\nline\",x\n",
+        ] {
+            let value = csv_to_value(csv, false).unwrap();
+            assert_eq!(value_to_csv(&value).unwrap(), csv);
+        }
+    }
+
+    #[test]
+    fn numbers() {
+        let value = csv_to_value("1,2\r\n3,4.5", true).unwrap();
+        assert_eq!(
+            value,
+            Value::from(Array::new(tiny_vec![2, 2], vec![1.0, 2.0, 3.0, 4.5]))
+        );
+        let value = csv_to_value("name,age\nAnn,30\nBob,\n", true).unwrap();
+        let cells = value.as_func_array().unwrap();
+        assert_eq!(cells.shape(), [3, 2]);
+        assert_eq!(cells.data[3].as_boxed(), Some(&Value::from(30.0)));
+        assert!(matches!(cells.data[4].as_boxed(), Some(Value::Char(_))));
+        assert_eq!(value_to_csv(&value).unwrap(), "name,age\nAnn,30\nBob,\n");
+    }
+
+    #[test]
+    fn ragged_and_errors() {
+        let value = csv_to_value("a,b,c\nd\n", false).unwrap();
+        assert_eq!(value.shape(), [2, 3]);
+        assert!(csv_to_value("\"open", false).is_err());
+        assert!(value_to_csv(&Value::from(Array::new(tiny_vec![1, 1, 1], vec![1.0]))).is_err());
+    }
+
+    #[test]
+    fn char_rows() {
+        let string: Value = "a,b".chars().collect::<Array<char>>().into();
+        assert_eq!(value_to_csv(&string).unwrap(), "\"a,b\"\n");
+        let rows = Array::new(tiny_vec![2, 3], "abcdef".chars().collect::<Vec<_>>());
+        assert_eq!(value_to_csv(&rows.into()).unwrap(), "abc\ndef\n");
+    }
+}
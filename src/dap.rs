@@ -0,0 +1,619 @@
+//! A Debug Adapter Protocol server
+//!
+//! The server runs a single program per session on its own thread.
+//! Requests that inspect or resume the program are queued until the program is paused.
+
+use std::{
+    collections::HashMap,
+    fs,
+    io::{self, BufRead, BufReader, Write},
+    net::TcpListener,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{channel, Receiver, Sender},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use parking_lot::Mutex;
+use serde_json::{json, Value as Json};
+
+use crate::{
+    debug::{line_span, Breakpoints, DebugAction, DebugHandler, DebugState, PauseReason},
+    lex::Span,
+    run::RunMode,
+    value::Value,
+    Uiua,
+};
+
+/// The default port the server listens on
+pub const DEFAULT_PORT: u16 = 4711;
+
+/// How long to wait for a stopped program to finish before abandoning it
+const STOP_GRACE: Duration = Duration::from_secs(1);
+
+/// Run the server, handling one client session at a time
+pub fn run_server(port: u16) -> io::Result<()> {
+    let listener = TcpListener::bind(("127.0.0.1", port))?;
+    eprintln!("Uiua debug adapter listening on port {port}");
+    for stream in listener.incoming() {
+        let stream = stream?;
+        serve(BufReader::new(stream.try_clone()?), stream)?;
+    }
+    Ok(())
+}
+
+/// Serve a single debug session over a reader and writer
+///
+/// Returns when the client disconnects
+pub fn serve(mut reader: impl BufRead, writer: impl Write + Send + 'static) -> io::Result<()> {
+    let mut session = Session {
+        out: Output::new(writer),
+        launch: None,
+        breakpoints: None,
+        pending_breakpoints: HashMap::new(),
+        paused: Arc::new(Mutex::new(None)),
+        program: None,
+        abort: None,
+    };
+    while let Some(request) = read_message(&mut reader)? {
+        if !session.handle(request) {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// Read a message with a `Content-Length` header
+fn read_message(reader: &mut impl BufRead) -> io::Result<Option<Json>> {
+    let mut length = None;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        let line = line.trim();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("Content-Length") {
+                length = value.trim().parse().ok();
+            }
+        }
+    }
+    let Some(length) = length else {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "Missing Content-Length header",
+        ));
+    };
+    let mut buffer = vec![0; length];
+    reader.read_exact(&mut buffer)?;
+    serde_json::from_slice(&buffer)
+        .map(Some)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+/// The sending half of the protocol
+struct Output {
+    writer: Mutex<(Box<dyn Write + Send>, u64)>,
+}
+
+impl Output {
+    fn new(writer: impl Write + Send + 'static) -> Arc<Self> {
+        Arc::new(Output {
+            writer: Mutex::new((Box::new(writer), 1)),
+        })
+    }
+    fn send(&self, mut message: Json) {
+        let mut writer = self.writer.lock();
+        let (writer, seq) = &mut *writer;
+        message["seq"] = json!(*seq);
+        *seq += 1;
+        let text = message.to_string();
+        _ = write!(writer, "Content-Length: {}\r\n\r\n{text}", text.len());
+        _ = writer.flush();
+    }
+    fn respond(&self, request: &Json, body: Json) {
+        self.send(json!({
+            "type": "response",
+            "request_seq": request["seq"],
+            "command": request["command"],
+            "success": true,
+            "body": body,
+        }));
+    }
+    fn respond_error(&self, request: &Json, message: impl Into<String>) {
+        self.send(json!({
+            "type": "response",
+            "request_seq": request["seq"],
+            "command": request["command"],
+            "success": false,
+            "message": message.into(),
+        }));
+    }
+    fn event(&self, event: &str, body: Json) {
+        self.send(json!({
+            "type": "event",
+            "event": event,
+            "body": body,
+        }));
+    }
+    fn output(&self, category: &str, output: impl Into<String>) {
+        self.event(
+            "output",
+            json!({ "category": category, "output": output.into() }),
+        );
+    }
+}
+
+struct Launch {
+    program: PathBuf,
+    args: Vec<String>,
+    stop_on_entry: bool,
+}
+
+struct Session {
+    out: Arc<Output>,
+    launch: Option<Launch>,
+    /// The breakpoints of the running program
+    breakpoints: Option<Breakpoints>,
+    /// Breakpoints set before the program starts, by source path
+    pending_breakpoints: HashMap<PathBuf, Vec<usize>>,
+    /// A channel to the program thread, present while the program is running
+    paused: Arc<Mutex<Option<Sender<Json>>>>,
+    program: Option<JoinHandle<()>>,
+    /// The flag that stops the running program
+    abort: Option<Arc<AtomicBool>>,
+}
+
+impl Session {
+    /// Handle a request, returning whether the session should continue
+    fn handle(&mut self, request: Json) -> bool {
+        let out = &self.out;
+        match request["command"].as_str().unwrap_or("") {
+            "initialize" => {
+                out.respond(
+                    &request,
+                    json!({
+                        "supportsConfigurationDoneRequest": true,
+                        "supportsTerminateRequest": true,
+                    }),
+                );
+                out.event("initialized", json!({}));
+            }
+            "launch" => {
+                let arguments = &request["arguments"];
+                let Some(program) = arguments["program"].as_str() else {
+                    out.respond_error(&request, "No program specified");
+                    return true;
+                };
+                let args = (arguments["args"].as_array().into_iter().flatten())
+                    .filter_map(|arg| arg.as_str().map(Into::into))
+                    .collect();
+                self.launch = Some(Launch {
+                    program: program.into(),
+                    args,
+                    stop_on_entry: arguments["stopOnEntry"].as_bool().unwrap_or(false),
+                });
+                out.respond(&request, json!({}));
+            }
+            "setBreakpoints" => {
+                let arguments = &request["arguments"];
+                let Some(path) = arguments["source"]["path"].as_str() else {
+                    out.respond_error(&request, "No source path specified");
+                    return true;
+                };
+                let path = PathBuf::from(path);
+                let lines: Vec<usize> = (arguments["breakpoints"].as_array().into_iter())
+                    .flatten()
+                    .filter_map(|bp| bp["line"].as_u64().map(|line| line as usize))
+                    .collect();
+                let verified = self.set_breakpoints(&path, &lines);
+                let breakpoints: Vec<Json> = (lines.iter().zip(verified))
+                    .map(|(line, verified)| json!({ "verified": verified, "line": line }))
+                    .collect();
+                self.pending_breakpoints.insert(path, lines);
+                out.respond(&request, json!({ "breakpoints": breakpoints }));
+            }
+            "configurationDone" => {
+                out.respond(&request, json!({}));
+                if let Some(launch) = self.launch.take() {
+                    self.start(launch);
+                }
+            }
+            "threads" => out.respond(
+                &request,
+                json!({ "threads": [{ "id": 1, "name": "main" }] }),
+            ),
+            "disconnect" | "terminate" => {
+                // Stop the program if it is running
+                if let Some(abort) = &self.abort {
+                    abort.store(true, Ordering::Relaxed);
+                }
+                let sent = match &*self.paused.lock() {
+                    Some(sender) => sender.send(request.clone()).is_ok(),
+                    None => false,
+                };
+                // The program may be blocked outside of the runtime, so only wait for it briefly
+                let start = Instant::now();
+                let finished = match self.program.take() {
+                    Some(program) => {
+                        while !program.is_finished() && start.elapsed() < STOP_GRACE {
+                            thread::sleep(Duration::from_millis(10));
+                        }
+                        program.is_finished() && program.join().is_ok()
+                    }
+                    None => true,
+                };
+                // If the program finished, it answered the request
+                if !sent || !finished {
+                    out.respond(&request, json!({}));
+                }
+                return request["command"] != "disconnect";
+            }
+            _ => {
+                // Everything else needs the program to be paused
+                let sent = match &*self.paused.lock() {
+                    Some(sender) => sender.send(request.clone()).is_ok(),
+                    None => false,
+                };
+                if !sent {
+                    out.respond_error(&request, "The program is not running");
+                }
+            }
+        }
+        true
+    }
+    /// Set the breakpoints for a source file, returning whether each one is valid
+    fn set_breakpoints(&self, path: &Path, lines: &[usize]) -> Vec<bool> {
+        let input = fs::read_to_string(path).unwrap_or_default();
+        let spans: Vec<_> = (lines.iter())
+            .map(|&line| line_span(&input, Some(path), line))
+            .collect();
+        if let Some(breakpoints) = &self.breakpoints {
+            let mut breakpoints = breakpoints.lock();
+            breakpoints.retain(|bp| bp.path.as_deref() != Some(path));
+            breakpoints.extend(spans.iter().flatten().cloned());
+        }
+        spans.iter().map(Option::is_some).collect()
+    }
+    /// Start the program on its own thread
+    fn start(&mut self, launch: Launch) {
+        let (send, recv) = channel();
+        *self.paused.lock() = Some(send);
+        let requests = Arc::new(Mutex::new(recv));
+        let handler = DapHandler {
+            out: self.out.clone(),
+            requests: requests.clone(),
+            stop_on_entry: launch.stop_on_entry,
+            first: true,
+        };
+        let mut env = Uiua::with_native_sys()
+            .with_mode(RunMode::Normal)
+            .with_file_path(&launch.program)
+            .with_args(launch.args)
+            .with_debugger(handler);
+        self.breakpoints = env.shared_breakpoints();
+        self.abort = Some(env.abort_flag());
+        for (path, lines) in &self.pending_breakpoints {
+            self.set_breakpoints(path, lines);
+        }
+        let out = self.out.clone();
+        let paused = self.paused.clone();
+        self.program = Some(thread::spawn(move || {
+            let exit_code = match env.load_file(&launch.program) {
+                Ok(()) => {
+                    for value in env.take_stack() {
+                        out.output("stdout", format!("{}\n", value.show()));
+                    }
+                    0
+                }
+                Err(e) => {
+                    out.output("stderr", format!("{}\n", e.show(false)));
+                    1
+                }
+            };
+            // Answer any requests that were waiting for a pause
+            paused.lock().take();
+            for request in requests.lock().try_iter() {
+                if let "disconnect" | "terminate" = request["command"].as_str().unwrap_or("") {
+                    out.respond(&request, json!({}));
+                } else {
+                    out.respond_error(&request, "The program is not running");
+                }
+            }
+            out.event("exited", json!({ "exitCode": exit_code }));
+            out.event("terminated", json!({}));
+        }));
+    }
+}
+
+/// The debug handler that runs on the program thread
+struct DapHandler {
+    out: Arc<Output>,
+    requests: Arc<Mutex<Receiver<Json>>>,
+    stop_on_entry: bool,
+    first: bool,
+}
+
+/// Variable references for the scopes of a frame
+const STACK_REF: u64 = 1;
+const TEMP_REF: u64 = 2;
+const BINDINGS_REF: u64 = 3;
+
+impl DebugHandler for DapHandler {
+    fn pause(&mut self, state: DebugState) -> DebugAction {
+        let reason = match &state.reason {
+            PauseReason::Breakpoint(_) => "breakpoint",
+            PauseReason::Step if self.first => "entry",
+            PauseReason::Step => "step",
+        };
+        if self.first {
+            self.first = false;
+            if !self.stop_on_entry && state.reason == PauseReason::Step {
+                return DebugAction::Continue;
+            }
+        }
+        self.out.event(
+            "stopped",
+            json!({ "reason": reason, "threadId": 1, "allThreadsStopped": true }),
+        );
+        let requests = self.requests.clone();
+        let requests = requests.lock();
+        while let Ok(request) = requests.recv() {
+            let out = &self.out;
+            let action = match request["command"].as_str().unwrap_or("") {
+                "stackTrace" => {
+                    let frames: Vec<Json> = (state.frames.iter().rev().enumerate())
+                        .map(|(i, frame)| {
+                            let span = frame.span.as_ref().unwrap_or(&frame.call_span);
+                            let mut json = json!({
+                                "id": i,
+                                "name": frame.function.id.to_string(),
+                                "line": 0,
+                                "column": 0,
+                            });
+                            if let Span::Code(span) = span {
+                                json["line"] = json!(span.start.line);
+                                json["column"] = json!(span.start.col);
+                                json["endLine"] = json!(span.end.line);
+                                json["endColumn"] = json!(span.end.col);
+                                if let Some(path) = &span.path {
+                                    json["source"] = json!({
+                                        "name": path.file_name().map(|name| name.to_string_lossy()),
+                                        "path": path.to_string_lossy(),
+                                    });
+                                }
+                            }
+                            json
+                        })
+                        .collect();
+                    out.respond(
+                        &request,
+                        json!({ "stackFrames": frames, "totalFrames": frames.len() }),
+                    );
+                    None
+                }
+                "scopes" => {
+                    let scope = |name: &str, reference: u64, count: usize| {
+                        json!({
+                            "name": name,
+                            "variablesReference": reference,
+                            "namedVariables": count,
+                            "expensive": false,
+                        })
+                    };
+                    let temps = state.inline_stack.len() + state.under_stack.len();
+                    out.respond(
+                        &request,
+                        json!({ "scopes": [
+                            scope("Stack", STACK_REF, state.stack.len()),
+                            scope("Temp", TEMP_REF, temps),
+                            scope("Bindings", BINDINGS_REF, state.bindings.len()),
+                        ]}),
+                    );
+                    None
+                }
+                "variables" => {
+                    let variable = |name: String, value: &Value| {
+                        json!({
+                            "name": name,
+                            "value": value.show(),
+                            "type": value.type_name(),
+                            "variablesReference": 0,
+                        })
+                    };
+                    let variables: Vec<Json> =
+                        match request["arguments"]["variablesReference"].as_u64() {
+                            Some(STACK_REF) => (state.stack.iter().rev().enumerate())
+                                .map(|(i, value)| variable(i.to_string(), value))
+                                .collect(),
+                            Some(TEMP_REF) => (state.inline_stack.iter().rev().enumerate())
+                                .map(|(i, value)| variable(format!("inline {i}"), value))
+                                .chain(
+                                    (state.under_stack.iter().rev().enumerate())
+                                        .map(|(i, value)| variable(format!("under {i}"), value)),
+                                )
+                                .collect(),
+                            Some(BINDINGS_REF) => {
+                                let mut bindings: Vec<_> = state.bindings.iter().collect();
+                                bindings.sort_by_key(|(name, _)| *name);
+                                (bindings.into_iter())
+                                    .map(|(name, value)| variable(name.to_string(), value))
+                                    .collect()
+                            }
+                            _ => Vec::new(),
+                        };
+                    out.respond(&request, json!({ "variables": variables }));
+                    None
+                }
+                "continue" => Some(DebugAction::Continue),
+                "next" => Some(DebugAction::StepOver),
+                "stepIn" => Some(DebugAction::StepInto),
+                "stepOut" => Some(DebugAction::StepOut),
+                "disconnect" | "terminate" => Some(DebugAction::Stop),
+                command => {
+                    out.respond_error(&request, format!("Unsupported request: {command}"));
+                    None
+                }
+            };
+            if let Some(action) = action {
+                let body = if action == DebugAction::Continue {
+                    json!({ "allThreadsContinued": true })
+                } else {
+                    json!({})
+                };
+                out.respond(&request, body);
+                return action;
+            }
+        }
+        DebugAction::Stop
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::TcpStream;
+
+    use super::*;
+
+    struct Client {
+        reader: BufReader<TcpStream>,
+        writer: TcpStream,
+        seq: u64,
+        messages: Vec<Json>,
+    }
+
+    impl Client {
+        fn request(&mut self, command: &str, arguments: Json) -> Json {
+            self.seq += 1;
+            let text = json!({
+                "seq": self.seq,
+                "type": "request",
+                "command": command,
+                "arguments": arguments,
+            })
+            .to_string();
+            write!(self.writer, "Content-Length: {}\r\n\r\n{text}", text.len()).unwrap();
+            let seq = self.seq;
+            self.wait_for(|message| message["request_seq"] == seq)
+        }
+        fn wait_for(&mut self, f: impl Fn(&Json) -> bool) -> Json {
+            loop {
+                let message = read_message(&mut self.reader).unwrap().unwrap();
+                self.messages.push(message.clone());
+                if f(&message) {
+                    return message;
+                }
+            }
+        }
+        fn event(&mut self, event: &str) -> Json {
+            self.wait_for(|message| message["event"] == event)
+        }
+    }
+
+    /// Write a program to a temp file that is unique to this process and test
+    fn temp_program(name: &str, code: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("uiua_dap_{name}_{}.ua", std::process::id()));
+        fs::write(&path, code).unwrap();
+        path
+    }
+
+    /// Start a server that serves one session and connect to it
+    fn connect() -> (Client, JoinHandle<()>) {
+        let listener = TcpListener::bind(("127.0.0.1", 0)).unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            serve(BufReader::new(stream.try_clone().unwrap()), stream).unwrap();
+        });
+        let stream = TcpStream::connect(addr).unwrap();
+        let client = Client {
+            reader: BufReader::new(stream.try_clone().unwrap()),
+            writer: stream,
+            seq: 0,
+            messages: Vec::new(),
+        };
+        (client, server)
+    }
+
+    #[test]
+    fn scripted_session() {
+        let path = temp_program("scripted", "x ← 5\ny ← +1 x\n×2 y\n+1\n");
+        let (mut client, server) = connect();
+
+        let response = client.request("initialize", json!({ "adapterID": "uiua" }));
+        assert_eq!(response["success"], true);
+        client.event("initialized");
+        let program = path.to_string_lossy();
+        client.request("launch", json!({ "program": program }));
+        let response = client.request(
+            "setBreakpoints",
+            json!({
+                "source": { "path": program },
+                "breakpoints": [{ "line": 3 }, { "line": 10 }],
+            }),
+        );
+        let breakpoints = &response["body"]["breakpoints"];
+        assert_eq!(breakpoints[0]["verified"], true);
+        assert_eq!(breakpoints[1]["verified"], false);
+        client.request("configurationDone", json!({}));
+
+        let stopped = client.event("stopped");
+        assert_eq!(stopped["body"]["reason"], "breakpoint");
+        let response = client.request("stackTrace", json!({ "threadId": 1 }));
+        let frame = &response["body"]["stackFrames"][0];
+        assert_eq!(frame["line"], 3);
+        assert_eq!(frame["source"]["path"], program.as_ref());
+        let response = client.request("scopes", json!({ "frameId": 0 }));
+        assert_eq!(response["body"]["scopes"].as_array().unwrap().len(), 3);
+        let response = client.request("variables", json!({ "variablesReference": STACK_REF }));
+        let variables = response["body"]["variables"].as_array().unwrap();
+        assert_eq!(variables.len(), 2);
+        assert_eq!(variables[0]["value"], "2");
+        assert_eq!(variables[1]["value"], "6");
+        let response = client.request("variables", json!({ "variablesReference": BINDINGS_REF }));
+        let names: Vec<_> = (response["body"]["variables"].as_array().unwrap().iter())
+            .map(|variable| variable["name"].as_str().unwrap())
+            .collect();
+        assert_eq!(names, ["x", "y"]);
+
+        client.request("next", json!({ "threadId": 1 }));
+        let stopped = client.event("stopped");
+        assert_eq!(stopped["body"]["reason"], "step");
+        client.request("continue", json!({ "threadId": 1 }));
+        let exited = client.event("exited");
+        assert_eq!(exited["body"]["exitCode"], 0);
+        client.event("terminated");
+        assert!(client.messages.iter().any(|message| {
+            message["event"] == "output" && message["body"]["output"] == "13\n"
+        }));
+        let response = client.request("stackTrace", json!({ "threadId": 1 }));
+        assert_eq!(response["success"], false);
+        client.request("disconnect", json!({}));
+        server.join().unwrap();
+        _ = fs::remove_file(path);
+    }
+
+    #[test]
+    fn disconnect_while_running() {
+        let path = temp_program("running", "⍥(+1)1e12 0\n");
+        let (mut client, server) = connect();
+
+        client.request("initialize", json!({ "adapterID": "uiua" }));
+        let program = path.to_string_lossy();
+        client.request("launch", json!({ "program": program }));
+        client.request("configurationDone", json!({}));
+        let start = Instant::now();
+        let response = client.request("disconnect", json!({}));
+        assert_eq!(response["success"], true);
+        server.join().unwrap();
+        assert!(start.elapsed() < STOP_GRACE * 2);
+        _ = fs::remove_file(path);
+    }
+}
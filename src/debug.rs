@@ -0,0 +1,226 @@
+//! Step debugging for the Uiua runtime
+//!
+//! Attach a [`DebugHandler`] to a runtime with [`Uiua::with_debugger`].
+//! The handler is called before instructions are executed and decides how execution continues.
+//!
+//! [`Uiua::with_debugger`]: crate::Uiua::with_debugger
+
+use std::{
+    collections::{BTreeSet, HashMap},
+    path::Path,
+    sync::Arc,
+};
+
+use parking_lot::Mutex;
+
+use crate::{
+    function::{Function, Instr},
+    lex::{CodeSpan, Loc, Span},
+    value::Value,
+    Ident,
+};
+
+/// A shared set of breakpoints
+///
+/// It may be modified while the program is running.
+pub type Breakpoints = Arc<Mutex<BTreeSet<CodeSpan>>>;
+
+/// A handler that is called whenever the debugger pauses execution
+pub trait DebugHandler: Send {
+    /// Called when execution is paused
+    ///
+    /// The returned action determines where execution will pause next
+    fn pause(&mut self, state: DebugState) -> DebugAction;
+}
+
+impl<F> DebugHandler for F
+where
+    F: FnMut(DebugState) -> DebugAction + Send,
+{
+    fn pause(&mut self, state: DebugState) -> DebugAction {
+        self(state)
+    }
+}
+
+/// What to do after the debugger pauses
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DebugAction {
+    /// Pause before the next instruction, even if it is in a called function
+    StepInto,
+    /// Pause before the next instruction in the current function or one of its callers
+    StepOver,
+    /// Pause before the next instruction in a caller of the current function
+    StepOut,
+    /// Only pause at breakpoints
+    Continue,
+    /// Stop execution with an error
+    Stop,
+}
+
+/// Why the debugger paused
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PauseReason {
+    /// A step finished
+    Step,
+    /// A breakpoint was hit
+    Breakpoint(CodeSpan),
+}
+
+/// A view of the runtime state when the debugger pauses
+pub struct DebugState<'a> {
+    /// Why execution paused
+    pub reason: PauseReason,
+    /// The call stack, with the innermost frame last
+    pub frames: Vec<DebugFrame>,
+    /// The main stack, with the top value last
+    pub stack: &'a [Value],
+    /// The temp stack used for inlining
+    pub inline_stack: &'a [Value],
+    /// The temp stack used for unders
+    pub under_stack: &'a [Value],
+    /// The bindings in the current scope
+    pub bindings: HashMap<Ident, Value>,
+    /// The current breakpoints
+    pub breakpoints: Breakpoints,
+}
+
+/// A frame of the call stack
+#[derive(Debug, Clone)]
+pub struct DebugFrame {
+    /// The function being executed
+    pub function: Arc<Function>,
+    /// The index of the next instruction to be executed
+    pub pc: usize,
+    /// The span at which the function was called
+    pub call_span: Span,
+    /// The span of the next instruction, if it has one
+    pub span: Option<Span>,
+}
+
+impl DebugFrame {
+    /// Get the next instruction to be executed
+    pub fn instr(&self) -> Option<&Instr> {
+        self.function.instrs.get(self.pc)
+    }
+}
+
+impl DebugState<'_> {
+    /// Get the innermost frame
+    pub fn current_frame(&self) -> Option<&DebugFrame> {
+        self.frames.last()
+    }
+}
+
+/// The debugger state held by a runtime
+#[derive(Clone)]
+pub(crate) struct Debugger {
+    pub handler: Arc<Mutex<dyn DebugHandler>>,
+    pub breakpoints: Breakpoints,
+    pub step: Step,
+    /// The breakpoint matched by the last instruction, to avoid pausing on it repeatedly
+    pub last_breakpoint: Option<CodeSpan>,
+}
+
+/// When the debugger should next pause
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Step {
+    /// Pause at any depth
+    Into,
+    /// Pause at this depth or shallower
+    Over(usize),
+    /// Pause shallower than this depth
+    Out(usize),
+    /// Only pause at breakpoints
+    Continue,
+}
+
+impl Debugger {
+    pub fn new(handler: impl DebugHandler + 'static) -> Self {
+        Self {
+            handler: Arc::new(Mutex::new(handler)),
+            breakpoints: Default::default(),
+            step: Step::Into,
+            last_breakpoint: None,
+        }
+    }
+    /// Check if a step should pause at the given depth
+    pub fn step_pauses(&self, depth: usize) -> bool {
+        match self.step {
+            Step::Into => true,
+            Step::Over(d) => depth <= d,
+            Step::Out(d) => depth < d,
+            Step::Continue => false,
+        }
+    }
+    /// Get the breakpoint that matches a span
+    pub fn breakpoint_at(&self, span: &CodeSpan) -> Option<CodeSpan> {
+        self.breakpoints
+            .lock()
+            .iter()
+            .find(|bp| {
+                bp.path == span.path && bp.contains_line_col(span.start.line, span.start.col)
+            })
+            .cloned()
+    }
+}
+
+/// Make a [`CodeSpan`] that covers an entire line of some input
+///
+/// Lines are 1-indexed. Returns `None` if the line does not exist.
+pub fn line_span(input: &str, path: Option<&Path>, line: usize) -> Option<CodeSpan> {
+    let mut char_pos = 0;
+    let mut byte_pos = 0;
+    for (i, text) in input.split('\n').enumerate() {
+        let len = text.chars().count();
+        if i + 1 == line {
+            return Some(CodeSpan {
+                start: Loc {
+                    char_pos,
+                    byte_pos,
+                    line,
+                    col: 1,
+                },
+                end: Loc {
+                    char_pos: char_pos + len,
+                    byte_pos: byte_pos + text.len(),
+                    line,
+                    col: len + 1,
+                },
+                path: path.map(Into::into),
+                input: input.into(),
+            });
+        }
+        char_pos += len + 1;
+        byte_pos += text.len() + 1;
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Uiua;
+
+    #[test]
+    fn breakpoints_and_steps() {
+        let input = "F ← +1\nx ← 5\nF x\n×2 3";
+        let paused = Arc::new(Mutex::new(Vec::new()));
+        let record = paused.clone();
+        let mut env = Uiua::with_native_sys().with_debugger(move |state: DebugState| {
+            let line = match state.current_frame().and_then(|frame| frame.span.clone()) {
+                Some(Span::Code(span)) => span.start.line,
+                _ => 0,
+            };
+            record.lock().push((state.reason, line, state.stack.len()));
+            DebugAction::Continue
+        });
+        env.add_breakpoint(line_span(input, None, 4).unwrap());
+        env.load_str(input).unwrap();
+        let paused = paused.lock();
+        assert_eq!(paused.len(), 2);
+        assert_eq!(paused[0].0, PauseReason::Step);
+        assert!(matches!(paused[1].0, PauseReason::Breakpoint(_)));
+        assert_eq!(paused[1].1, 4);
+        assert_eq!(paused[1].2, 3);
+    }
+}
@@ -0,0 +1,107 @@
+//! Textual listings of compiled code
+//!
+//! Use [`Uiua::disassemble`] after loading some code to see what it was compiled to.
+//! To compile code without running it, load it with [`Uiua::with_compile_only`].
+
+use std::{collections::HashSet, fmt::Write, sync::Arc};
+
+use crate::{
+    function::{Function, Instr},
+    lex::Span,
+    value::Value,
+    Uiua,
+};
+
+impl Uiua {
+    /// Disassemble every global function
+    ///
+    /// Globals are only compiled when code is loaded, so this should be called after loading.
+    /// Functions defined inside other functions are listed after the function that contains them.
+    pub fn disassemble(&self) -> String {
+        let globals = self.globals.lock().clone();
+        let spans = self.spans.lock();
+        let mut listed = HashSet::new();
+        let mut output = String::new();
+        for value in &globals {
+            if let Some(f) = value.as_function() {
+                disassemble_into(f, &spans, &mut listed, &mut output);
+            }
+        }
+        output
+    }
+    /// Disassemble a function and the functions it contains
+    pub fn disassemble_function(&self, f: &Arc<Function>) -> String {
+        let spans = self.spans.lock();
+        let mut output = String::new();
+        disassemble_into(f, &spans, &mut HashSet::new(), &mut output);
+        output
+    }
+}
+
+fn disassemble_into(
+    f: &Arc<Function>,
+    spans: &[Span],
+    listed: &mut HashSet<*const Function>,
+    output: &mut String,
+) {
+    if !listed.insert(Arc::as_ptr(f)) {
+        return;
+    }
+    if !output.is_empty() {
+        output.push('\n');
+    }
+    _ = writeln!(output, "{} {}", f.id, f.signature());
+    let instrs: Vec<String> = f.instrs.iter().map(instr_text).collect();
+    let width = instrs.iter().map(|s| s.chars().count()).max().unwrap_or(0);
+    for (i, (instr, text)) in f.instrs.iter().zip(&instrs).enumerate() {
+        _ = write!(output, "  {i:>4}  {text}");
+        match instr.span().map(|span| &spans[span]) {
+            Some(Span::Code(span)) => {
+                let pad = width - text.chars().count();
+                _ = write!(output, "{:pad$}  {span}", "");
+            }
+            Some(Span::Builtin) | None => {}
+        }
+        output.push('\n');
+    }
+    for instr in &f.instrs {
+        if let Some(inner) = instr.as_push().and_then(Value::as_function) {
+            disassemble_into(inner, spans, listed, output);
+        }
+    }
+}
+
+/// Format an instruction so that pushed functions refer to their own listing
+fn instr_text(instr: &Instr) -> String {
+    match instr.as_push() {
+        Some(value) => match value.as_function() {
+            Some(f) => format!("push {}", f.id),
+            None => format!("push {}", value.show().replace('\n', " ")),
+        },
+        None => instr.to_string().replace('\n', " "),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Uiua;
+
+    #[test]
+    fn disassemble() {
+        // Compiling does not run the code
+        let mut env = Uiua::with_native_sys().with_compile_only(true);
+        env.load_str("F ← +1\nG ← ≡(×2)\n&p \"side effect\"\n⍥(+1)1e12 0")
+            .unwrap();
+        let listing = env.disassemble();
+        let f_start = listing.find("`F` |1.1\n").unwrap();
+        let g_start = listing.find("`G` |1.1\n").unwrap();
+        assert!(f_start < g_start);
+        let f_listing = &listing[f_start..g_start];
+        assert!(f_listing.contains("push 1"));
+        assert!(f_listing.contains("1:5"));
+        assert!(f_listing.contains("+"));
+        // The function passed to rows is listed after G
+        assert!(listing[g_start..].contains("push fn from 2:6"));
+        assert!(listing[g_start..].contains("\nfn from 2:6 |1.1\n"));
+    }
+}
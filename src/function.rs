@@ -143,6 +143,36 @@ impl Instr {
             _ => None,
         }
     }
+    /// Get the index of the instruction's span, if it has one
+    pub(crate) fn span(&self) -> Option<usize> {
+        match self {
+            Instr::Push(_) | Instr::BeginArray | Instr::Dynamic(_) => None,
+            Instr::EndArray { span, .. }
+            | Instr::Prim(_, span)
+            | Instr::Call(span)
+            | Instr::PushTempUnder { span, .. }
+            | Instr::PopTempUnder { span, .. }
+            | Instr::PushTempInline { span, .. }
+            | Instr::PopTempInline { span, .. }
+            | Instr::CopyTempInline { span, .. }
+            | Instr::DropTempInline { span, .. } => Some(*span),
+        }
+    }
+    /// Get a mutable reference to the index of the instruction's span, if it has one
+    pub(crate) fn span_mut(&mut self) -> Option<&mut usize> {
+        match self {
+            Instr::Push(_) | Instr::BeginArray | Instr::Dynamic(_) => None,
+            Instr::EndArray { span, .. }
+            | Instr::Prim(_, span)
+            | Instr::Call(span)
+            | Instr::PushTempUnder { span, .. }
+            | Instr::PopTempUnder { span, .. }
+            | Instr::PushTempInline { span, .. }
+            | Instr::PopTempInline { span, .. }
+            | Instr::CopyTempInline { span, .. }
+            | Instr::DropTempInline { span, .. } => Some(span),
+        }
+    }
     pub fn is_temp(&self) -> bool {
         matches!(
             self,
@@ -379,6 +409,17 @@ impl Function {
     pub fn signature(&self) -> Signature {
         self.signature
     }
+    /// Check if calling the function has no side effects
+    ///
+    /// This is conservative, so some functions that are pure will not be recognized as such.
+    pub fn is_pure(&self) -> bool {
+        self.instrs.iter().all(|instr| match instr {
+            Instr::Push(val) => val.is_pure(),
+            Instr::Prim(prim, _) => prim.is_pure(),
+            Instr::Dynamic(_) => false,
+            _ => true,
+        })
+    }
     pub fn is_constant(&self) -> bool {
         matches!(&*self.instrs, [Instr::Push(_)])
     }
@@ -13,6 +13,7 @@ use std::{
 
 use crate::{
     array::{Array, ArrayValue},
+    complex::Complex,
     function::Function,
     primitive::Primitive,
     value::Value,
@@ -65,6 +66,16 @@ impl GridFmt for f64 {
     }
 }
 
+impl GridFmt for Complex {
+    fn fmt_grid(&self, boxed: bool) -> Grid {
+        let mut row = self.re.fmt_grid(boxed).remove(0);
+        row.push(if self.im.is_sign_negative() { '-' } else { '+' });
+        row.extend(self.im.abs().fmt_grid(false).remove(0));
+        row.push('i');
+        vec![row]
+    }
+}
+
 pub fn format_char_inner(c: char) -> String {
     if c == char::MAX {
         return '_'.to_string();
@@ -149,6 +160,7 @@ impl GridFmt for Value {
         match self {
             Value::Num(array) => array.fmt_grid(boxed),
             Value::Byte(array) => array.fmt_grid(boxed),
+            Value::Complex(array) => array.fmt_grid(boxed),
             Value::Char(array) => array.fmt_grid(boxed),
             Value::Func(array) => array.fmt_grid(boxed),
         }
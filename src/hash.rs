@@ -0,0 +1,97 @@
+//! Hash functions and byte encodings
+
+use md5::Md5;
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+
+/// The names of the supported hash algorithms
+pub(crate) const HASH_ALGORITHMS: [&str; 3] = ["sha256", "sha1", "md5"];
+
+/// Compute the digest of some bytes with a hash algorithm, named as in [`HASH_ALGORITHMS`]
+pub(crate) fn digest(algorithm: &str, bytes: &[u8]) -> Result<Vec<u8>, String> {
+    Ok(match algorithm {
+        "sha256" => Sha256::digest(bytes).to_vec(),
+        "sha1" => Sha1::digest(bytes).to_vec(),
+        "md5" => Md5::digest(bytes).to_vec(),
+        _ => {
+            return Err(format!(
+                "Unknown hash algorithm {algorithm:?}. The supported algorithms are {}",
+                HASH_ALGORITHMS.join(", ")
+            ))
+        }
+    })
+}
+
+/// Encode bytes as lowercase hexadecimal
+pub(crate) fn hex_encode(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+/// Decode hexadecimal into bytes
+///
+/// Both uppercase and lowercase digits are accepted.
+pub(crate) fn hex_decode(hex: &str) -> Result<Vec<u8>, String> {
+    let digits: Vec<char> = hex.chars().collect();
+    if digits.len() % 2 == 1 {
+        return Err(format!(
+            "Hex strings must have an even length, but this one has length {}",
+            digits.len()
+        ));
+    }
+    digits
+        .chunks_exact(2)
+        .map(|pair| {
+            let digit = |c: char| {
+                c.to_digit(16)
+                    .ok_or_else(|| format!("{c:?} is not a hex digit"))
+            };
+            Ok((digit(pair[0])? * 16 + digit(pair[1])?) as u8)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn digests() {
+        for (input, sha256_hex, sha1_hex, md5_hex) in [
+            (
+                "",
+                "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855",
+                "da39a3ee5e6b4b0d3255bfef95601890afd80709",
+                "d41d8cd98f00b204e9800998ecf8427e",
+            ),
+            (
+                "abc",
+                "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad",
+                "a9993e364706816aba3e25717850c26c9cd0d89d",
+                "900150983cd24fb0d6963f7d28e17f72",
+            ),
+            // Longer than one block
+            (
+                "abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq",
+                "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1",
+                "84983e441c3bd26ebaae4aa1f95129e5e54670f1",
+                "8215ef0796a20bcaaae116d3876c664a",
+            ),
+        ] {
+            for (algorithm, hex) in [("sha256", sha256_hex), ("sha1", sha1_hex), ("md5", md5_hex)] {
+                assert_eq!(
+                    hex_encode(&digest(algorithm, input.as_bytes()).unwrap()),
+                    hex
+                );
+            }
+        }
+        assert!(digest("sha3", b"abc").is_err());
+    }
+
+    #[test]
+    fn hex() {
+        assert_eq!(hex_encode(&[0, 15, 255]), "000fff");
+        assert_eq!(hex_decode("000FfF").unwrap(), [0, 15, 255]);
+        assert!(hex_decode("abc").is_err());
+        assert!(hex_decode("zz").is_err());
+    }
+}
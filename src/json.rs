@@ -0,0 +1,207 @@
+//! Conversion between values and JSON
+//!
+//! JSON values are decoded like this:
+//! - Numbers become scalar numbers
+//! - `true` and `false` become `1` and `0`
+//! - `null` becomes `NaN`
+//! - Strings become character lists
+//! - Arrays whose items are all numbers or all strings of the same shape become arrays with one more axis
+//! - Other arrays become lists of boxes
+//! - Objects become rank 2 arrays of boxed key-value pairs
+//!
+//! Encoding does the reverse, so anything that was decoded can be encoded back to equivalent JSON.
+//! Booleans are encoded as numbers.
+
+use std::sync::Arc;
+
+use serde_json::{Map, Number, Value as Json};
+use tinyvec::tiny_vec;
+
+use crate::{
+    array::{Array, ArrayValue},
+    function::Function,
+    value::Value,
+};
+
+/// Decode a JSON string into a value
+pub(crate) fn json_to_value(json: &str) -> Result<Value, String> {
+    let json: Json = serde_json::from_str(json).map_err(|e| format!("Invalid JSON: {e}"))?;
+    Ok(from_json(json))
+}
+
+/// Encode a value as a JSON string
+pub(crate) fn value_to_json(value: &Value) -> Result<String, String> {
+    let json = to_json(value)?;
+    serde_json::to_string(&json).map_err(|e| e.to_string())
+}
+
+fn from_json(json: Json) -> Value {
+    match json {
+        Json::Null => f64::NAN.into(),
+        Json::Bool(b) => (b as u8 as f64).into(),
+        Json::Number(n) => n.as_f64().unwrap_or(f64::NAN).into(),
+        Json::String(s) => s.chars().collect::<Array<char>>().into(),
+        Json::Array(items) => {
+            let values: Vec<Value> = items.into_iter().map(from_json).collect();
+            let uniform = values.windows(2).all(|pair| {
+                pair[0].shape() == pair[1].shape()
+                    && matches!(
+                        (&pair[0], &pair[1]),
+                        (Value::Num(_), Value::Num(_)) | (Value::Char(_), Value::Char(_))
+                    )
+            }) && !matches!(values.first(), Some(Value::Func(_)));
+            if uniform {
+                Value::from_row_values_infallible(values)
+            } else {
+                values
+                    .into_iter()
+                    .map(|value| Arc::new(Function::constant(value)))
+                    .collect::<Array<_>>()
+                    .into()
+            }
+        }
+        Json::Object(map) => {
+            let len = map.len();
+            let mut pairs = Vec::with_capacity(len * 2);
+            for (key, value) in map {
+                let key = key.chars().collect::<Array<char>>();
+                pairs.push(Arc::new(Function::constant(key)));
+                pairs.push(Arc::new(Function::constant(from_json(value))));
+            }
+            Array::new(tiny_vec![len, 2], pairs).into()
+        }
+    }
+}
+
+fn to_json(value: &Value) -> Result<Json, String> {
+    match value {
+        Value::Num(arr) => array_to_json(arr, &|&n| num_to_json(n)),
+        Value::Byte(arr) => array_to_json(arr, &|&b| Ok(b.into())),
+        Value::Complex(_) => Err("Cannot encode complex numbers as JSON".into()),
+        Value::Char(arr) => chars_to_json(arr),
+        Value::Func(arr) => funcs_to_json(arr),
+    }
+}
+
+fn array_to_json<T: ArrayValue>(
+    arr: &Array<T>,
+    scalar: &dyn Fn(&T) -> Result<Json, String>,
+) -> Result<Json, String> {
+    if arr.rank() == 0 {
+        scalar(&arr.data[0])
+    } else {
+        (arr.rows())
+            .map(|row| array_to_json(&row, scalar))
+            .collect::<Result<_, _>>()
+            .map(Json::Array)
+    }
+}
+
+fn num_to_json(n: f64) -> Result<Json, String> {
+    if n.is_nan() {
+        Ok(Json::Null)
+    } else if n.is_infinite() {
+        Err(format!("Cannot encode {n} as JSON"))
+    } else if n.fract() == 0.0 && n.abs() < (1u64 << 53) as f64 {
+        Ok((n as i64).into())
+    } else {
+        Ok(Number::from_f64(n).unwrap().into())
+    }
+}
+
+fn chars_to_json(arr: &Array<char>) -> Result<Json, String> {
+    match arr.rank() {
+        0 => Ok(Json::String(arr.data[0].into())),
+        1 => Ok(Json::String(arr.data.iter().collect())),
+        _ => (arr.rows())
+            .map(|row| chars_to_json(&row))
+            .collect::<Result<_, _>>()
+            .map(Json::Array),
+    }
+}
+
+fn funcs_to_json(arr: &Array<Arc<Function>>) -> Result<Json, String> {
+    if arr.rank() == 0 {
+        return match arr.data[0].as_boxed() {
+            Some(value) => to_json(value),
+            None => Err(format!("Cannot encode function {} as JSON", arr.data[0].id)),
+        };
+    }
+    if arr.rank() == 2 && arr.shape()[1] == 2 {
+        if let Some(map) = object_to_json(arr)? {
+            return Ok(Json::Object(map));
+        }
+    }
+    (arr.rows())
+        .map(|row| funcs_to_json(&row))
+        .collect::<Result<_, _>>()
+        .map(Json::Array)
+}
+
+/// Encode key-value pairs as an object if all the keys are strings
+fn object_to_json(arr: &Array<Arc<Function>>) -> Result<Option<Map<String, Json>>, String> {
+    let mut map = Map::with_capacity(arr.shape()[0]);
+    for pair in arr.data.chunks_exact(2) {
+        let Some(Value::Char(key)) = pair[0].as_boxed() else {
+            return Ok(None);
+        };
+        if key.rank() > 1 {
+            return Ok(None);
+        }
+        let value = match pair[1].as_boxed() {
+            Some(value) => to_json(value)?,
+            None => return Ok(None),
+        };
+        map.insert(key.data.iter().collect(), value);
+    }
+    Ok(Some(map))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        for json in [
+            r#"5"#,
+            r#"[1,2.5,null]"#,
+            r#""hello""#,
+            r#"[[1,2],[3,4]]"#,
+            r#"["ab","cd"]"#,
+            r#"[1,"two",[3]]"#,
+            r#"[[1,2],[3]]"#,
+            r#"{"name":"uiua","tags":["array","stack"],"version":[0,0,18]}"#,
+            r#"[{"a":1},{"b":{}}]"#,
+            r#"[]"#,
+        ] {
+            let value = json_to_value(json).unwrap();
+            assert_eq!(value_to_json(&value).unwrap(), json);
+        }
+    }
+
+    #[test]
+    fn decode_shapes() {
+        assert_eq!(json_to_value("[[1,2],[3,4]]").unwrap().shape(), [2, 2]);
+        assert_eq!(json_to_value(r#"["ab","cd"]"#).unwrap().shape(), [2, 2]);
+        assert_eq!(json_to_value(r#"[1,"a"]"#).unwrap().shape(), [2]);
+        assert_eq!(json_to_value(r#"{"a":1,"b":2}"#).unwrap().shape(), [2, 2]);
+        assert_eq!(json_to_value("true").unwrap(), Value::from(1.0));
+    }
+
+    #[test]
+    fn encode_errors() {
+        let err = value_to_json(&Value::from(f64::INFINITY)).unwrap_err();
+        assert!(err.contains("inf"), "{err}");
+        let f = Function::new(
+            crate::function::FunctionId::Constant,
+            [],
+            crate::function::Signature::new(0, 0),
+        );
+        let err = value_to_json(&Value::from(f)).unwrap_err();
+        assert!(err.contains("Cannot encode function"), "{err}");
+        assert!(json_to_value("[1,")
+            .unwrap_err()
+            .starts_with("Invalid JSON"));
+    }
+}
@@ -12,18 +12,28 @@ pub mod array;
 pub mod ast;
 mod check;
 mod compile;
+pub mod complex;
+mod compress;
 mod cowslice;
+mod csv;
+#[cfg(feature = "dap")]
+pub mod dap;
+pub mod debug;
+pub mod disasm;
 mod error;
 pub mod format;
 pub mod function;
 mod grid_fmt;
+mod hash;
+mod json;
 pub mod lex;
 pub mod lsp;
+mod optimize;
 pub mod parse;
 pub mod primitive;
-#[doc(hidden)]
 pub mod profile;
 pub mod run;
+pub mod snapshot;
 mod sys;
 pub mod value;
 
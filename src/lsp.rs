@@ -1,10 +1,11 @@
-use std::slice;
+use std::{collections::HashMap, slice};
 
 use crate::{
-    ast::{Item, Word},
+    ast::{Binding, Item, Word},
     lex::{CodeSpan, Loc, Sp},
     parse::parse,
     primitive::Primitive,
+    Ident, SysOp,
 };
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -97,29 +98,301 @@ fn words_spans(words: &[Sp<Word>]) -> Vec<Sp<SpanKind>> {
     spans
 }
 
+/// The binding definitions and references in a document
+#[derive(Debug, Clone, Default)]
+pub struct SymbolIndex {
+    /// The name spans of all binding definitions
+    pub definitions: Vec<Sp<Ident>>,
+    /// Each use of a binding, paired with the name span of its definition
+    pub references: Vec<(Sp<Ident>, CodeSpan)>,
+    /// Bindings that bring in a binding from another file with [`Primitive::Use`]
+    pub imports: Vec<ImportedBinding>,
+}
+
+/// A binding of the form `X ← use "Name" &i "path"`
+#[derive(Debug, Clone)]
+pub struct ImportedBinding {
+    /// The name span of the local binding
+    pub local: CodeSpan,
+    /// The path passed to `&i`
+    pub path: String,
+    /// The name of the imported binding, spanning the contents of its string literal
+    pub name: Sp<String>,
+}
+
+impl SymbolIndex {
+    /// Build the index for some input
+    pub fn new(input: &str) -> Self {
+        let (items, _, _) = parse(input, None);
+        let mut index = SymbolIndex::default();
+        index.items(&items, &mut vec![ScopeSymbols::default()]);
+        index
+    }
+    /// Get the definition of the binding at a position
+    pub fn definition_at(&self, line: usize, col: usize) -> Option<&CodeSpan> {
+        if let Some(def) =
+            (self.definitions.iter()).find(|def| def.span.contains_line_col(line, col))
+        {
+            return Some(&def.span);
+        }
+        (self.references.iter())
+            .find(|(r, _)| r.span.contains_line_col(line, col))
+            .map(|(_, def)| def)
+    }
+    /// Get the definition with the given name span
+    pub fn definition(&self, span: &CodeSpan) -> Option<&Sp<Ident>> {
+        self.definitions.iter().find(|def| def.span == *span)
+    }
+    /// Get the uses of the binding defined at a span
+    pub fn references_to<'a>(&'a self, def: &'a CodeSpan) -> impl Iterator<Item = &'a Sp<Ident>> {
+        (self.references.iter())
+            .filter(move |(_, d)| d == def)
+            .map(|(r, _)| r)
+    }
+    /// Get the import whose local binding is defined at a span
+    pub fn import_of(&self, def: &CodeSpan) -> Option<&ImportedBinding> {
+        self.imports.iter().find(|import| import.local == *def)
+    }
+    /// Get the import whose imported name is at a position
+    pub fn import_name_at(&self, line: usize, col: usize) -> Option<&ImportedBinding> {
+        (self.imports.iter()).find(|import| import.name.span.contains_line_col(line, col))
+    }
+    fn items(&mut self, items: &[Item], scopes: &mut Vec<ScopeSymbols>) {
+        for item in items {
+            match item {
+                Item::Scoped { items, .. } => {
+                    scopes.push(ScopeSymbols::default());
+                    self.items(items, scopes);
+                    scopes.pop();
+                }
+                Item::Words(words) => self.words(words, scopes),
+                Item::Binding(binding) => self.binding(binding, scopes),
+                Item::ExtraNewlines(_) => {}
+            }
+        }
+    }
+    fn binding(&mut self, binding: &Binding, scopes: &mut [ScopeSymbols]) {
+        self.words(&binding.words, scopes);
+        let words: Vec<&Sp<Word>> = (binding.words.iter())
+            .filter(|word| !matches!(word.value, Word::Spaces | Word::Comment(_)))
+            .collect();
+        let scope = scopes.last_mut().unwrap();
+        match words.as_slice() {
+            // m ← &i "path"
+            [import, path] => {
+                if let (Word::Primitive(Primitive::Sys(SysOp::Import)), Word::String(path)) =
+                    (&import.value, &path.value)
+                {
+                    scope
+                        .modules
+                        .insert(binding.name.span.clone(), path.clone());
+                }
+            }
+            // X ← use "Name" m
+            [prim, name, module] => {
+                if let (Word::Primitive(Primitive::Use), Word::String(text), Word::Ident(module)) =
+                    (&prim.value, &name.value, &module.value)
+                {
+                    let path = (scopes.iter().rev())
+                        .find_map(|scope| scope.names.get(module))
+                        .and_then(|def| scopes.iter().rev().find_map(|s| s.modules.get(def)));
+                    if let Some(path) = path {
+                        self.imports.push(ImportedBinding {
+                            local: binding.name.span.clone(),
+                            path: path.clone(),
+                            name: string_contents(&name.span).sp(text.clone()),
+                        });
+                    }
+                }
+            }
+            // X ← use "Name" &i "path"
+            [prim, name, import, path] => {
+                if let (
+                    Word::Primitive(Primitive::Use),
+                    Word::String(text),
+                    Word::Primitive(Primitive::Sys(SysOp::Import)),
+                    Word::String(path),
+                ) = (&prim.value, &name.value, &import.value, &path.value)
+                {
+                    self.imports.push(ImportedBinding {
+                        local: binding.name.span.clone(),
+                        path: path.clone(),
+                        name: string_contents(&name.span).sp(text.clone()),
+                    });
+                }
+            }
+            _ => {}
+        }
+        let scope = scopes.last_mut().unwrap();
+        (scope.names).insert(binding.name.value.clone(), binding.name.span.clone());
+        self.definitions.push(binding.name.clone());
+    }
+    fn words(&mut self, words: &[Sp<Word>], scopes: &[ScopeSymbols]) {
+        for word in words {
+            match &word.value {
+                Word::Ident(ident) => {
+                    // Local scopes can see the names of the scope directly above them
+                    if let Some(def) =
+                        (scopes.iter().rev().take(2)).find_map(|scope| scope.names.get(ident))
+                    {
+                        let reference = word.span.clone().sp(ident.clone());
+                        self.references.push((reference, def.clone()));
+                    }
+                }
+                Word::Strand(items) => self.words(items, scopes),
+                Word::Array(arr) => {
+                    for line in &arr.lines {
+                        self.words(line, scopes);
+                    }
+                }
+                Word::Func(func) => {
+                    for line in &func.lines {
+                        self.words(line, scopes);
+                    }
+                }
+                Word::Modified(m) => self.words(&m.operands, scopes),
+                _ => {}
+            }
+        }
+    }
+}
+
+#[derive(Default)]
+struct ScopeSymbols {
+    /// Map names to the spans of their definitions
+    names: HashMap<Ident, CodeSpan>,
+    /// Map the definitions of bindings to `&i` to the imported paths
+    modules: HashMap<CodeSpan, String>,
+}
+
+/// Get the span of the contents of a string literal
+fn string_contents(span: &CodeSpan) -> CodeSpan {
+    let shrink = |loc: Loc, d: isize| Loc {
+        char_pos: (loc.char_pos as isize + d) as usize,
+        byte_pos: (loc.byte_pos as isize + d) as usize,
+        line: loc.line,
+        col: (loc.col as isize + d) as usize,
+    };
+    CodeSpan {
+        start: shrink(span.start, 1),
+        end: shrink(span.end, -1),
+        ..span.clone()
+    }
+}
+
+/// A modifier whose operands contain a position
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ModifierCall {
+    /// The modifier
+    pub modifier: Primitive,
+    /// The index of the operand at the position
+    ///
+    /// This may be one past the last operand if the next one has not been written yet.
+    pub operand: usize,
+}
+
+/// Find the innermost modifier call at a position
+pub fn modifier_at(input: &str, line: usize, col: usize) -> Option<ModifierCall> {
+    let (items, _, _) = parse(input, None);
+    items_modifier_at(&items, line, col)
+}
+
+fn items_modifier_at(items: &[Item], line: usize, col: usize) -> Option<ModifierCall> {
+    items.iter().find_map(|item| match item {
+        Item::Scoped { items, .. } => items_modifier_at(items, line, col),
+        Item::Words(words) => words_modifier_at(words, line, col),
+        Item::Binding(binding) => words_modifier_at(&binding.words, line, col),
+        Item::ExtraNewlines(_) => None,
+    })
+}
+
+fn words_modifier_at(words: &[Sp<Word>], line: usize, col: usize) -> Option<ModifierCall> {
+    let word = (words.iter()).find(|word| word.span.contains_line_col(line, col))?;
+    match &word.value {
+        Word::Strand(items) => words_modifier_at(items, line, col),
+        Word::Array(arr) => {
+            (arr.lines.iter()).find_map(|line_words| words_modifier_at(line_words, line, col))
+        }
+        Word::Func(func) => {
+            (func.lines.iter()).find_map(|line_words| words_modifier_at(line_words, line, col))
+        }
+        // A modifier that has no operands yet
+        Word::Primitive(prim) if prim.modifier_args().is_some() => Some(ModifierCall {
+            modifier: *prim,
+            operand: 0,
+        }),
+        Word::Modified(m) => {
+            if let Some(inner) = words_modifier_at(&m.operands, line, col) {
+                return Some(inner);
+            }
+            let operands: Vec<&Sp<Word>> = (m.operands.iter())
+                .filter(|word| !matches!(word.value, Word::Spaces | Word::Comment(_)))
+                .collect();
+            let margs = m.modifier.value.modifier_args().unwrap_or(0) as usize;
+            let operand = match operands
+                .iter()
+                .position(|word| word.span.contains_line_col(line, col))
+            {
+                // At the end of the last operand but with more to come
+                Some(i) if i + 1 == operands.len() && operands.len() < margs => {
+                    let end = operands[i].span.end;
+                    if (end.line, end.col) == (line, col) && !m.terminated {
+                        operands.len()
+                    } else {
+                        i
+                    }
+                }
+                Some(i) => i,
+                None => (operands.iter())
+                    .filter(|word| (word.span.end.line, word.span.end.col) <= (line, col))
+                    .count(),
+            };
+            Some(ModifierCall {
+                modifier: m.modifier.value,
+                operand: operand.min(margs.saturating_sub(1)),
+            })
+        }
+        _ => None,
+    }
+}
+
 #[cfg(feature = "lsp")]
 pub use server::run_server;
 
 #[cfg(feature = "lsp")]
 mod server {
-    use std::{collections::BTreeMap, sync::Arc};
+    use std::{
+        any::Any,
+        collections::{BTreeMap, BTreeSet, HashMap},
+        fs,
+        path::PathBuf,
+        sync::Arc,
+    };
 
     use dashmap::DashMap;
-    use tower_lsp::{jsonrpc::Result, lsp_types::*, *};
+    use tower_lsp::{
+        jsonrpc::{Error, Result},
+        lsp_types::*,
+        *,
+    };
 
     use super::*;
 
     use crate::{
         format::{format_str, FormatConfig /*, FormatConfigSource*/},
-        lex::Loc,
-        primitive::PrimDocFragment,
-        Ident, Uiua,
+        function::Signature,
+        lex::{is_ident_char, Loc, Span},
+        primitive::{PrimDocFragment, CONSTANTS},
+        run::RunMode,
+        DiagnosticKind, Ident, SysBackend, Uiua, UiuaError,
     };
 
     pub struct LspDoc {
         pub input: String,
         pub spans: Vec<Sp<SpanKind>>,
         pub bindings: BindingsInfo,
+        pub symbols: Arc<SymbolIndex>,
+        pub analysis: Analysis,
     }
 
     type BindingsInfo = BTreeMap<Sp<Ident>, Arc<BindingInfo>>;
@@ -129,14 +402,153 @@ mod server {
             let (items, _, _) = parse(&input, None);
             let spans = items_spans(&items);
             let bindings = bindings_info(&items);
+            let symbols = SymbolIndex::new(&input).into();
+            let analysis = analyze(&input);
             Self {
                 input,
                 spans,
                 bindings,
+                symbols,
+                analysis,
             }
         }
     }
 
+    /// What is known about a document from compiling it
+    #[derive(Default)]
+    pub(super) struct Analysis {
+        /// Errors and warnings from parsing and compiling
+        pub diagnostics: Vec<lsp_types::Diagnostic>,
+        /// Signatures of bindings and the height of the stack after each line
+        pub inlay_hints: Vec<InlayHint>,
+        /// The bindings at the end of the document
+        ///
+        /// Each is `None` if its value is only known by running the document.
+        pub bindings: HashMap<Ident, Option<BindingSig>>,
+    }
+
+    /// The kind and signature of a binding
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub(super) struct BindingSig {
+        pub is_function: bool,
+        pub signature: Signature,
+    }
+
+    /// Compile a document without running it
+    ///
+    /// Every item is compiled, even after one has an error.
+    /// Bindings without a declared signature are annotated with their signature,
+    /// and lines are annotated with the height of the stack after they run,
+    /// as inferred from their signatures.
+    pub(super) fn analyze(input: &str) -> Analysis {
+        let diagnostic = |span: &CodeSpan, message: String, severity| lsp_types::Diagnostic {
+            range: uiua_span_to_lsp(span),
+            severity: Some(severity),
+            source: Some("uiua".into()),
+            message,
+            ..Default::default()
+        };
+        let (items, errors, parse_diagnostics) = parse(input, None);
+        let mut analysis = Analysis::default();
+        for error in &errors {
+            let message = error.value.to_string();
+            (analysis.diagnostics).push(diagnostic(
+                &error.span,
+                message,
+                DiagnosticSeverity::ERROR,
+            ));
+        }
+        let mut uiua_diagnostics = parse_diagnostics;
+        let mut env = Uiua::with_backend(SandboxSys)
+            .with_mode(RunMode::All)
+            .with_compile_only(true);
+        let hint = |loc: Loc, label: String| InlayHint {
+            position: uiua_loc_to_lsp(loc),
+            label: InlayHintLabel::String(label),
+            kind: Some(InlayHintKind::TYPE),
+            text_edits: None,
+            tooltip: None,
+            padding_left: Some(true),
+            padding_right: None,
+            data: None,
+        };
+        let mut height: Option<usize> = Some(0);
+        if errors.is_empty() {
+            env.items_each(items, &mut |env, item, res| {
+                if let Some((span, message)) = res.as_ref().err().and_then(error_span) {
+                    (analysis.diagnostics).push(diagnostic(
+                        &span,
+                        message,
+                        DiagnosticSeverity::ERROR,
+                    ));
+                }
+                match item {
+                    Item::Binding(binding) => {
+                        if binding.signature.is_some() {
+                            return;
+                        }
+                        let sig = match env.scope.names.get(&binding.name.value) {
+                            Some(idx) if res.is_ok() && !env.placeholders.contains(idx) => {
+                                Some(env.globals.lock()[*idx].signature())
+                            }
+                            // A binding with no arguments is bound to the value it leaves on top
+                            _ => (env.words_signature(binding.words.clone()))
+                                .filter(|sig| sig.args > 0 || res.is_ok())
+                                .map(|sig| {
+                                    if sig.args == 0 {
+                                        Signature::new(0, 1)
+                                    } else {
+                                        sig
+                                    }
+                                }),
+                        };
+                        if let Some(sig) = sig {
+                            (analysis.inlay_hints)
+                                .push(hint(binding.name.span.end, sig.to_string()));
+                        }
+                    }
+                    Item::Words(words) => {
+                        height = height
+                            .zip(env.words_signature(words.clone()))
+                            .map(|(height, sig)| height.saturating_sub(sig.args) + sig.outputs);
+                        let last = (words.iter())
+                            .rev()
+                            .find(|word| !matches!(word.value, Word::Spaces | Word::Comment(_)));
+                        if let Some((last, height)) = last.zip(height) {
+                            (analysis.inlay_hints)
+                                .push(hint(last.span.end, format!("stack: {height}")));
+                        }
+                    }
+                    _ => {}
+                }
+            });
+            uiua_diagnostics = env.take_diagnostics().into_iter().collect();
+        }
+        for diag in uiua_diagnostics {
+            let Span::Code(span) = &diag.span else {
+                continue;
+            };
+            let severity = match diag.kind {
+                DiagnosticKind::Warning => DiagnosticSeverity::WARNING,
+                DiagnosticKind::Advice => DiagnosticSeverity::INFORMATION,
+                DiagnosticKind::Style => DiagnosticSeverity::HINT,
+            };
+            (analysis.diagnostics).push(diagnostic(span, diag.message, severity));
+        }
+        let globals = env.globals.lock();
+        for (name, &idx) in &env.scope.names {
+            if CONSTANTS.iter().any(|c| c.name == name.as_ref()) {
+                continue;
+            }
+            let sig = (!env.placeholders.contains(&idx)).then(|| BindingSig {
+                is_function: globals[idx].as_function().is_some(),
+                signature: globals[idx].signature(),
+            });
+            analysis.bindings.insert(name.clone(), sig);
+        }
+        analysis
+    }
+
     pub struct BindingInfo {
         pub span: CodeSpan,
         pub comment: Option<String>,
@@ -220,6 +632,187 @@ mod server {
         docs: DashMap<Url, LspDoc>,
     }
 
+    impl Backend {
+        /// Get the symbols of a document, reading it from disk if it is not open
+        fn symbols(&self, uri: &Url) -> Option<Arc<SymbolIndex>> {
+            if let Some(doc) = self.docs.get(uri) {
+                return Some(doc.symbols.clone());
+            }
+            let input = fs::read_to_string(uri.to_file_path().ok()?).ok()?;
+            Some(SymbolIndex::new(&input).into())
+        }
+        /// Follow an import to the definition of the imported binding
+        fn imported_definition(
+            &self,
+            uri: &Url,
+            import: &ImportedBinding,
+        ) -> Option<(Url, Sp<Ident>)> {
+            let target = import_uri(uri, &import.path)?;
+            let def = (self.symbols(&target)?.definitions.iter().rev())
+                .find(|def| *def.value == *import.name.value)?
+                .clone();
+            Some((target, def))
+        }
+        /// Find the definition of the binding at a position
+        ///
+        /// If `follow_imports` is set, bindings that are imported from other files
+        /// resolve to their definitions in those files.
+        fn symbol_at(
+            &self,
+            uri: &Url,
+            (line, col): (usize, usize),
+            follow_imports: bool,
+        ) -> Option<(Url, Sp<Ident>)> {
+            let symbols = self.symbols(uri)?;
+            if let Some(import) = symbols.import_name_at(line, col) {
+                return self.imported_definition(uri, import);
+            }
+            let def = symbols.definition_at(line, col)?;
+            if let Some(import) = symbols.import_of(def).filter(|_| follow_imports) {
+                if let Some(imported) = self.imported_definition(uri, import) {
+                    return Some(imported);
+                }
+            }
+            Some((uri.clone(), symbols.definition(def)?.clone()))
+        }
+        /// Find every span that refers to a binding, including imports of it in other files
+        fn references(
+            &self,
+            target: &Url,
+            def: &Sp<Ident>,
+            include_declaration: bool,
+        ) -> Vec<(Url, CodeSpan)> {
+            let mut refs = Vec::new();
+            if include_declaration {
+                refs.push((target.clone(), def.span.clone()));
+            }
+            if let Some(symbols) = self.symbols(target) {
+                let uses = symbols.references_to(&def.span);
+                refs.extend(uses.map(|r| (target.clone(), r.span.clone())));
+            }
+            let target_path = canonical_path(target);
+            for uri in self.importer_candidates(target) {
+                let Some(symbols) = self.symbols(&uri) else {
+                    continue;
+                };
+                for import in &symbols.imports {
+                    if *import.name.value == *def.value
+                        && import_uri(&uri, &import.path).and_then(|uri| canonical_path(&uri))
+                            == target_path
+                    {
+                        refs.push((uri.clone(), import.name.span.clone()));
+                    }
+                }
+            }
+            refs
+        }
+        /// Get the documents that may import from a document
+        ///
+        /// These are the open documents and the other files in its directory
+        fn importer_candidates(&self, target: &Url) -> BTreeSet<Url> {
+            let mut uris: BTreeSet<Url> = self.docs.iter().map(|doc| doc.key().clone()).collect();
+            let dir =
+                (target.to_file_path().ok()).and_then(|path| path.parent().map(PathBuf::from));
+            for entry in dir
+                .and_then(|dir| fs::read_dir(dir).ok())
+                .into_iter()
+                .flatten()
+            {
+                let Ok(entry) = entry else {
+                    continue;
+                };
+                let path = entry.path();
+                if path.extension().is_some_and(|ext| ext == "ua") {
+                    if let Ok(uri) = Url::from_file_path(path) {
+                        uris.insert(uri);
+                    }
+                }
+            }
+            uris
+        }
+    }
+
+    /// A backend that does not allow any system interaction
+    struct SandboxSys;
+
+    impl SysBackend for SandboxSys {
+        fn any(&self) -> &dyn Any {
+            self
+        }
+    }
+
+    /// Get the span and message of an error in the document itself
+    ///
+    /// Errors in other files are ignored
+    fn error_span(error: &UiuaError) -> Option<(CodeSpan, String)> {
+        let (span, message) = match error {
+            UiuaError::Traced { error, .. } | UiuaError::Fill(error) => return error_span(error),
+            UiuaError::Run(error) => (&error.span, error.value.clone()),
+            UiuaError::Throw(value, span) => (span, value.to_string()),
+            UiuaError::Break(_, span) => (span, error.to_string()),
+            _ => return None,
+        };
+        match span {
+            Span::Code(span) if span.path.is_none() => Some((span.clone(), message)),
+            _ => None,
+        }
+    }
+
+    /// Describe the operands a modifier expects
+    fn operand_descriptions(prim: Primitive) -> Vec<&'static str> {
+        use Primitive::*;
+        match prim {
+            Reduce | Scan | Table | Cross => vec!["f |2.1"],
+            Fold => vec!["f |n+1.n"],
+            Group | Partition => vec!["f |0.0, |1.1 or |2.1"],
+            Repeat => vec!["f |1.1 unless the count is known"],
+            Invert => vec!["f invertible"],
+            Under => vec!["f invertible", "g"],
+            Fill => vec!["fill value", "f"],
+            If => vec!["true branch", "false branch"],
+            Try => vec!["f |a.o", "handler |a+1.o"],
+            Level => vec!["ranks", "f"],
+            Bind => vec!["f", "g"],
+            Fork | Bracket => vec!["f", "g"],
+            prim => vec!["f"; prim.modifier_args().unwrap_or(0) as usize],
+        }
+    }
+
+    /// Resolve an import path relative to the importing document
+    fn import_uri(uri: &Url, path: &str) -> Option<Url> {
+        let dir = uri.to_file_path().ok()?.parent()?.to_path_buf();
+        Url::from_file_path(dir.join(path)).ok()
+    }
+
+    fn canonical_path(uri: &Url) -> Option<PathBuf> {
+        let path = uri.to_file_path().ok()?;
+        Some(path.canonicalize().unwrap_or(path))
+    }
+
+    /// Find a document containing references to a binding that already binds its new name
+    pub(super) fn rename_conflict(
+        refs: &[(Url, CodeSpan)],
+        new_name: &str,
+        symbols: impl Fn(&Url) -> Option<Arc<SymbolIndex>>,
+    ) -> Option<Url> {
+        let uris: BTreeSet<&Url> = refs.iter().map(|(uri, _)| uri).collect();
+        uris.into_iter()
+            .find(|uri| {
+                symbols(uri).is_some_and(|symbols| {
+                    (symbols.definitions.iter()).any(|def| *def.value == *new_name)
+                })
+            })
+            .cloned()
+    }
+
+    /// Check if a name can be used for a binding without being formatted into a primitive
+    fn is_valid_binding_name(name: &str) -> bool {
+        !name.is_empty()
+            && name.chars().all(is_ident_char)
+            && Primitive::from_format_name(name).is_none()
+            && Primitive::from_format_name_multi(name).is_none()
+    }
+
     #[tower_lsp::async_trait]
     impl LanguageServer for Backend {
         async fn initialize(&self, _params: InitializeParams) -> Result<InitializeResult> {
@@ -229,6 +822,26 @@ mod server {
                         TextDocumentSyncKind::FULL,
                     )),
                     hover_provider: Some(HoverProviderCapability::Simple(true)),
+                    code_action_provider: Some(CodeActionProviderCapability::Simple(true)),
+                    completion_provider: Some(CompletionOptions {
+                        trigger_characters: Some(vec!["&".into()]),
+                        ..Default::default()
+                    }),
+                    signature_help_provider: Some(SignatureHelpOptions {
+                        trigger_characters: Some(
+                            (Primitive::non_deprecated())
+                                .filter(|prim| prim.modifier_args().is_some())
+                                .filter_map(|prim| prim.glyph())
+                                .map(String::from)
+                                .chain(["(".into(), " ".into()])
+                                .collect(),
+                        ),
+                        ..Default::default()
+                    }),
+                    inlay_hint_provider: Some(OneOf::Left(true)),
+                    definition_provider: Some(OneOf::Left(true)),
+                    references_provider: Some(OneOf::Left(true)),
+                    rename_provider: Some(OneOf::Left(true)),
                     document_formatting_provider: Some(OneOf::Left(true)),
                     semantic_tokens_provider: Some(
                         SemanticTokensServerCapabilities::SemanticTokensOptions(
@@ -260,17 +873,69 @@ mod server {
         }
 
         async fn did_open(&self, param: DidOpenTextDocumentParams) {
-            self.docs.insert(
-                param.text_document.uri,
-                LspDoc::new(param.text_document.text),
-            );
+            let doc = LspDoc::new(param.text_document.text);
+            let diagnostics = doc.analysis.diagnostics.clone();
+            self.docs.insert(param.text_document.uri.clone(), doc);
+            self.client
+                .publish_diagnostics(
+                    param.text_document.uri,
+                    diagnostics,
+                    Some(param.text_document.version),
+                )
+                .await;
         }
 
         async fn did_change(&self, params: DidChangeTextDocumentParams) {
-            self.docs.insert(
-                params.text_document.uri,
-                LspDoc::new(params.content_changes[0].text.clone()),
-            );
+            let doc = LspDoc::new(params.content_changes[0].text.clone());
+            let diagnostics = doc.analysis.diagnostics.clone();
+            self.docs.insert(params.text_document.uri.clone(), doc);
+            self.client
+                .publish_diagnostics(
+                    params.text_document.uri,
+                    diagnostics,
+                    Some(params.text_document.version),
+                )
+                .await;
+        }
+
+        async fn code_action(
+            &self,
+            params: CodeActionParams,
+        ) -> Result<Option<CodeActionResponse>> {
+            let uri = params.text_document.uri;
+            let Some(doc) = self.docs.get(&uri) else {
+                return Ok(None);
+            };
+            let mut actions = Vec::new();
+            for sp in &doc.spans {
+                let SpanKind::Primitive(prim) = sp.value else {
+                    continue;
+                };
+                let Some(replacement) = prim.deprecation_replacement() else {
+                    continue;
+                };
+                let range = uiua_span_to_lsp(&sp.span);
+                if range.end < params.range.start || params.range.end < range.start {
+                    continue;
+                }
+                let diagnostics = (params.context.diagnostics.iter())
+                    .filter(|diag| diag.range == range)
+                    .cloned()
+                    .collect();
+                let edit = TextEdit::new(range, replacement.clone());
+                actions.push(CodeActionOrCommand::CodeAction(CodeAction {
+                    title: format!("Replace deprecated {prim} with {replacement}"),
+                    kind: Some(CodeActionKind::QUICKFIX),
+                    diagnostics: Some(diagnostics),
+                    edit: Some(WorkspaceEdit {
+                        changes: Some(HashMap::from([(uri.clone(), vec![edit])])),
+                        ..Default::default()
+                    }),
+                    is_preferred: Some(true),
+                    ..Default::default()
+                }));
+            }
+            Ok(Some(actions))
         }
 
         async fn hover(&self, params: HoverParams) -> Result<Option<Hover>> {
@@ -350,6 +1015,257 @@ mod server {
             }))
         }
 
+        async fn completion(&self, params: CompletionParams) -> Result<Option<CompletionResponse>> {
+            let position = params.text_document_position;
+            let Some(doc) = self.docs.get(&position.text_document.uri) else {
+                return Ok(None);
+            };
+            let doc = &*doc;
+            let (line, col) = lsp_pos_to_uiua(position.position);
+
+            // Find the name being typed
+            let line_chars: Vec<char> = (doc.input.lines().nth(line - 1).unwrap_or(""))
+                .chars()
+                .take(col - 1)
+                .collect();
+            let mut start = line_chars.len();
+            while start > 0 && is_ident_char(line_chars[start - 1]) {
+                start -= 1;
+            }
+            if start > 0 && line_chars[start - 1] == '&' {
+                start -= 1;
+            }
+            let prefix: String = line_chars[start..].iter().collect();
+            if prefix.is_empty() {
+                return Ok(None);
+            }
+            let lower = prefix.to_lowercase();
+            let range = Range::new(
+                Position::new(position.position.line, start as u32),
+                position.position,
+            );
+            let item =
+                |label: String, insert: String, kind, detail: String, doc: String| CompletionItem {
+                    filter_text: Some(label.clone()),
+                    text_edit: Some(CompletionTextEdit::Edit(TextEdit::new(range, insert))),
+                    label,
+                    kind: Some(kind),
+                    detail: Some(detail),
+                    documentation: (!doc.is_empty()).then_some(Documentation::String(doc)),
+                    ..Default::default()
+                };
+            let mut items = Vec::new();
+
+            if prefix.starts_with('&') {
+                // System functions
+                for op in SysOp::ALL {
+                    if op.name().starts_with(&lower) {
+                        let doc =
+                            (op.doc().map(|doc| doc.short_text().into_owned())).unwrap_or_default();
+                        items.push(item(
+                            op.name().into(),
+                            op.name().into(),
+                            CompletionItemKind::FUNCTION,
+                            op.long_name().into(),
+                            doc,
+                        ));
+                    }
+                }
+                return Ok(Some(CompletionResponse::Array(items)));
+            }
+
+            // Primitives
+            for prim in Primitive::non_deprecated() {
+                if let Primitive::Sys(_) = prim {
+                    continue;
+                }
+                let Some(name) = prim.name().filter(|name| name.starts_with(&lower)) else {
+                    continue;
+                };
+                let insert = (prim.glyph().map(String::from)).unwrap_or_else(|| name.into());
+                let doc = (prim.doc().map(|doc| doc.short_text().into_owned())).unwrap_or_default();
+                items.push(item(
+                    name.into(),
+                    insert.clone(),
+                    CompletionItemKind::FUNCTION,
+                    insert,
+                    doc,
+                ));
+            }
+
+            // Constants
+            for def in &*CONSTANTS {
+                if def.name.to_lowercase().starts_with(&lower) {
+                    items.push(item(
+                        def.name.into(),
+                        def.name.into(),
+                        CompletionItemKind::CONSTANT,
+                        def.value.show(),
+                        def.doc.into(),
+                    ));
+                }
+            }
+
+            // Bindings defined before the cursor
+            let mut seen = BTreeSet::new();
+            for def in doc.symbols.definitions.iter().rev() {
+                if def.span.start.line > line
+                    || !def.value.to_lowercase().starts_with(&lower)
+                    || !seen.insert(def.value.clone())
+                {
+                    continue;
+                }
+                let (kind, detail) = match doc.analysis.bindings.get(&def.value) {
+                    Some(Some(sig)) => {
+                        let kind = if sig.is_function {
+                            CompletionItemKind::FUNCTION
+                        } else {
+                            CompletionItemKind::VARIABLE
+                        };
+                        (kind, sig.signature.to_string())
+                    }
+                    _ => (CompletionItemKind::VARIABLE, String::new()),
+                };
+                let name = def.value.to_string();
+                items.push(item(name.clone(), name, kind, detail, String::new()));
+            }
+
+            Ok(Some(CompletionResponse::Array(items)))
+        }
+
+        async fn signature_help(
+            &self,
+            params: SignatureHelpParams,
+        ) -> Result<Option<SignatureHelp>> {
+            let position = params.text_document_position_params;
+            let Some(doc) = self.docs.get(&position.text_document.uri) else {
+                return Ok(None);
+            };
+            let (line, col) = lsp_pos_to_uiua(position.position);
+            let Some(call) = modifier_at(&doc.input, line, col) else {
+                return Ok(None);
+            };
+            let prim = call.modifier;
+            let mut label = match (prim.glyph(), prim.name()) {
+                (Some(glyph), Some(name)) => format!("{glyph} {name}"),
+                (_, Some(name)) => name.into(),
+                _ => prim.to_string(),
+            };
+            let mut parameters = Vec::new();
+            for desc in operand_descriptions(prim) {
+                label.push(' ');
+                let start = label.chars().count() as u32;
+                label.push_str(desc);
+                let end = label.chars().count() as u32;
+                parameters.push(ParameterInformation {
+                    label: ParameterLabel::LabelOffsets([start, end]),
+                    documentation: None,
+                });
+            }
+            let count = parameters.len();
+            let mut documentation = format!(
+                "{} takes {count} function{}",
+                prim.name().unwrap_or_default(),
+                if count == 1 { "" } else { "s" }
+            );
+            if let Some(doc) = prim.doc() {
+                documentation.push_str("\n\n");
+                documentation.push_str(&doc.short_text());
+            }
+            Ok(Some(SignatureHelp {
+                signatures: vec![SignatureInformation {
+                    label,
+                    documentation: Some(Documentation::String(documentation)),
+                    parameters: Some(parameters),
+                    active_parameter: None,
+                }],
+                active_signature: Some(0),
+                active_parameter: Some(call.operand as u32),
+            }))
+        }
+
+        async fn inlay_hint(&self, params: InlayHintParams) -> Result<Option<Vec<InlayHint>>> {
+            let Some(doc) = self.docs.get(&params.text_document.uri) else {
+                return Ok(None);
+            };
+            let range = params.range;
+            Ok(Some(
+                (doc.analysis.inlay_hints.iter())
+                    .filter(|hint| range.start <= hint.position && hint.position <= range.end)
+                    .cloned()
+                    .collect(),
+            ))
+        }
+
+        async fn goto_definition(
+            &self,
+            params: GotoDefinitionParams,
+        ) -> Result<Option<GotoDefinitionResponse>> {
+            let position = params.text_document_position_params;
+            let pos = lsp_pos_to_uiua(position.position);
+            Ok(self
+                .symbol_at(&position.text_document.uri, pos, true)
+                .map(|(uri, def)| {
+                    GotoDefinitionResponse::Scalar(Location::new(uri, uiua_span_to_lsp(&def.span)))
+                }))
+        }
+
+        async fn references(&self, params: ReferenceParams) -> Result<Option<Vec<Location>>> {
+            let position = params.text_document_position;
+            let pos = lsp_pos_to_uiua(position.position);
+            let Some((target, def)) = self.symbol_at(&position.text_document.uri, pos, false)
+            else {
+                return Ok(None);
+            };
+            let refs = self.references(&target, &def, params.context.include_declaration);
+            Ok(Some(
+                (refs.into_iter())
+                    .map(|(uri, span)| Location::new(uri, uiua_span_to_lsp(&span)))
+                    .collect(),
+            ))
+        }
+
+        async fn rename(&self, params: RenameParams) -> Result<Option<WorkspaceEdit>> {
+            let new_name = params.new_name;
+            if !is_valid_binding_name(&new_name) {
+                return Err(Error::invalid_params(format!(
+                    "`{new_name}` is not a valid binding name"
+                )));
+            }
+            let position = params.text_document_position;
+            let pos = lsp_pos_to_uiua(position.position);
+            let Some((target, def)) = self.symbol_at(&position.text_document.uri, pos, false)
+            else {
+                return Ok(None);
+            };
+            let refs = self.references(&target, &def, true);
+            // Renaming to an existing name could change what other references resolve to
+            if let Some(uri) = rename_conflict(&refs, &new_name, |uri| self.symbols(uri)) {
+                let file = if uri == position.text_document.uri {
+                    "this file".into()
+                } else {
+                    (uri.path_segments()
+                        .and_then(|mut segments| segments.next_back()))
+                    .unwrap_or(uri.as_str())
+                    .to_string()
+                };
+                return Err(Error::invalid_params(format!(
+                    "`{new_name}` is already bound in {file}"
+                )));
+            }
+            let mut changes: HashMap<Url, Vec<TextEdit>> = HashMap::new();
+            for (uri, span) in refs {
+                changes.entry(uri).or_default().push(TextEdit {
+                    range: uiua_span_to_lsp(&span),
+                    new_text: new_name.clone(),
+                });
+            }
+            Ok(Some(WorkspaceEdit {
+                changes: Some(changes),
+                ..Default::default()
+            }))
+        }
+
         async fn formatting(
             &self,
             params: DocumentFormattingParams,
@@ -493,3 +1409,166 @@ mod server {
         uiua_locs_to_lsp(span.start, span.end)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn symbol_index() {
+        let input = "\
+m ← &i \"lib.ua\"
+X ← use \"Foo\" m
+A ← 1
+B ← +A A
+---
+A ← 2
++A B
+---
+X";
+        let index = SymbolIndex::new(input);
+        assert_eq!(index.definitions.len(), 5);
+
+        // Uses resolve to the nearest preceding definition
+        let outer_a = index.definition_at(4, 6).unwrap();
+        assert_eq!(outer_a.start.line, 3);
+        assert_eq!(index.references_to(outer_a).count(), 2);
+        let inner_a = index.definition_at(7, 2).unwrap();
+        assert_eq!(inner_a.start.line, 6);
+
+        // Test scopes can see the scope above them
+        assert_eq!(index.definition_at(7, 4).unwrap().start.line, 4);
+
+        // Imports
+        let x = index.definition_at(9, 1).unwrap();
+        let import = index.import_of(x).unwrap();
+        assert_eq!(import.path, "lib.ua");
+        assert_eq!(import.name.value, "Foo");
+        assert_eq!(import.name.span.as_str(), "Foo");
+        assert!(index.import_name_at(2, 10).is_some());
+    }
+
+    #[test]
+    fn modifier_operands() {
+        let input = "∧(+×) 0 [1 2]\n⊃(+1)(-1 4\n⊃+\n/+";
+        let call =
+            |line, col| modifier_at(input, line, col).map(|call| (call.modifier, call.operand));
+        assert_eq!(call(1, 4), Some((Primitive::Fold, 0)));
+        assert_eq!(call(1, 8), None);
+        assert_eq!(call(2, 3), Some((Primitive::Fork, 0)));
+        assert_eq!(call(2, 8), Some((Primitive::Fork, 1)));
+        assert_eq!(call(3, 3), Some((Primitive::Fork, 1)));
+        assert_eq!(call(4, 2), Some((Primitive::Reduce, 0)));
+    }
+
+    #[cfg(feature = "lsp")]
+    #[test]
+    fn inlay_hints() {
+        use tower_lsp::lsp_types::{InlayHintLabel, Position};
+
+        let hints: Vec<(Position, String)> =
+            (server::analyze("F ← +1\nx ← 5\ny ← +x 1\nF x 2\n&p 3\n+").inlay_hints)
+                .into_iter()
+                .map(|hint| match hint.label {
+                    InlayHintLabel::String(label) => (hint.position, label),
+                    InlayHintLabel::LabelParts(_) => panic!("label parts"),
+                })
+                .collect();
+        assert_eq!(
+            hints,
+            [
+                (Position::new(0, 1), "|1.1".into()),
+                (Position::new(1, 1), "|0.1".into()),
+                // The value is not known without running the code, but its signature is
+                (Position::new(2, 1), "|0.1".into()),
+                (Position::new(3, 5), "stack: 2".into()),
+                (Position::new(4, 4), "stack: 2".into()),
+                (Position::new(5, 1), "stack: 1".into()),
+            ]
+        );
+    }
+
+    #[cfg(feature = "lsp")]
+    #[test]
+    fn analysis_bindings() {
+        use crate::function::Signature;
+
+        // The document is compiled but not run, so this does not loop forever
+        let analysis = server::analyze(
+            "F ← +1\nX ← 5\nY ← ⍥(+1)1e12 0\nG ← /Y\nH ← ≡F\n⍥(+1)1e12 0\nZ ← Unknown",
+        );
+        let sig =
+            |name: &str| (analysis.bindings[name]).map(|sig| (sig.is_function, sig.signature));
+        assert_eq!(sig("F"), Some((true, Signature::new(1, 1))));
+        assert_eq!(sig("X"), Some((false, Signature::new(0, 1))));
+        assert_eq!(sig("H"), Some((true, Signature::new(1, 1))));
+        // Values that are only known by running the code
+        assert_eq!(sig("Y"), None);
+        assert_eq!(sig("G"), None);
+        // Bindings with errors are not bound
+        assert!(!analysis.bindings.contains_key("Z"));
+    }
+
+    #[cfg(feature = "lsp")]
+    #[test]
+    fn rename_conflict() {
+        use std::{collections::HashMap, sync::Arc};
+
+        use tower_lsp::lsp_types::Url;
+
+        let lib = Url::parse("file:///lib.ua").unwrap();
+        let main = Url::parse("file:///main.ua").unwrap();
+        let docs: HashMap<Url, Arc<SymbolIndex>> = [
+            (lib.clone(), SymbolIndex::new("Foo ← 1\nBar ← 2").into()),
+            (
+                main.clone(),
+                SymbolIndex::new("X ← use \"Foo\" &i \"lib.ua\"\nBaz ← 3").into(),
+            ),
+        ]
+        .into();
+        let index = &docs[&lib];
+        let foo = &index.definitions[0];
+        let import = &docs[&main].imports[0];
+        let refs = [
+            (lib.clone(), foo.span.clone()),
+            (main.clone(), import.name.span.clone()),
+        ];
+        let conflict = |name| server::rename_conflict(&refs, name, |uri| docs.get(uri).cloned());
+        assert_eq!(conflict("Qux"), None);
+        assert_eq!(conflict("Bar"), Some(lib.clone()));
+        // Names bound in an importing file conflict too
+        assert_eq!(conflict("Baz"), Some(main.clone()));
+    }
+
+    #[cfg(feature = "lsp")]
+    #[test]
+    fn document_diagnostics() {
+        use tower_lsp::lsp_types::{DiagnosticSeverity, Position};
+
+        let diagnostics = server::analyze("+1 (").diagnostics;
+        assert!(!diagnostics.is_empty());
+        assert!((diagnostics.iter()).all(|diag| diag.severity == Some(DiagnosticSeverity::ERROR)));
+
+        // Deprecation warnings, but no errors from system functions, which are not run
+        let diagnostics = server::analyze("roll 1 2 3\n&p 5").diagnostics;
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].severity, Some(DiagnosticSeverity::WARNING));
+        assert_eq!(diagnostics[0].range.start, Position::new(0, 0));
+
+        // Every item is checked, and nothing is run
+        let diagnostics = server::analyze("+1 Foo\n⍥(+1)1e12 0\nF ← /5\n+1 Bar").diagnostics;
+        let errors: Vec<_> = (diagnostics.iter())
+            .map(|diag| (diag.range.start, diag.severity))
+            .collect();
+        let error = Some(DiagnosticSeverity::ERROR);
+        assert_eq!(
+            errors,
+            [
+                (Position::new(0, 3), error),
+                (Position::new(2, 0), error),
+                (Position::new(3, 3), error),
+            ]
+        );
+        assert_eq!(diagnostics[0].range.end, Position::new(0, 6));
+    }
+}
@@ -17,9 +17,13 @@ use instant::Instant;
 use notify::{EventKind, RecursiveMode, Watcher};
 use once_cell::sync::Lazy;
 use parking_lot::Mutex;
+use rustyline::{error::ReadlineError, DefaultEditor};
 use uiua::{
-    format::{format_file, FormatConfig, FormatConfigSource},
+    debug::{line_span, DebugAction, DebugHandler, DebugState, PauseReason},
+    format::{format_file, format_str, FormatConfig, FormatConfigSource},
+    lex::{lex, AsciiToken, Token},
     run::RunMode,
+    snapshot::{is_snapshot, Snapshot},
     Uiua, UiuaError, UiuaResult,
 };
 
@@ -47,6 +51,20 @@ fn main() {
     }
 }
 
+/// Read a snapshot, or `None` if the file is not one
+fn read_snapshot(path: &Path) -> UiuaResult<Option<Snapshot>> {
+    let bytes = fs::read(path).map_err(|e| UiuaError::Load(path.into(), e.into()))?;
+    if !is_snapshot(&bytes) {
+        return Ok(None);
+    }
+    Snapshot::from_bytes(&bytes).map(Some).map_err(|e| {
+        UiuaError::Load(
+            path.into(),
+            io::Error::new(io::ErrorKind::InvalidData, e).into(),
+        )
+    })
+}
+
 static WATCH_CHILD: Lazy<Mutex<Option<Child>>> = Lazy::new(Default::default);
 
 fn run() -> UiuaResult {
@@ -85,6 +103,11 @@ fn run() -> UiuaResult {
                 formatter_options,
                 no_update,
                 mode,
+                import_cache,
+                profile,
+                profile_out,
+                threads,
+                no_optimize,
                 #[cfg(feature = "audio")]
                 audio_options,
                 args,
@@ -103,7 +126,8 @@ fn run() -> UiuaResult {
                         }
                     }
                 };
-                if !no_format {
+                let snapshot = read_snapshot(&path)?;
+                if !no_format && snapshot.is_none() {
                     let config = FormatConfig::from_source(
                         formatter_options.format_config_source,
                         Some(&path),
@@ -117,11 +141,33 @@ fn run() -> UiuaResult {
                     .with_mode(mode)
                     .with_file_path(&path)
                     .with_args(args)
+                    .with_optimizations(!no_optimize)
                     .print_diagnostics(true);
-                rt.load_file(path)?;
+                if let Some(dir) = import_cache {
+                    rt = rt.with_import_cache(dir);
+                }
+                if profile || profile_out.is_some() {
+                    rt = rt.with_profiler();
+                }
+                if let Some(threads) = threads {
+                    rt = rt.with_max_threads(threads);
+                }
+                if let Some(snapshot) = snapshot {
+                    rt.load_snapshot(&snapshot);
+                } else {
+                    rt.load_file(&path)?;
+                }
                 for value in rt.take_stack() {
                     println!("{}", value.show());
                 }
+                if let Some(profiler) = rt.profiler() {
+                    eprint!("{}", profiler.report());
+                    if let Some(folded_path) = profile_out {
+                        fs::write(&folded_path, profiler.folded_stacks())
+                            .map_err(|e| UiuaError::Load(folded_path.clone(), e.into()))?;
+                        eprintln!("Folded stacks written to {}", folded_path.display());
+                    }
+                }
             }
             App::Eval {
                 code,
@@ -186,8 +232,102 @@ fn run() -> UiuaResult {
                     eprintln!("Error watching file: {e}");
                 }
             }
+            App::Debug {
+                path,
+                breakpoints,
+                args,
+            } => {
+                let path = if let Some(path) = path {
+                    path
+                } else {
+                    match working_file_path() {
+                        Ok(path) => path,
+                        Err(e) => {
+                            eprintln!("{}", e);
+                            return Ok(());
+                        }
+                    }
+                };
+                let input = fs::read_to_string(&path)
+                    .map_err(|e| UiuaError::Load(path.clone(), e.into()))?;
+                let mut rt = Uiua::with_native_sys()
+                    .with_file_path(&path)
+                    .with_args(args)
+                    .print_diagnostics(true)
+                    .with_debugger(CliDebugger::new(&path, &input)?);
+                for line in breakpoints {
+                    match line_span(&input, Some(&path), line) {
+                        Some(span) => rt.add_breakpoint(span),
+                        None => eprintln!("Line {line} does not exist"),
+                    }
+                }
+                rt.load_str_path(&input, &path)?;
+                for value in rt.take_stack() {
+                    println!("{}", value.show());
+                }
+            }
+            App::Snapshot { path, output, args } => {
+                let path = if let Some(path) = path {
+                    path
+                } else {
+                    match working_file_path() {
+                        Ok(path) => path,
+                        Err(e) => {
+                            eprintln!("{}", e);
+                            return Ok(());
+                        }
+                    }
+                };
+                let mut rt = Uiua::with_native_sys()
+                    .with_file_path(&path)
+                    .with_args(args)
+                    .print_diagnostics(true);
+                let bytes = rt
+                    .snapshot_file(&path)?
+                    .to_bytes()
+                    .map_err(|e| UiuaError::Load(path.clone(), io::Error::other(e).into()))?;
+                let output = output.unwrap_or_else(|| path.with_extension("uiuas"));
+                fs::write(&output, bytes).map_err(|e| UiuaError::Load(output, e.into()))?;
+            }
+            App::Disasm { path } => {
+                let path = if let Some(path) = path {
+                    path
+                } else {
+                    match working_file_path() {
+                        Ok(path) => path,
+                        Err(e) => {
+                            eprintln!("{}", e);
+                            return Ok(());
+                        }
+                    }
+                };
+                let mut rt = Uiua::with_native_sys()
+                    .with_file_path(&path)
+                    .with_compile_only(true)
+                    .print_diagnostics(true);
+                rt.load_file(path)?;
+                print!("{}", rt.disassemble());
+            }
+            App::Repl {
+                formatter_options,
+                #[cfg(feature = "audio")]
+                audio_options,
+                args,
+            } => {
+                #[cfg(feature = "audio")]
+                setup_audio(audio_options);
+                let config =
+                    FormatConfig::from_source(formatter_options.format_config_source, None)?;
+                repl(config, args);
+            }
             #[cfg(feature = "lsp")]
             App::Lsp => uiua::lsp::run_server(),
+            #[cfg(feature = "dap")]
+            App::Dap { port } => {
+                if let Err(e) = uiua::dap::run_server(port) {
+                    eprintln!("Error running the debug adapter: {e}");
+                }
+            }
         },
         Err(e) if e.kind() == ErrorKind::DisplayHelpOnMissingArgumentOrSubcommand => {
             show_update_message();
@@ -426,6 +566,19 @@ enum App {
         no_update: bool,
         #[clap(long, help = "Run the file in a specific mode")]
         mode: Option<RunMode>,
+        #[clap(long, help = "Cache snapshots of imports in a directory")]
+        import_cache: Option<PathBuf>,
+        #[clap(long, help = "Print where time was spent")]
+        profile: bool,
+        #[clap(
+            long,
+            help = "Profile the run and write its folded stacks to a file for flame graph tools"
+        )]
+        profile_out: Option<PathBuf>,
+        #[clap(long, help = "The most threads to split loops across, or 1 to disable")]
+        threads: Option<usize>,
+        #[clap(long, help = "Don't optimize the compiled code")]
+        no_optimize: bool,
         #[cfg(feature = "audio")]
         #[clap(flatten)]
         audio_options: AudioOptions,
@@ -462,6 +615,40 @@ enum App {
         #[clap(trailing_var_arg = true)]
         args: Vec<String>,
     },
+    #[clap(about = "Step through a file in the debugger")]
+    Debug {
+        path: Option<PathBuf>,
+        #[clap(short = 'b', long = "break", help = "Set a breakpoint on a line")]
+        breakpoints: Vec<usize>,
+        #[clap(trailing_var_arg = true)]
+        args: Vec<String>,
+    },
+    #[clap(about = "Run a file and save a snapshot of its bindings and stack")]
+    Snapshot {
+        path: Option<PathBuf>,
+        #[clap(
+            short,
+            long,
+            help = "The output file (defaults to the path with a .uiuas extension)"
+        )]
+        output: Option<PathBuf>,
+        #[clap(trailing_var_arg = true)]
+        args: Vec<String>,
+    },
+    #[clap(
+        about = "Compile a file without running it and print the compiled code of its functions"
+    )]
+    Disasm { path: Option<PathBuf> },
+    #[clap(about = "Start an interactive session")]
+    Repl {
+        #[clap(flatten)]
+        formatter_options: FormatterOptions,
+        #[cfg(feature = "audio")]
+        #[clap(flatten)]
+        audio_options: AudioOptions,
+        #[clap(trailing_var_arg = true)]
+        args: Vec<String>,
+    },
     #[clap(about = "Format a uiua file or all files in the current directory")]
     Fmt {
         path: Option<PathBuf>,
@@ -471,6 +658,12 @@ enum App {
     #[cfg(feature = "lsp")]
     #[clap(about = "Run the Language Server")]
     Lsp,
+    #[cfg(feature = "dap")]
+    #[clap(about = "Run the Debug Adapter Protocol server")]
+    Dap {
+        #[clap(long, default_value_t = uiua::dap::DEFAULT_PORT, help = "The port to listen on")]
+        port: u16,
+    },
 }
 
 #[derive(clap::Args)]
@@ -512,6 +705,214 @@ fn setup_audio(options: AudioOptions) {
     }
 }
 
+fn repl(config: FormatConfig, args: Vec<String>) {
+    let mut rt = Uiua::with_native_sys()
+        .with_mode(RunMode::Normal)
+        .with_args(args)
+        .print_diagnostics(true);
+    let mut line_reader = match DefaultEditor::new() {
+        Ok(reader) => reader,
+        Err(e) => {
+            eprintln!("Failed to start the REPL: {e}");
+            return;
+        }
+    };
+    println!("Uiua {} (end with ctrl+D)", env!("CARGO_PKG_VERSION"));
+    loop {
+        // Read lines until all brackets and scopes are closed
+        let mut code = String::new();
+        loop {
+            let prompt = if code.is_empty() { "» " } else { "… " };
+            match line_reader.readline(prompt) {
+                Ok(line) => {
+                    if !code.is_empty() {
+                        code.push('\n');
+                    }
+                    code.push_str(&line);
+                    if !input_is_incomplete(&code) {
+                        break;
+                    }
+                }
+                Err(ReadlineError::Interrupted) if !code.is_empty() => {
+                    code.clear();
+                    break;
+                }
+                Err(ReadlineError::Interrupted | ReadlineError::Eof) => return,
+                Err(e) => {
+                    eprintln!("Failed to read input: {e}");
+                    return;
+                }
+            }
+        }
+        if code.trim().is_empty() {
+            continue;
+        }
+        _ = line_reader.add_history_entry(&code);
+
+        // Format names into glyphs
+        let formatted = match format_str(&code, &config) {
+            Ok(formatted) => formatted.output,
+            Err(e) => {
+                println!("{}", e.show(true));
+                continue;
+            }
+        };
+        if formatted.trim_end() != code.trim_end() {
+            println!("{}", formatted.trim_end().bright_black());
+        }
+
+        // Run and show the stack
+        if let Err(e) = rt.load_str(&formatted) {
+            println!("{}", e.show(true));
+        }
+        for value in rt.stack() {
+            println!("{}", value.show());
+        }
+    }
+}
+
+/// Check if some REPL input has unclosed brackets or scopes
+fn input_is_incomplete(input: &str) -> bool {
+    let (tokens, _) = lex(input, None);
+    let mut depth = 0isize;
+    let mut in_scope = false;
+    for token in tokens {
+        match token.value {
+            Token::Simple(
+                AsciiToken::OpenParen | AsciiToken::OpenBracket | AsciiToken::OpenCurly,
+            ) => depth += 1,
+            Token::Simple(
+                AsciiToken::CloseParen | AsciiToken::CloseBracket | AsciiToken::CloseCurly,
+            ) => depth -= 1,
+            Token::Simple(AsciiToken::TripleMinus | AsciiToken::TripleTilde) => {
+                in_scope = !in_scope
+            }
+            _ => {}
+        }
+    }
+    depth > 0 || in_scope
+}
+
+struct CliDebugger {
+    path: PathBuf,
+    input: String,
+    line_reader: DefaultEditor,
+    last_command: String,
+}
+
+const DEBUG_HELP: &str = "\
+Commands:
+  s, step       step into the next instruction
+  n, next       step over the next instruction
+  o, out        step out of the current function
+  c, continue   continue until a breakpoint
+  b [line]      set a breakpoint, or list breakpoints
+  d <line>      delete a breakpoint
+  st, stack     show the stack and temp stacks
+  bt, trace     show the call stack
+  q, quit       stop execution
+  h, help       show this message
+An empty line repeats the last command";
+
+impl CliDebugger {
+    fn new(path: &Path, input: &str) -> UiuaResult<Self> {
+        let line_reader = DefaultEditor::new()
+            .map_err(|e| UiuaError::Load(path.into(), io::Error::other(e).into()))?;
+        Ok(CliDebugger {
+            path: path.into(),
+            input: input.into(),
+            line_reader,
+            last_command: "step".into(),
+        })
+    }
+}
+
+impl DebugHandler for CliDebugger {
+    fn pause(&mut self, state: DebugState) -> DebugAction {
+        let frame = state.current_frame();
+        match &state.reason {
+            PauseReason::Breakpoint(span) => println!("{} {span}", "Breakpoint".bright_red()),
+            PauseReason::Step => {}
+        }
+        match frame.and_then(|frame| frame.span.as_ref()) {
+            Some(uiua::lex::Span::Code(span)) => {
+                let line = span.input.lines().nth(span.start.line - 1).unwrap_or("");
+                println!("{} {}", span.to_string().bright_black(), line);
+                let indent = span.to_string().chars().count() + span.start.col;
+                println!("{:indent$}{}", "", "^".bright_yellow());
+            }
+            _ => println!("{}", "<no span>".bright_black()),
+        }
+        if let Some(instr) = frame.and_then(|frame| frame.instr()) {
+            println!("{} {instr}", "next:".bright_black());
+        }
+        if let Some(top) = state.stack.last() {
+            println!("{} {}", "top:".bright_black(), top.show());
+        }
+        loop {
+            let command = match self.line_reader.readline("(debug) ") {
+                Ok(line) if line.trim().is_empty() => self.last_command.clone(),
+                Ok(line) => {
+                    _ = self.line_reader.add_history_entry(&line);
+                    line.trim().to_string()
+                }
+                Err(_) => return DebugAction::Stop,
+            };
+            self.last_command = command.clone();
+            let (name, arg) = command.split_once(' ').unwrap_or((&command, ""));
+            match name {
+                "s" | "step" => return DebugAction::StepInto,
+                "n" | "next" => return DebugAction::StepOver,
+                "o" | "out" => return DebugAction::StepOut,
+                "c" | "continue" => return DebugAction::Continue,
+                "q" | "quit" => return DebugAction::Stop,
+                "b" | "break" if arg.is_empty() => {
+                    let breakpoints = state.breakpoints.lock();
+                    if breakpoints.is_empty() {
+                        println!("No breakpoints");
+                    }
+                    for span in breakpoints.iter() {
+                        println!("{span}");
+                    }
+                }
+                "b" | "break" | "d" | "delete" => {
+                    let span = (arg.trim().parse().ok())
+                        .and_then(|line| line_span(&self.input, Some(&self.path), line));
+                    let Some(span) = span else {
+                        println!("Invalid line: {arg}");
+                        continue;
+                    };
+                    if name.starts_with('b') {
+                        state.breakpoints.lock().insert(span);
+                    } else if !state.breakpoints.lock().remove(&span) {
+                        println!("No breakpoint on line {arg}");
+                    }
+                }
+                "st" | "stack" => {
+                    for (name, stack) in [
+                        ("stack", state.stack),
+                        ("inline", state.inline_stack),
+                        ("under", state.under_stack),
+                    ] {
+                        println!("{}", format!("{name}:").bright_black());
+                        for value in stack.iter().rev() {
+                            println!("{}", value.show());
+                        }
+                    }
+                }
+                "bt" | "trace" => {
+                    for frame in state.frames.iter().rev() {
+                        let span = (frame.span.as_ref()).unwrap_or(&frame.call_span);
+                        println!("  in {} at {span}", frame.function.id);
+                    }
+                }
+                "h" | "help" => println!("{DEBUG_HELP}"),
+                _ => println!("Unknown command: {command}\n{DEBUG_HELP}"),
+            }
+        }
+    }
+}
+
 fn uiua_files() -> Vec<PathBuf> {
     fs::read_dir(".")
         .unwrap()
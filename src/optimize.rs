@@ -0,0 +1,457 @@
+//! Optimizations on compiled instructions
+//!
+//! Optimizations should not change what code does, only how fast it does it.
+//! Fused primitives keep the instructions they were made from,
+//! and run those instead when their fast path does not apply.
+//! Optimizations can be turned off with [`Uiua::with_optimizations`].
+
+use crate::{
+    algorithm::pervade::ChainStep,
+    function::{Function, FunctionId, Instr, Signature},
+    primitive::{PrimClass, Primitive},
+    value::Value,
+    Uiua, UiuaResult,
+};
+
+/// Push an instruction to some instructions being compiled
+///
+/// If the instruction and the previous instructions form some known pattern,
+/// they are replaced with something faster.
+pub(crate) fn push_instr(instrs: &mut Vec<Instr>, instr: Instr) {
+    use Primitive::*;
+    match (instrs.as_mut_slice(), instr) {
+        // Cosine
+        ([.., Instr::Prim(Eta, _), Instr::Prim(Add, _)], Instr::Prim(Sin, span)) => {
+            instrs.pop();
+            instrs.pop();
+            instrs.push(Instr::Prim(Cos, span));
+        }
+        // First reverse = last
+        ([.., Instr::Prim(top @ Reverse, _)], Instr::Prim(First, _)) => *top = Last,
+        // First rise = index of the first minimum
+        ([.., Instr::Prim(Rise, _)], instr @ Instr::Prim(First, _)) => {
+            fuse_top(instrs, instr, FirstRise, Signature::new(1, 1))
+        }
+        // Length of keep = sum of the counts
+        ([.., Instr::Prim(Keep, _)], instr @ Instr::Prim(Len, _)) => {
+            fuse_top(instrs, instr, LenKeep, Signature::new(2, 1))
+        }
+        // Stream windows through rows or reduce
+        (
+            [.., Instr::Prim(Windows, _), Instr::Push(f)],
+            Instr::Prim(prim @ (Rows | Reduce), span),
+        ) if f.as_function().is_some_and(|f| {
+            let sig = f.signature();
+            sig.outputs == 1 && sig.args == if prim == Rows { 1 } else { 2 }
+        }) =>
+        {
+            let f = instrs.pop().unwrap();
+            instrs.pop();
+            instrs.push(f);
+            let fused = if prim == Rows {
+                RowsWindows
+            } else {
+                ReduceWindows
+            };
+            instrs.push(Instr::Prim(fused, span));
+        }
+        // Push/pop elimination: values that are popped right after they are pushed
+        // Only pushes and constants are removed. Stack and pervasive primitives
+        // can fail, so removing them would hide their errors.
+        ([.., Instr::Push(_)], Instr::Prim(Pop, _)) => {
+            instrs.pop();
+        }
+        ([.., Instr::Prim(prim, _)], Instr::Prim(Pop, _))
+            if prim.class() == PrimClass::Constant =>
+        {
+            instrs.pop();
+        }
+        (_, instr) => instrs.push(instr),
+    }
+}
+
+/// Fuse the top instruction and the next one into a modifier primitive
+///
+/// The modifier's function is the original instructions,
+/// so that they can be run if the fused version does not apply.
+fn fuse_top(instrs: &mut Vec<Instr>, next: Instr, fused: Primitive, sig: Signature) {
+    let top = instrs.pop().unwrap();
+    let Instr::Prim(_, span) = top else {
+        unreachable!("only primitives are fused")
+    };
+    let chain = vec![top, next];
+    let ids = (chain.iter())
+        .filter_map(|instr| match instr {
+            Instr::Prim(prim, _) => Some(FunctionId::Primitive(*prim)),
+            _ => None,
+        })
+        .collect();
+    instrs.push(Instr::push(Function::new(
+        FunctionId::Composed(ids),
+        chain,
+        sig,
+    )));
+    instrs.push(Instr::Prim(fused, span));
+}
+
+/// Optimize the instructions of a fully compiled function
+pub(crate) fn optimize_instrs(instrs: Vec<Instr>, env: &Uiua) -> Vec<Instr> {
+    let instrs = fold_constants(instrs, env);
+    fuse_pervasive_chains(instrs)
+}
+
+/// Evaluate the instructions at the start of a function that only operate on constants
+///
+/// Only primitives whose outputs are no bigger than their inputs are folded,
+/// so folding never builds a big array that the code might never have built.
+/// If evaluation fails, the instructions are left alone so that the error
+/// is reported when the code is run.
+fn fold_constants(mut instrs: Vec<Instr>, env: &Uiua) -> Vec<Instr> {
+    let mut depth: usize = 0;
+    let mut array_starts = Vec::new();
+    let mut end = 0;
+    for (i, instr) in instrs.iter().enumerate() {
+        match instr {
+            Instr::Push(val) if !matches!(**val, Value::Func(_)) => depth += 1,
+            Instr::BeginArray => array_starts.push(depth),
+            Instr::EndArray { .. } => match array_starts.pop() {
+                Some(start) => depth = start + 1,
+                None => break,
+            },
+            Instr::Prim(prim, _) if can_fold(*prim) => {
+                let (Some(args), Some(outputs)) = (prim.args(), prim.outputs()) else {
+                    break;
+                };
+                let Some(remaining) = depth.checked_sub(args as usize) else {
+                    break;
+                };
+                depth = remaining + outputs as usize;
+            }
+            _ => break,
+        }
+        if array_starts.is_empty() {
+            end = i + 1;
+        }
+    }
+    if instrs[..end]
+        .iter()
+        .all(|instr| matches!(instr, Instr::Push(_)))
+    {
+        return instrs;
+    }
+    let mut fold_env = env.fork(Vec::new());
+    if fold_env.exec_global_instrs(instrs[..end].to_vec()).is_err() {
+        return instrs;
+    }
+    let rest = instrs.split_off(end);
+    (fold_env.take_stack().into_iter())
+        .map(Instr::push)
+        .chain(rest)
+        .collect()
+}
+
+fn can_fold(prim: Primitive) -> bool {
+    prim.is_pure()
+        && prim.modifier_args().is_none()
+        && matches!(
+            prim.class(),
+            PrimClass::Stack
+                | PrimClass::Constant
+                | PrimClass::MonadicPervasive
+                | PrimClass::DyadicPervasive
+        )
+}
+
+/// Fuse runs of pervasive operations so that number arrays are processed in a single pass
+fn fuse_pervasive_chains(instrs: Vec<Instr>) -> Vec<Instr> {
+    let mut fused = Vec::with_capacity(instrs.len());
+    let mut i = 0;
+    while i < instrs.len() {
+        let mut end = i;
+        let mut steps = 0;
+        while let Some((_, len)) = ChainStep::parse(&instrs[end..]) {
+            end += len;
+            steps += 1;
+        }
+        if steps < 2 {
+            fused.push(instrs[i].clone());
+            i += 1;
+            continue;
+        }
+        let chain = instrs[i..end].to_vec();
+        let mut ids = Vec::new();
+        let mut span = None;
+        for instr in &chain {
+            if let Instr::Prim(prim, prim_span) = instr {
+                ids.push(FunctionId::Primitive(*prim));
+                span.get_or_insert(*prim_span);
+            }
+        }
+        let f = Function::new(FunctionId::Composed(ids), chain, Signature::new(1, 1));
+        fused.push(Instr::push(f));
+        fused.push(Instr::Prim(Primitive::PervasiveChain, span.unwrap()));
+        i = end;
+    }
+    fused
+}
+
+/// Whether a primitive was fused from the instructions in its function
+fn is_fused(prim: Primitive) -> bool {
+    use Primitive::*;
+    matches!(prim, PervasiveChain | FirstRise | LenKeep)
+}
+
+/// Run the instructions that a fused primitive was made from
+///
+/// The instructions are run in place, so that errors are traced
+/// as if they had never been fused.
+pub(crate) fn run_unfused(fused: Primitive, f: Value, env: &mut Uiua) -> UiuaResult {
+    let Some(f) = f.as_function() else {
+        return env.call(f);
+    };
+    let span = env.span_index();
+    env.pop_span();
+    for instr in &f.instrs {
+        match instr {
+            Instr::Push(val) => env.push((**val).clone()),
+            &Instr::Prim(prim, span) => {
+                env.push_span(span, Some(prim));
+                prim.run(env)?;
+                env.pop_span();
+            }
+            _ => unreachable!("fused functions only contain pushes and primitives"),
+        }
+    }
+    env.push_span(span, Some(fused));
+    Ok(())
+}
+
+/// Expand fused primitives back into the instructions they were made from
+///
+/// Returns `None` if there are no fused primitives.
+pub(crate) fn unfuse(instrs: &[Instr]) -> Option<Vec<Instr>> {
+    if !(instrs.iter()).any(|instr| matches!(instr, Instr::Prim(prim, _) if is_fused(*prim))) {
+        return None;
+    }
+    let mut unfused = Vec::with_capacity(instrs.len());
+    for instr in instrs {
+        if let (&Instr::Prim(prim, _), Some(Instr::Push(f))) = (instr, unfused.last()) {
+            if let Some(chain) = (f.as_function())
+                .filter(|_| is_fused(prim))
+                .map(|f| f.instrs.clone())
+            {
+                unfused.pop();
+                unfused.extend(chain);
+                continue;
+            }
+        }
+        unfused.push(instr.clone());
+    }
+    Some(unfused)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use Instr::*;
+    use Primitive::*;
+
+    fn push<T>(val: T) -> Instr
+    where
+        T: Into<Value>,
+    {
+        Push(val.into().into())
+    }
+
+    fn run(code: &str, optimize: bool) -> Result<Vec<Value>, String> {
+        let mut env = Uiua::with_native_sys().with_optimizations(optimize);
+        env.load_str(code).map_err(|e| e.to_string())?;
+        Ok(env.take_stack())
+    }
+
+    #[test]
+    fn optimized_matches_unoptimized() {
+        for code in [
+            "⊢⇌ [1 2 3]",
+            "⊢⍏ [3 1 2 1]",
+            "⊢⍏ [[2 1] [1 3] [1 2]]",
+            "⊢⍏ {\"b\" \"a\"}",
+            "⧻▽ [1 0 2] [4 5 6]",
+            "⧻▽ 3 [4 5 6]",
+            "⧻▽ 2 5",
+            "+1 ×2 ⇡10",
+            "√+1 ×2 -3 ⇡10",
+            "⌊÷2 +1 ⇡10",
+            "+1 ×2 [1_2 3_4]",
+            "+1 ×2 5",
+            "+1 ×2 \"abc\"",
+            "×2 +@a \"abc\"",
+            "-1 ¬ =2 ⇡5",
+            "+1 ×2 ⇡0",
+            "×2 ◿3 ⇡10",
+            "≡(+1 ×2) ↯3_4⇡12",
+            "⍘(+1 ×2) [3 5 7]",
+            "⍜(+1 ×2)(÷2) [3 5 7]",
+            "⍜(×2 +1)⊢ [3 5 7]",
+            "+1 2",
+            "[+1 2 ×3 4]",
+            "+ 1 ⇡5",
+            "÷0 1",
+            "+π ×2 η",
+            ";1 2",
+            "; π",
+            ";+1 2 3",
+            ",1 2 .3",
+            "+ \"a\" 1",
+            "+ 1 [1 2] [3 4 5]",
+            "f ← +1 ×2\nf 3 f ⇡4",
+            "○+η 0.5",
+        ] {
+            assert_eq!(run(code, false), run(code, true), "{code}");
+        }
+    }
+
+    #[test]
+    fn errors_match_unoptimized() {
+        for code in [
+            "×2 +1 \"abc\"",
+            "√+1 ×2 \"abc\"",
+            "+ [1 2] [3 4 5]",
+            "+1 2 ⍤\"no\" 0",
+            "⊢⍏ 5",
+            "⊢⍏ []",
+            "⊢⍏ ⇡0_3",
+            "⧻▽ [1 2] [4 5 6]",
+            "⧻▽ [1 ¯1 2] [4 5 6]",
+            "⧻▽ [1 0.5 2] [4 5 6]",
+            "f ← ⧻▽\nf [1 2] [4 5 6]",
+            ";¬ \"a\"",
+            ";.",
+        ] {
+            let optimized = run(code, true);
+            assert!(optimized.is_err(), "{code}");
+            assert_eq!(run(code, false), optimized, "{code}");
+        }
+    }
+
+    #[test]
+    fn peephole() {
+        let mut instrs = Vec::new();
+        for instr in [Prim(Rise, 0), Prim(First, 1), Prim(Keep, 2), Prim(Len, 3)] {
+            push_instr(&mut instrs, instr);
+        }
+        let fused = |prims: [Primitive; 2], span, fused, sig| {
+            let ids = prims.iter().copied().map(FunctionId::Primitive).collect();
+            let chain = vec![Prim(prims[0], span), Prim(prims[1], span + 1)];
+            let f = Function::new(FunctionId::Composed(ids), chain, sig);
+            [Instr::push(f), Prim(fused, span)]
+        };
+        let first_rise = fused([Rise, First], 0, FirstRise, Signature::new(1, 1));
+        let len_keep = fused([Keep, Len], 2, LenKeep, Signature::new(2, 1));
+        assert_eq!(instrs, [first_rise, len_keep].concat());
+        assert_eq!(
+            unfuse(&instrs),
+            Some(vec![
+                Prim(Rise, 0),
+                Prim(First, 1),
+                Prim(Keep, 2),
+                Prim(Len, 3)
+            ])
+        );
+
+        let mut instrs = Vec::new();
+        for instr in [
+            push(1),
+            Prim(Pop, 0),
+            Prim(Pi, 1),
+            Prim(Pop, 2),
+            Prim(Neg, 3),
+        ] {
+            push_instr(&mut instrs, instr);
+        }
+        assert_eq!(instrs, [Prim(Neg, 3)]);
+
+        // Primitives that can fail are kept
+        let kept = [Prim(Dup, 0), Prim(Pop, 1), Prim(Neg, 2), Prim(Pop, 3)];
+        let mut instrs = Vec::new();
+        for instr in kept.clone() {
+            push_instr(&mut instrs, instr);
+        }
+        assert_eq!(instrs, kept);
+    }
+
+    #[test]
+    fn constant_folding() {
+        let env = Uiua::with_native_sys();
+        let fold = |instrs: Vec<Instr>| fold_constants(instrs, &env);
+        assert_eq!(
+            fold(vec![push(2), push(1), Prim(Add, 0), Prim(Mul, 1)]),
+            [push(3), Prim(Mul, 1)]
+        );
+        assert_eq!(
+            fold(vec![
+                BeginArray,
+                push(2),
+                push(1),
+                EndArray {
+                    span: 0,
+                    boxed: false
+                }
+            ]),
+            [push(Value::from_iter([1.0, 2.0]))]
+        );
+        // Only pushes
+        assert_eq!(fold(vec![push(1), push(2)]), [push(1), push(2)]);
+        // Not enough constants
+        assert_eq!(
+            fold(vec![push(1), Prim(Add, 0), Prim(Neg, 1)]),
+            [push(1), Prim(Add, 0), Prim(Neg, 1)]
+        );
+        // Arrays that are not closed
+        assert_eq!(
+            fold(vec![BeginArray, push(1), Prim(Neg, 0), Prim(Dup, 1)]),
+            [BeginArray, push(1), Prim(Neg, 0), Prim(Dup, 1)]
+        );
+        // Primitives that might build big arrays
+        assert_eq!(
+            fold(vec![push(5), Prim(Range, 0)]),
+            [push(5), Prim(Range, 0)]
+        );
+        // Errors
+        assert_eq!(
+            fold(vec![push('a'), push('b'), Prim(Add, 0)]),
+            [push('a'), push('b'), Prim(Add, 0)]
+        );
+    }
+
+    #[test]
+    fn pervasive_chain_fusion() {
+        let instrs = vec![
+            Prim(Dup, 0),
+            push(2),
+            Prim(Mul, 1),
+            Prim(Sqrt, 2),
+            push(1),
+            Prim(Add, 3),
+            Prim(Range, 4),
+        ];
+        let fused = fuse_pervasive_chains(instrs.clone());
+        assert!(
+            matches!(
+                fused.as_slice(),
+                [
+                    Prim(Dup, 0),
+                    Push(_),
+                    Prim(PervasiveChain, 1),
+                    Prim(Range, 4)
+                ]
+            ),
+            "{fused:?}"
+        );
+        assert_eq!(unfuse(&fused), Some(instrs));
+        // A single step is not worth fusing
+        let instrs = vec![push(2), Prim(Mul, 0), Prim(Range, 1), Prim(Neg, 2)];
+        assert_eq!(fuse_pervasive_chains(instrs.clone()), instrs);
+        assert_eq!(unfuse(&instrs), None);
+    }
+}
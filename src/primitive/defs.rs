@@ -457,6 +457,23 @@ primitive!(
     /// ex: ∠ ¯1 0
     /// ex: ∠ √2 √2
     (2, Atan, DyadicPervasive, ("atangent", '∠')),
+    /// Make a complex number
+    ///
+    /// The first argument is the imaginary part, and the second argument is the real part.
+    /// ex: ℂ 3 5
+    /// ex: ℂ [0 1 2] [3 4 5]
+    /// The arithmetic primitives, along with [absolute value], [sign], [sqrt], [power], and [logarithm], work on complex numbers.
+    /// ex: × ℂ1 2 ℂ3 4
+    /// ex: √ ℂ0 ¯4
+    /// ex: ⌵ ℂ4 3
+    /// Complex numbers are ordered by their real parts, then by their imaginary parts.
+    /// This order is used by comparisons, [minimum], [maximum], and sorting.
+    /// ex: ↥ ℂ1 2 ℂ0 2
+    /// [invert][complex] gets the real and imaginary parts back.
+    /// ex: ⍘ℂ ℂ3 5
+    (2, Complex, DyadicPervasive, ("complex", 'ℂ')),
+    /// Get the real and imaginary parts of a complex number
+    (1(2), InvComplex, MonadicPervasive),
     /// Get the number of rows in an array
     ///
     /// ex: ⧻5
@@ -552,6 +569,10 @@ primitive!(
     /// Here, we sort the array ascending by the [absolute value] of its elements.
     /// ex: ⊏⍏⌵.6_2_7_0_¯1_5
     (1, Rise, MonadicArray, ("rise", '⍏')),
+    /// Get the index of the first minimal row of an array
+    ///
+    /// The function is the [rise] and [first] that were fused.
+    (1[1], FirstRise, OtherModifier),
     /// Get the indices into an array if it were sorted descending
     ///
     /// The [fall] of an array is the list of indices that would sort the array descending if used with [select].
@@ -817,6 +838,10 @@ primitive!(
     ///
     /// [keep]'s glyph is `▽` because its main use is to filter, and `▽` kind of looks like a coffee filter.
     (2, Keep, DyadicArray, ("keep", '▽')),
+    /// Get the number of rows that keep would keep
+    ///
+    /// The function is the [keep] and [length] that were fused.
+    (2[1], LenKeep, OtherModifier),
     /// End step of under keep
     (3, Unkeep, Misc),
     /// Find the occurences of one array in another
@@ -876,6 +901,8 @@ primitive!(
     /// [break]ing out of [reduce] discards the unreduced values.
     /// ex: /(⎋≥10.+) [3 4 8 9]
     (1[1], Reduce, AggregatingModifier, ("reduce", '/')),
+    /// Reduce the windows of an array without materializing them
+    (2[1], ReduceWindows, AggregatingModifier),
     /// Apply a reducing function to an array with an initial value
     ///
     /// For reducing without an initial value, see [reduce].
@@ -932,6 +959,10 @@ primitive!(
     /// ex: ⍚¯1/+ [1_2_3 4_5_6 7_8_9]
     /// ex:   ≡/+ [1_2_3 4_5_6 7_8_9]
     ([1], Rows, IteratingModifier, ("rows", '≡')),
+    /// Apply a function to each window of an array without materializing the windows
+    (2[1], RowsWindows, IteratingModifier),
+    /// Apply a chain of pervasive operations in one pass over a number array
+    (1[1], PervasiveChain, OtherModifier),
     /// Apply a function to a fixed value and each row of an array
     ///
     /// ex: ∺⊂ 1 2_3_4
@@ -1422,6 +1453,31 @@ primitive!(
     /// ex: parse "3.1415926535897932"
     /// ex! parse "dog"
     (1, Parse, Misc, "parse"),
+    /// Find all matches of a regular expression in a string
+    ///
+    /// The first argument is the pattern, and the second is the string to search.
+    /// The result is a rank `2` array of [box]ed strings with a row for each match.
+    /// The first column is the whole match, and there is a column for each capture group.
+    /// ex: regex "[0-9]+" "a1 b22 c333"
+    /// ex: regex "([a-z])([0-9]+)" "a1 b22 c333"
+    /// Capture groups that did not participate in a match are empty.
+    /// ex: regex "a(x)?" "a ax"
+    ///
+    /// Compiled patterns are cached, so using the same pattern many times is fast.
+    (2, Regex, Misc, "regex"),
+    /// Replace all matches of a regular expression in a string
+    ///
+    /// The first argument is the pattern, the second is the replacement, and the third is the string.
+    /// ex: replace "[0-9]+" "#" "a1 b22 c333"
+    /// Capture groups can be referred to in the replacement with `$`.
+    /// ex: replace "([a-z])([0-9]+)" "$2$1" "a1 b22 c333"
+    (3, Replace, Misc, "replace"),
+    /// Split a string at every match of a regular expression
+    ///
+    /// The first argument is the pattern, and the second is the string to split.
+    /// The parts are [box]ed.
+    /// ex: split ", *" "a, b,c,  d"
+    (2, Split, Misc, "split"),
     /// Generate a random number between 0 and 1
     ///
     /// If you need a seeded random number, use [gen].
@@ -1472,6 +1528,7 @@ primitive!(
     /// `0` indicates a number array.
     /// `1` indicates a character array.
     /// `2` indicates a function array.
+    /// `3` indicates a complex array.
     /// ex: type 5
     /// ex: type "hello"
     /// ex: type (+)
@@ -1496,6 +1553,53 @@ primitive!(
     /// [under][now] can be used to time a function.
     /// ex: ⍜now(5&sl1)
     (0, Now, Misc, "now"),
+    /// Split a time into calendar fields
+    ///
+    /// Times are in seconds since the Unix epoch, like the ones returned by [now].
+    /// The result has an extra axis of length `6` containing the year, month, day, hour, minute, and second in UTC.
+    /// The second may have a fractional part.
+    /// ex: datetime 0
+    /// ex: datetime 1697550330.25
+    /// ex: datetime [0 86400 1e9]
+    /// Times must be within about 285 million years of the epoch.
+    /// [datetime] is the inverse of [timestamp].
+    /// ex: ⍘datetime [2023 10 17 13 45 30]
+    (1, Datetime, Misc, "datetime"),
+    /// Combine calendar fields into a time
+    ///
+    /// Expects an array whose last axis contains the year, month, day, hour, minute, and second in UTC.
+    /// Missing trailing fields are treated as the start of their range.
+    /// The result is in seconds since the Unix epoch, like the ones returned by [now].
+    /// ex: timestamp [2023 10 17 13 45 30]
+    /// ex: timestamp [2000_1_1 2000_2_1]
+    /// Fields outside of their normal range carry over.
+    /// ex: datetime timestamp [2023 14 1]
+    /// [timestamp] is the inverse of [datetime].
+    (1, Timestamp, Misc, "timestamp"),
+    /// Format times with a strftime-style pattern
+    ///
+    /// The first argument is the pattern, and the second is the times in seconds since the Unix epoch.
+    /// A single time is formatted as a string. An array of times is formatted as an array of [box]ed strings.
+    /// ex: datefmt "%Y-%m-%d %H:%M:%S" 1697550330
+    /// ex: datefmt "%a %e %B" [0 1e9]
+    ///
+    /// The supported specifiers are:
+    /// `%Y` year, `%y` 2-digit year, `%m` month, `%d` day, `%e` space-padded day, `%j` day of the year,
+    /// `%H` 24-hour hour, `%I` 12-hour hour, `%p` AM or PM, `%M` minute, `%S` second, `%f` milliseconds,
+    /// `%B` month name, `%b` short month name, `%A` weekday name, `%a` short weekday name,
+    /// `%s` seconds since the epoch, `%F` `%Y-%m-%d`, `%T` `%H:%M:%S`, and `%%` a literal `%`.
+    (2, DateFormat, Misc, "datefmt"),
+    /// Parse dates with a strftime-style pattern into times
+    ///
+    /// The first argument is the pattern, and the second is a string, a rank `2` character array, or an array of [box]ed strings.
+    /// The result is in seconds since the Unix epoch.
+    /// ex: dateparse "%Y-%m-%d %H:%M:%S" "2023-10-17 13:45:30"
+    /// ex: dateparse "%d/%m/%Y" {"1/1/2000" "29/2/2000"}
+    /// Names are matched case-insensitively, and `%f` accepts any number of digits.
+    /// Like POSIX, `%y` parses `69` to `99` as 1969 to 1999 and `00` to `68` as 2000 to 2068.
+    /// ex: dateparse "%B %d, %Y %I:%M:%S.%f %p" "october 17, 2023 1:45:30.25 pm"
+    /// See [datefmt] for the supported specifiers.
+    (2, DateParse, Misc, "dateparse"),
     /// The number of radians in a quarter circle
     ///
     /// Equivalent to `divide``2``pi` or `divide``4``tau`
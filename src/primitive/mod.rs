@@ -24,12 +24,13 @@ use once_cell::sync::Lazy;
 use rand::prelude::*;
 
 use crate::{
-    algorithm::{fork, loops},
+    algorithm::{fork, loops, pervade},
     array::Array,
     function::Function,
     grid_fmt::GridFmt,
     lex::AsciiToken,
-    run::FunctionArg,
+    optimize,
+    run::{ArrayArg, FunctionArg},
     sys::*,
     value::*,
     Uiua, UiuaError, UiuaResult,
@@ -117,6 +118,7 @@ impl fmt::Display for Primitive {
                 InverseBits => write!(f, "⍘{Bits}"),
                 InvTrace => write!(f, "⍘{Trace}"),
                 InvWhere => write!(f, "⍘{Where}"),
+                InvComplex => write!(f, "⍘{Complex}"),
                 Uncouple => write!(f, "⍘{Couple}"),
                 Untake => write!(f, "⍘{Take}"),
                 Undrop => write!(f, "⍘{Drop}"),
@@ -126,6 +128,10 @@ impl fmt::Display for Primitive {
                 Asin => write!(f, "{Invert}{Sin}"),
                 Acos => write!(f, "{Invert}{Cos}"),
                 Last => write!(f, "{First}{Reverse}"),
+                ReduceWindows => write!(f, "{Reduce}{Windows}"),
+                RowsWindows => write!(f, "{Rows}{Windows}"),
+                FirstRise => write!(f, "{First}{Rise}"),
+                LenKeep => write!(f, "{Len}{Keep}"),
                 _ => write!(f, "{self:?}"),
             }
         }
@@ -170,9 +176,28 @@ impl Primitive {
             _ => None,
         }
     }
+    /// Code that behaves the same as a deprecated primitive
+    pub(crate) fn deprecation_replacement(&self) -> Option<String> {
+        use Primitive::*;
+        match self {
+            Roll => Some(format!("{Dip}{Flip}{Flip}")),
+            Unroll => Some(format!("{Flip}{Dip}{Flip}")),
+            _ => None,
+        }
+    }
     pub fn is_deprecated(&self) -> bool {
         self.deprecation_suggestion().is_some()
     }
+    /// Check if the primitive has no side effects and always returns the same outputs for the same inputs
+    ///
+    /// Calls to pure primitives may be reordered or run in parallel.
+    pub fn is_pure(&self) -> bool {
+        use Primitive::*;
+        !matches!(
+            self,
+            Sys(_) | Rand | Now | Tag | Spawn | Wait | Trace | InvTrace | Dump
+        )
+    }
     pub fn inverse(&self) -> Option<Self> {
         use Primitive::*;
         Some(match self {
@@ -190,6 +215,8 @@ impl Primitive {
             Bits => InverseBits,
             InverseBits => Bits,
             Couple => Uncouple,
+            Complex => InvComplex,
+            InvComplex => Complex,
             Roll => Unroll,
             Unroll => Roll,
             Trace => InvTrace,
@@ -198,6 +225,8 @@ impl Primitive {
             Unbox => Box,
             Where => InvWhere,
             InvWhere => Where,
+            Datetime => Timestamp,
+            Timestamp => Datetime,
             _ => return None,
         })
     }
@@ -306,11 +335,30 @@ impl Primitive {
             Primitive::Min => env.dyadic_rr_env(Value::min)?,
             Primitive::Max => env.dyadic_rr_env(Value::max)?,
             Primitive::Atan => env.dyadic_rr_env(Value::atan2)?,
+            Primitive::Complex => env.dyadic_rr_env(Value::complex)?,
+            Primitive::InvComplex => {
+                let (re, im) = env.pop(1)?.uncomplex(env)?;
+                env.push(re);
+                env.push(im);
+            }
+            Primitive::PervasiveChain => pervade::pervasive_chain(env)?,
             Primitive::Match => env.dyadic_rr(|a, b| a == b)?,
             Primitive::Join => env.dyadic_oo_env(Value::join)?,
             Primitive::Transpose => env.monadic_mut(Value::transpose)?,
             Primitive::InvTranspose => env.monadic_mut(Value::inv_transpose)?,
             Primitive::Keep => env.dyadic_ro_env(Value::keep)?,
+            Primitive::LenKeep => {
+                let f = env.pop(FunctionArg(1))?;
+                let counts = env.pop(ArrayArg(1))?;
+                let kept = env.pop(ArrayArg(2))?;
+                if let Some(len) = counts.keep_len(&kept, env) {
+                    env.push(len);
+                } else {
+                    env.push(kept);
+                    env.push(counts);
+                    optimize::run_unfused(*self, f, env)?;
+                }
+            }
             Primitive::Unkeep => {
                 let from = env.pop(1)?;
                 let counts = env.pop(2)?;
@@ -340,6 +388,16 @@ impl Primitive {
                 env.push(a);
             }
             Primitive::Rise => env.monadic_ref_env(|v, env| v.rise(env))?,
+            Primitive::FirstRise => {
+                let f = env.pop(FunctionArg(1))?;
+                let xs = env.pop(ArrayArg(1))?;
+                if let Some(index) = xs.first_rise() {
+                    env.push(index);
+                } else {
+                    env.push(xs);
+                    optimize::run_unfused(*self, f, env)?;
+                }
+            }
             Primitive::Fall => env.monadic_ref_env(|v, env| v.fall(env))?,
             Primitive::Pick => env.dyadic_oo_env(Value::pick)?,
             Primitive::Unpick => {
@@ -380,6 +438,19 @@ impl Primitive {
                 env.call(f)?
             }
             Primitive::Parse => env.monadic_env(|v, env| v.parse_num(env))?,
+            Primitive::Regex => env.dyadic_rr_env(Value::regex_match)?,
+            Primitive::Replace => {
+                let pattern = env.pop(1)?;
+                let replacement = env.pop(2)?;
+                let string = env.pop(3)?;
+                let replaced = pattern.regex_replace(&replacement, &string, env)?;
+                env.push(replaced);
+            }
+            Primitive::Split => env.dyadic_rr_env(Value::regex_split)?,
+            Primitive::Datetime => env.monadic_ref_env(Value::datetime)?,
+            Primitive::Timestamp => env.monadic_ref_env(Value::timestamp)?,
+            Primitive::DateFormat => env.dyadic_rr_env(Value::format_datetime)?,
+            Primitive::DateParse => env.dyadic_rr_env(Value::parse_datetime)?,
             Primitive::Range => env.monadic_ref_env(Value::range)?,
             Primitive::Reverse => env.monadic_mut(Value::reverse)?,
             Primitive::Deshape => env.monadic_mut(Value::deshape)?,
@@ -391,20 +462,29 @@ impl Primitive {
                     Array::row_count,
                     Array::row_count,
                     Array::row_count,
+                    Array::row_count,
                 )
             })?,
             Primitive::Shape => env.monadic_ref(|v| {
-                v.generic_ref_shallow(Array::shape, Array::shape, Array::shape, Array::shape)
-                    .iter()
-                    .copied()
-                    .collect::<Value>()
+                v.generic_ref_shallow(
+                    Array::shape,
+                    Array::shape,
+                    Array::shape,
+                    Array::shape,
+                    Array::shape,
+                )
+                .iter()
+                .copied()
+                .collect::<Value>()
             })?,
             Primitive::Bits => env.monadic_ref_env(Value::bits)?,
             Primitive::InverseBits => env.monadic_ref_env(Value::inverse_bits)?,
             Primitive::Fold => loops::fold(env)?,
             Primitive::Reduce => loops::reduce(env)?,
+            Primitive::ReduceWindows => loops::reduce_windows(env)?,
             Primitive::Each => loops::each(env)?,
             Primitive::Rows => loops::rows(env)?,
+            Primitive::RowsWindows => loops::rows_windows(env)?,
             Primitive::Distribute => loops::distribute(env)?,
             Primitive::Table => loops::table(env)?,
             Primitive::Cross => loops::cross(env)?,
@@ -574,6 +654,7 @@ impl Primitive {
                     Value::Num(_) | Value::Byte(_) => 0,
                     Value::Char(_) => 1,
                     Value::Func(_) => 2,
+                    Value::Complex(_) => 3,
                 });
             }
             Primitive::Sig => {
@@ -998,6 +1079,24 @@ mod tests {
         }
     }
 
+    #[test]
+    fn deprecation_replacements() {
+        for prim in Primitive::all() {
+            let Some(replacement) = prim.deprecation_replacement() else {
+                continue;
+            };
+            let mut deprecated = Uiua::with_native_sys();
+            deprecated.load_str(&format!("{prim} 1 2 3")).unwrap();
+            let mut replaced = Uiua::with_native_sys();
+            replaced.load_str(&format!("{replacement} 1 2 3")).unwrap();
+            assert_eq!(
+                deprecated.take_stack(),
+                replaced.take_stack(),
+                "{replacement} does not replace {prim:?}"
+            );
+        }
+    }
+
     #[test]
     fn primitive_from_name() {
         assert_eq!(Primitive::from_format_name("rev"), Some(Primitive::Reverse));
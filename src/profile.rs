@@ -1,3 +1,202 @@
+//! Profiling of Uiua code
+//!
+//! Attach a [`Profiler`] to a runtime with [`Uiua::with_profiler`] to record
+//! how much time is spent in each primitive, function, and source line.
+//!
+//! [`Uiua::with_profiler`]: crate::Uiua::with_profiler
+
+use std::{cmp::Reverse, collections::HashMap, fmt::Write, path::Path, sync::Arc, time::Duration};
+
+use instant::Instant;
+
+use crate::{function::FunctionId, primitive::Primitive};
+
+/// Records where time is spent while running code
+///
+/// Times are inclusive of any functions called. Self times exclude them.
+#[derive(Clone, Default)]
+pub struct Profiler {
+    /// Statistics for each primitive
+    pub primitives: HashMap<Primitive, ProfileStat>,
+    /// Statistics for each function
+    pub functions: HashMap<FunctionId, ProfileStat>,
+    /// Statistics for each source line
+    ///
+    /// Instructions' self times are attributed to the lines they came from.
+    pub lines: HashMap<(Option<Arc<Path>>, usize), ProfileStat>,
+    /// The self time spent in each stack of functions and primitives
+    folded: HashMap<Vec<ProfileFrame>, Duration>,
+    active: Vec<ActiveEntry>,
+}
+
+/// The time spent in some code
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ProfileStat {
+    /// The number of times the code was run
+    pub calls: u64,
+    /// The total time spent in the code
+    pub time: Duration,
+    /// The time spent in the code excluding called functions
+    pub self_time: Duration,
+}
+
+/// A frame in a profiled stack
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum ProfileFrame {
+    Function(FunctionId),
+    Primitive(Primitive),
+}
+
+#[derive(Clone)]
+struct ActiveEntry {
+    /// The frame, or `None` for instructions that are not primitives
+    frame: Option<ProfileFrame>,
+    start: Instant,
+    children: Duration,
+}
+
+impl ProfileStat {
+    fn add(&mut self, time: Duration, self_time: Duration) {
+        self.calls += 1;
+        self.time += time;
+        self.self_time += self_time;
+    }
+}
+
+impl Profiler {
+    pub(crate) fn enter_function(&mut self, id: FunctionId) {
+        self.enter(Some(ProfileFrame::Function(id)));
+    }
+    pub(crate) fn enter_instr(&mut self, prim: Option<Primitive>) {
+        self.enter(prim.map(ProfileFrame::Primitive));
+    }
+    fn enter(&mut self, frame: Option<ProfileFrame>) {
+        self.active.push(ActiveEntry {
+            frame,
+            start: Instant::now(),
+            children: Duration::ZERO,
+        });
+    }
+    /// Finish the innermost entry, attributing its self time to a line if it has one
+    pub(crate) fn exit(&mut self, line: Option<(Option<Arc<Path>>, usize)>) {
+        let Some(entry) = self.active.pop() else {
+            return;
+        };
+        let time = entry.start.elapsed();
+        let self_time = time.saturating_sub(entry.children);
+        if let Some(parent) = self.active.last_mut() {
+            parent.children += time;
+        }
+        match &entry.frame {
+            Some(ProfileFrame::Function(id)) => {
+                // Recursive calls would otherwise be counted more than once
+                if !(self.active.iter()).any(|e| e.frame.as_ref() == entry.frame.as_ref()) {
+                    self.functions
+                        .entry(id.clone())
+                        .or_default()
+                        .add(time, self_time);
+                } else if let Some(stat) = self.functions.get_mut(id) {
+                    stat.calls += 1;
+                    stat.self_time += self_time;
+                }
+            }
+            Some(ProfileFrame::Primitive(prim)) => {
+                self.primitives
+                    .entry(*prim)
+                    .or_default()
+                    .add(time, self_time);
+            }
+            None => {}
+        }
+        if let Some(line) = line {
+            self.lines.entry(line).or_default().add(time, self_time);
+        }
+        let path: Vec<ProfileFrame> = (self.active.iter())
+            .chain([&entry])
+            .filter_map(|e| e.frame.clone())
+            .collect();
+        *self.folded.entry(path).or_default() += self_time;
+    }
+    /// Get a table of the recorded statistics, sorted by self time
+    pub fn report(&self) -> String {
+        fn ms(d: Duration) -> String {
+            format!("{:.3}", d.as_secs_f64() * 1000.0)
+        }
+        fn table(output: &mut String, title: &str, mut rows: Vec<(String, ProfileStat)>) {
+            if rows.is_empty() {
+                return;
+            }
+            rows.sort_by_key(|(name, stat)| (Reverse(stat.self_time), name.clone()));
+            _ = writeln!(
+                output,
+                "{title:<32} {:>10} {:>12} {:>12}",
+                "calls", "time (ms)", "self (ms)"
+            );
+            for (name, stat) in rows {
+                _ = writeln!(
+                    output,
+                    "  {name:<30} {:>10} {:>12} {:>12}",
+                    stat.calls,
+                    ms(stat.time),
+                    ms(stat.self_time)
+                );
+            }
+            output.push('\n');
+        }
+        let mut output = String::new();
+        let prims = (self.primitives.iter())
+            .map(|(prim, stat)| (prim_label(*prim), *stat))
+            .collect();
+        table(&mut output, "Primitive", prims);
+        let functions = (self.functions.iter())
+            .map(|(id, stat)| (id.to_string(), *stat))
+            .collect();
+        table(&mut output, "Function", functions);
+        let lines = (self.lines.iter())
+            .map(|((path, line), stat)| {
+                let name = match path {
+                    Some(path) => format!("{}:{line}", path.display()),
+                    None => format!("line {line}"),
+                };
+                (name, *stat)
+            })
+            .collect();
+        table(&mut output, "Line", lines);
+        output
+    }
+    /// Get the recorded stacks in the folded format used by flamegraph tools
+    ///
+    /// Each line is a `;`-separated stack followed by its self time in microseconds.
+    pub fn folded_stacks(&self) -> String {
+        let mut lines: Vec<String> = (self.folded.iter())
+            .filter(|(path, _)| !path.is_empty())
+            .map(|(path, time)| {
+                let stack: Vec<String> = (path.iter())
+                    .map(|frame| {
+                        let label = match frame {
+                            ProfileFrame::Function(id) => id.to_string(),
+                            ProfileFrame::Primitive(prim) => prim_label(*prim),
+                        };
+                        label.replace(';', ",")
+                    })
+                    .collect();
+                format!("{} {}", stack.join(";"), time.as_micros())
+            })
+            .collect();
+        lines.sort();
+        let mut output = lines.join("\n");
+        output.push('\n');
+        output
+    }
+}
+
+fn prim_label(prim: Primitive) -> String {
+    prim.name()
+        .map(Into::into)
+        .unwrap_or_else(|| prim.to_string())
+}
+
+#[doc(hidden)]
 #[macro_export]
 macro_rules! profile_function {
     () => {
@@ -8,6 +207,7 @@ macro_rules! profile_function {
     };
 }
 
+#[doc(hidden)]
 #[macro_export]
 macro_rules! profile_scope {
     ($label:expr) => {
@@ -16,6 +216,7 @@ macro_rules! profile_scope {
     };
 }
 
+#[doc(hidden)]
 pub fn run_profile() {
     #[cfg(feature = "profile")]
     enabled::run_profile();
@@ -63,6 +264,13 @@ Z ← ⊟/- ⁿ2 ∶×2 /×.⇌
 H ← ⁿ∶2÷12
 [×H10.∶ ×H7.∶ ×H4. 220]
 ÷⧻∶ ≡/+ sine ×2×π ⊞× ÷∶ ⇡.&asr.",
+        ),
+        (
+            "LOOPS",
+            "\
+≡(/+ ×. ⇡) +1000 ⇡2000
+∵(/+ ⇡) ⇡3000
+⊞(/+ ⇡ +) ⇡60 ⇡60",
         ),
         (
             "LOGO",
@@ -298,3 +506,32 @@ c ← <∶√/+ⁿ2 xy
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Uiua;
+
+    #[test]
+    fn profiler() {
+        let mut env = Uiua::with_native_sys().with_profiler();
+        env.load_str("F ← /+ ≡(×2)\nF ⇡10\nF ⇡20").unwrap();
+        let profiler = env.profiler().unwrap();
+
+        assert_eq!(profiler.primitives[&Primitive::Reduce].calls, 2);
+        assert_eq!(profiler.primitives[&Primitive::Mul].calls, 30);
+        let f = &profiler.functions[&FunctionId::Named("F".into())];
+        assert_eq!(f.calls, 2);
+        assert!(f.time >= f.self_time);
+        assert_eq!(profiler.lines[&(None, 1)].calls, 2 + 2 + 30);
+        assert_eq!(profiler.lines[&(None, 2)].calls, 2);
+
+        let report = profiler.report();
+        assert!(report.contains("reduce"));
+        assert!(report.contains("`F`"));
+        assert!(report.contains("line 1"));
+        let folded = profiler.folded_stacks();
+        assert!((folded.lines()).any(|line| line.starts_with("main;`F`;rows;fn from 1:")));
+        assert!((folded.lines()).all(|line| line.rsplit_once(' ').is_some()));
+    }
+}
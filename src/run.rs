@@ -6,7 +6,10 @@ use std::{
     panic::{catch_unwind, AssertUnwindSafe},
     path::{Path, PathBuf},
     str::FromStr,
-    sync::Arc,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc,
+    },
 };
 
 use instant::Duration;
@@ -14,10 +17,16 @@ use parking_lot::Mutex;
 
 use crate::{
     array::Array,
+    ast::Item,
+    complex::Complex,
+    debug::{
+        Breakpoints, DebugAction, DebugFrame, DebugHandler, DebugState, Debugger, PauseReason, Step,
+    },
     function::*,
-    lex::Span,
+    lex::{CodeSpan, Span},
     parse::parse,
     primitive::{Primitive, CONSTANTS},
+    profile::Profiler,
     value::Value,
     Diagnostic, DiagnosticKind, Handle, Ident, NativeSys, SysBackend, TraceFrame, UiuaError,
     UiuaResult,
@@ -48,10 +57,26 @@ pub struct Uiua {
     execution_limit: Option<f64>,
     /// The time at which execution started
     execution_start: f64,
+    /// A flag that stops execution when set
+    abort: Arc<AtomicBool>,
+    /// The number of impure primitives that have been run, across all threads
+    impure_calls: Arc<AtomicUsize>,
+    /// The most threads that a loop over a large array may be split across
+    max_threads: usize,
+    /// Whether compiled code is optimized
+    optimize: bool,
+    /// Whether code is only compiled and not run
+    pub(crate) compile_only: bool,
+    /// Globals bound to placeholders because their values are only known by running code
+    pub(crate) placeholders: HashSet<usize>,
+    /// The number of times compiled code has referred to a placeholder
+    pub(crate) placeholder_uses: usize,
     /// The paths of files currently being imported (used to detect import cycles)
     current_imports: Arc<Mutex<HashSet<PathBuf>>>,
     /// The stacks of imported files
     imports: Arc<Mutex<HashMap<PathBuf, Vec<Value>>>>,
+    /// The directory in which compiled imports are cached
+    pub(crate) import_cache: Option<PathBuf>,
     /// Accumulated diagnostics
     pub(crate) diagnostics: BTreeSet<Diagnostic>,
     /// Print diagnostics as they are encountered
@@ -62,6 +87,10 @@ pub struct Uiua {
     cli_file_path: PathBuf,
     /// The system backend
     pub(crate) backend: Arc<dyn SysBackend>,
+    /// The attached debugger
+    debugger: Option<Debugger>,
+    /// The attached profiler
+    profiler: Option<Profiler>,
 }
 
 #[derive(Clone)]
@@ -102,6 +131,7 @@ impl Default for Scope {
 #[derive(Default, Clone)]
 struct Fills {
     nums: Vec<f64>,
+    complexes: Vec<Complex>,
     chars: Vec<char>,
     functions: Vec<Arc<Function>>,
 }
@@ -170,6 +200,7 @@ impl Uiua {
             new_functions: Vec::new(),
             current_imports: Arc::new(Mutex::new(HashSet::new())),
             imports: Arc::new(Mutex::new(HashMap::new())),
+            import_cache: None,
             mode: RunMode::Normal,
             diagnostics: BTreeSet::new(),
             backend: Arc::new(NativeSys),
@@ -178,6 +209,15 @@ impl Uiua {
             cli_file_path: PathBuf::new(),
             execution_limit: None,
             execution_start: 0.0,
+            abort: Arc::new(AtomicBool::new(false)),
+            impure_calls: Arc::new(AtomicUsize::new(0)),
+            max_threads: std::thread::available_parallelism().map_or(1, |n| n.get()),
+            optimize: true,
+            compile_only: false,
+            placeholders: HashSet::new(),
+            placeholder_uses: 0,
+            debugger: None,
+            profiler: None,
         }
     }
     /// Create a new Uiua runtime with a custom IO backend
@@ -202,6 +242,85 @@ impl Uiua {
         self.execution_limit = Some(limit.as_millis() as f64);
         self
     }
+    /// Get a flag that stops execution when set
+    ///
+    /// It may be set from another thread while the program is running.
+    /// Every instruction after that fails, including in spawned threads.
+    pub fn abort_flag(&self) -> Arc<AtomicBool> {
+        self.abort.clone()
+    }
+    /// Limit the number of threads that loops and pervasive operations over large arrays may be split across
+    ///
+    /// Only loops whose functions are pure are split.
+    /// A limit of `1` disables parallelism.
+    /// The default is the number of available CPU cores.
+    pub fn with_max_threads(mut self, max_threads: usize) -> Self {
+        self.max_threads = max_threads.max(1);
+        self
+    }
+    /// Set whether compiled code is optimized
+    ///
+    /// Optimizations never change what code does, only how fast it runs.
+    /// They are on by default.
+    pub fn with_optimizations(mut self, optimize: bool) -> Self {
+        self.optimize = optimize;
+        self
+    }
+    /// Only compile code, without running it
+    ///
+    /// This is useful for tools that need to inspect code that may have side effects or not terminate.
+    /// Bindings whose values are only known by running code are bound to placeholders.
+    /// Errors in code that refers to a placeholder are ignored, since running it may not fail.
+    pub fn with_compile_only(mut self, compile_only: bool) -> Self {
+        self.compile_only = compile_only;
+        self
+    }
+    /// Attach a debugger
+    ///
+    /// The handler will be called before the first instruction is executed
+    /// and then wherever the [`DebugAction`] it returns says to pause next
+    pub fn with_debugger(mut self, handler: impl DebugHandler + 'static) -> Self {
+        self.debugger = Some(Debugger::new(handler));
+        self
+    }
+    /// Record where time is spent while running code
+    ///
+    /// Code run in spawned threads or in parallel loops is not profiled.
+    pub fn with_profiler(mut self) -> Self {
+        self.profiler = Some(Profiler::default());
+        self
+    }
+    /// Get the attached profiler
+    pub fn profiler(&self) -> Option<&Profiler> {
+        self.profiler.as_ref()
+    }
+    /// Add a breakpoint
+    ///
+    /// Execution will pause before any instruction that starts within the span.
+    /// This has no effect if no debugger is attached.
+    pub fn add_breakpoint(&mut self, span: CodeSpan) {
+        if let Some(debugger) = &self.debugger {
+            debugger.breakpoints.lock().insert(span);
+        }
+    }
+    /// Remove a breakpoint
+    ///
+    /// Returns whether the breakpoint existed
+    pub fn remove_breakpoint(&mut self, span: &CodeSpan) -> bool {
+        (self.debugger.as_ref()).is_some_and(|debugger| debugger.breakpoints.lock().remove(span))
+    }
+    /// Get the current breakpoints
+    pub fn breakpoints(&self) -> Vec<CodeSpan> {
+        (self.debugger.as_ref())
+            .map(|debugger| debugger.breakpoints.lock().iter().cloned().collect())
+            .unwrap_or_default()
+    }
+    /// Get a handle to the breakpoints that can be modified while the program runs
+    ///
+    /// Returns `None` if no debugger is attached
+    pub fn shared_breakpoints(&self) -> Option<Breakpoints> {
+        (self.debugger.as_ref()).map(|debugger| debugger.breakpoints.clone())
+    }
     /// Set the [`RunMode`]
     ///
     /// Default is [`RunMode::Normal`]
@@ -300,6 +419,37 @@ code:
         }
         res
     }
+    /// Run parsed items one at a time
+    ///
+    /// `f` is called after each item outside of a scope with the result of running it.
+    /// Errors do not stop later items from being run.
+    pub(crate) fn items_each(
+        &mut self,
+        items: Vec<Item>,
+        f: &mut dyn FnMut(&mut Self, &Item, UiuaResult),
+    ) {
+        self.execution_start = instant::now();
+        self.items_each_impl(items, f);
+    }
+    fn items_each_impl(
+        &mut self,
+        items: Vec<Item>,
+        f: &mut dyn FnMut(&mut Self, &Item, UiuaResult),
+    ) {
+        for item in items {
+            if let Item::Scoped { items, .. } = item {
+                if let Ok(scope_stack) = self.in_scope(true, |env| {
+                    env.items_each_impl(items, f);
+                    Ok(())
+                }) {
+                    self.stack.extend(scope_stack);
+                }
+            } else {
+                let res = self.items(vec![item.clone()], false);
+                f(self, &item, res);
+            }
+        }
+    }
     fn trace_error(&self, mut error: UiuaError, frame: StackFrame) -> UiuaError {
         let mut frames = Vec::new();
         for (span, prim) in &frame.spans {
@@ -332,7 +482,19 @@ code:
             )));
         }
         if !self.imports.lock().contains_key(path) {
-            let import = self.in_scope(false, |env| env.load_str_path(input, path).map(drop))?;
+            let import = if let Some(import) = self.cached_import(input, path) {
+                import
+            } else {
+                let impure_calls = self.impure_calls.load(Ordering::Relaxed);
+                let import =
+                    self.in_scope(false, |env| env.load_str_path(input, path).map(drop))?;
+                // The stack of a module that imports other files, has side effects, or is random
+                // may change even if its source does not, so it cannot be cached
+                if self.impure_calls.load(Ordering::Relaxed) == impure_calls {
+                    self.cache_import(input, path, &import);
+                }
+                import
+            };
             self.imports.lock().insert(path.into(), import);
         }
         self.stack.extend(self.imports.lock()[path].iter().cloned());
@@ -348,9 +510,22 @@ code:
         })
     }
     fn exec(&mut self, frame: StackFrame) -> UiuaResult {
+        if let Some(profiler) = &mut self.profiler {
+            profiler.enter_function(frame.function.id.clone());
+            let res = self.exec_frames(frame);
+            self.profiler.as_mut().unwrap().exit(None);
+            res
+        } else {
+            self.exec_frames(frame)
+        }
+    }
+    fn exec_frames(&mut self, frame: StackFrame) -> UiuaResult {
         let ret_height = self.scope.call.len();
         self.scope.call.push(frame);
         while self.scope.call.len() > ret_height {
+            if self.debugger.is_some() {
+                self.debug_pause()?;
+            }
             let frame = self.scope.call.last().unwrap();
             let Some(instr) = frame.function.instrs.get(frame.pc) else {
                 self.scope.call.pop();
@@ -369,6 +544,16 @@ code:
             // }
             // println!();
             // println!("  {:?}", instr);
+            let profiled_span = if let Some(profiler) = &mut self.profiler {
+                let prim = match instr {
+                    Instr::Prim(prim, _) => Some(*prim),
+                    _ => None,
+                };
+                profiler.enter_instr(prim);
+                Some(instr.span())
+            } else {
+                None
+            };
             let res = match instr {
                 Instr::Push(val) => {
                     self.stack.push(Value::clone(val));
@@ -405,6 +590,9 @@ code:
                 })(),
                 &Instr::Prim(prim, span) => (|| {
                     self.push_span(span, Some(prim));
+                    if !prim.is_pure() {
+                        self.impure_calls.fetch_add(1, Ordering::Relaxed);
+                    }
                     prim.run(self)?;
                     self.pop_span();
                     Ok(())
@@ -412,7 +600,10 @@ code:
                 &Instr::Call(span) => self
                     .pop("called function")
                     .and_then(|f| self.call_with_span(f, span)),
-                Instr::Dynamic(df) => df.f.clone()(self),
+                Instr::Dynamic(df) => {
+                    self.impure_calls.fetch_add(1, Ordering::Relaxed);
+                    df.f.clone()(self)
+                }
                 &Instr::PushTempUnder { count, span } => (|| {
                     self.push_span(span, None);
                     for _ in 0..count {
@@ -484,6 +675,13 @@ code:
                     Ok(())
                 })(),
             };
+            if let Some(span) = profiled_span {
+                let line = span.and_then(|i| match &self.spans.lock()[i] {
+                    Span::Code(span) => Some((span.path.clone(), span.start.line)),
+                    Span::Builtin => None,
+                });
+                self.profiler.as_mut().unwrap().exit(line);
+            }
             if let Err(mut err) = res {
                 // Trace errors
                 let frames = self
@@ -502,10 +700,86 @@ code:
                         return Err(UiuaError::Timeout(self.span()));
                     }
                 }
+                if self.abort.load(Ordering::Relaxed) {
+                    return Err(self.error("Execution aborted"));
+                }
             }
         }
         Ok(())
     }
+    /// Call the debugger if execution should pause before the next instruction
+    fn debug_pause(&mut self) -> UiuaResult {
+        let frame = self.scope.call.last().unwrap();
+        let Some(instr) = frame.function.instrs.get(frame.pc) else {
+            return Ok(());
+        };
+        let span = instr.span().map(|i| self.spans.lock()[i].clone());
+        let depth = self.call_depth();
+        let debugger = self.debugger.as_mut().unwrap();
+        // Only pause on entering a breakpoint
+        let breakpoint = match &span {
+            Some(Span::Code(span)) => debugger.breakpoint_at(span),
+            _ => None,
+        };
+        let entered = breakpoint
+            .clone()
+            .filter(|bp| debugger.last_breakpoint.as_ref() != Some(bp));
+        if span.is_some() {
+            debugger.last_breakpoint = breakpoint;
+        }
+        let reason = if let Some(bp) = entered {
+            PauseReason::Breakpoint(bp)
+        } else if debugger.step_pauses(depth) {
+            PauseReason::Step
+        } else {
+            return Ok(());
+        };
+        let handler = debugger.handler.clone();
+        let breakpoints = debugger.breakpoints.clone();
+        let frames = self.debug_frames();
+        let action = handler.lock().pause(DebugState {
+            reason,
+            frames,
+            stack: &self.stack,
+            inline_stack: &self.inline_stack,
+            under_stack: &self.under_stack,
+            bindings: self.all_bindings_in_scope(),
+            breakpoints,
+        });
+        let debugger = self.debugger.as_mut().unwrap();
+        debugger.step = match action {
+            DebugAction::StepInto => Step::Into,
+            DebugAction::StepOver => Step::Over(depth),
+            DebugAction::StepOut => Step::Out(depth),
+            DebugAction::Continue => Step::Continue,
+            DebugAction::Stop => return Err(self.error("Execution stopped by the debugger")),
+        };
+        Ok(())
+    }
+    /// Get the number of call frames across all scopes
+    fn call_depth(&self) -> usize {
+        (self.higher_scopes.iter())
+            .map(|scope| scope.call.len())
+            .sum::<usize>()
+            + self.scope.call.len()
+    }
+    /// Get the call frames across all scopes, skipping ones that are not executing
+    fn debug_frames(&self) -> Vec<DebugFrame> {
+        let spans = self.spans.lock();
+        (self.higher_scopes.iter())
+            .chain([&self.scope])
+            .flat_map(|scope| &scope.call)
+            .filter(|frame| frame.pc < frame.function.instrs.len())
+            .map(|frame| DebugFrame {
+                function: frame.function.clone(),
+                pc: frame.pc,
+                call_span: spans[frame.call_span].clone(),
+                span: frame.function.instrs[frame.pc]
+                    .span()
+                    .map(|i| spans[i].clone()),
+            })
+            .collect()
+    }
     pub(crate) fn push_span(&mut self, span: usize, prim: Option<Primitive>) {
         self.scope.call.last_mut().unwrap().spans.push((span, prim));
     }
@@ -632,6 +906,10 @@ code:
     pub fn take_stack(&mut self) -> Vec<Value> {
         take(&mut self.stack)
     }
+    /// Get a reference to the stack
+    pub fn stack(&self) -> &[Value] {
+        &self.stack
+    }
     /// Get the values for all bindings in the current scope
     pub fn all_bindings_in_scope(&self) -> HashMap<Ident, Value> {
         let mut bindings = HashMap::new();
@@ -728,6 +1006,9 @@ code:
         let n = self.scope.fills.nums.last().copied()?;
         (n.fract() == 0.0 && (0.0..=255.0).contains(&n)).then_some(n as u8)
     }
+    pub(crate) fn complex_fill(&self) -> Option<Complex> {
+        (self.scope.fills.complexes.last().copied()).or_else(|| self.num_fill().map(Complex::from))
+    }
     pub(crate) fn char_fill(&self) -> Option<char> {
         self.scope.fills.chars.last().copied()
     }
@@ -754,6 +1035,12 @@ code:
                     set = true;
                 }
             }
+            Value::Complex(c) => {
+                if let Some(&c) = c.as_scalar() {
+                    self.scope.fills.complexes.push(c);
+                    set = true;
+                }
+            }
             Value::Char(c) => {
                 if let Some(&c) = c.as_scalar() {
                     self.scope.fills.chars.push(c);
@@ -778,6 +1065,9 @@ code:
             Value::Num(_) | Value::Byte(_) => {
                 self.scope.fills.nums.pop();
             }
+            Value::Complex(_) => {
+                self.scope.fills.complexes.pop();
+            }
             Value::Char(_) => {
                 self.scope.fills.chars.pop();
             }
@@ -800,14 +1090,23 @@ code:
                 self.stack.len()
             )))?;
         }
-        let env = Uiua {
+        let stack = self
+            .stack
+            .drain(self.stack.len() - capture_count..)
+            .collect();
+        let env = self.fork(stack);
+        self.backend
+            .spawn(env, Box::new(f))
+            .map(Value::from)
+            .map_err(|e| self.error(e))
+    }
+    /// Create a runtime that can run code from this one on another thread
+    pub(crate) fn fork(&self, stack: Vec<Value>) -> Self {
+        Uiua {
             new_functions: Vec::new(),
             globals: self.globals.clone(),
             spans: self.spans.clone(),
-            stack: self
-                .stack
-                .drain(self.stack.len() - capture_count..)
-                .collect(),
+            stack,
             inline_stack: Vec::new(),
             under_stack: Vec::new(),
             scope: self.scope.clone(),
@@ -815,6 +1114,7 @@ code:
             mode: self.mode,
             current_imports: self.current_imports.clone(),
             imports: self.imports.clone(),
+            import_cache: self.import_cache.clone(),
             diagnostics: BTreeSet::new(),
             print_diagnostics: self.print_diagnostics,
             cli_arguments: self.cli_arguments.clone(),
@@ -822,11 +1122,32 @@ code:
             backend: self.backend.clone(),
             execution_limit: self.execution_limit,
             execution_start: self.execution_start,
-        };
-        self.backend
-            .spawn(env, Box::new(f))
-            .map(Value::from)
-            .map_err(|e| self.error(e))
+            abort: self.abort.clone(),
+            impure_calls: self.impure_calls.clone(),
+            max_threads: self.max_threads,
+            optimize: self.optimize,
+            compile_only: self.compile_only,
+            placeholders: self.placeholders.clone(),
+            placeholder_uses: 0,
+            debugger: self.debugger.clone(),
+            profiler: None,
+        }
+    }
+    /// Get the most threads that work may be split across
+    ///
+    /// Work is not split while debugging, so that loops can be stepped through.
+    pub(crate) fn max_threads(&self) -> usize {
+        if self.debugger.is_some() {
+            1
+        } else {
+            self.max_threads
+        }
+    }
+    /// Check whether compiled code should be optimized
+    ///
+    /// Code is not optimized while debugging, so that every instruction can be stepped through.
+    pub(crate) fn optimize(&self) -> bool {
+        self.optimize && self.debugger.is_none()
     }
     /// Wait for a thread to finish
     pub(crate) fn wait(&mut self, handle: Value) -> UiuaResult {
@@ -0,0 +1,806 @@
+//! A binary format for snapshots of a run
+//!
+//! A [`Snapshot`] stores the bindings and stack left after running a file,
+//! so they can be loaded directly with [`Uiua::load_snapshot`] without running the file again.
+//! It is not a serialization of the file's code: any side effects of running the file,
+//! such as printing or writing files, happen only when the snapshot is taken.
+//!
+//! Imports can be cached in this format with [`Uiua::with_import_cache`].
+
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use crate::{
+    array::{Array, Shape},
+    complex::Complex,
+    function::{Function, FunctionId, Instr, Signature},
+    lex::{CodeSpan, Loc, Span},
+    primitive::{Primitive, CONSTANTS},
+    value::Value,
+    Ident, Uiua, UiuaError, UiuaResult,
+};
+
+/// The version of the snapshot format
+///
+/// Snapshot data from other versions cannot be loaded.
+pub const FORMAT_VERSION: u16 = 2;
+
+const MAGIC: &[u8; 4] = b"UIUA";
+
+/// A snapshot of the bindings and stack left by a run
+///
+/// Values in a snapshot do not depend on the runtime they were taken in.
+#[derive(Clone)]
+pub struct Snapshot {
+    /// A hash of the path and source that were run
+    pub source_hash: u64,
+    /// The bindings defined at the top level, in the order they were defined
+    pub bindings: Vec<(Ident, Value)>,
+    /// The values left on the stack
+    pub stack: Vec<Value>,
+    /// The spans referred to by instructions
+    spans: Vec<Span>,
+}
+
+/// Hash a module's path and source
+///
+/// The hash is stable across runs and platforms, so it can be used to key caches.
+pub fn source_hash(path: Option<&Path>, input: &str) -> u64 {
+    // FNV-1a
+    let mut hash: u64 = 0xcbf29ce484222325;
+    let path = path.map(|path| path.to_string_lossy().into_owned());
+    let bytes = (FORMAT_VERSION.to_le_bytes().into_iter())
+        .chain(path.unwrap_or_default().into_bytes())
+        .chain([0])
+        .chain(input.bytes());
+    for byte in bytes {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+/// Check if some bytes look like a snapshot
+pub fn is_snapshot(bytes: &[u8]) -> bool {
+    bytes.starts_with(MAGIC)
+}
+
+impl Snapshot {
+    /// Serialize the snapshot
+    ///
+    /// Fails if the snapshot contains functions that are not made of instructions.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, String> {
+        let mut body = Encoder::default();
+        body.uint(self.spans.len());
+        for span in &self.spans {
+            match span {
+                Span::Code(span) => {
+                    body.u8(1);
+                    body.code_span(span);
+                }
+                Span::Builtin => body.u8(0),
+            }
+        }
+        body.uint(self.bindings.len());
+        for (name, value) in &self.bindings {
+            body.str(name);
+            body.value(value)?;
+        }
+        body.uint(self.stack.len());
+        for value in &self.stack {
+            body.value(value)?;
+        }
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend(FORMAT_VERSION.to_le_bytes());
+        bytes.extend(self.source_hash.to_le_bytes());
+        let mut header = Encoder::default();
+        header.uint(body.strings.len());
+        for s in &body.strings {
+            header.str(s);
+        }
+        bytes.extend(header.bytes);
+        bytes.extend(body.bytes);
+        Ok(bytes)
+    }
+    /// Deserialize a snapshot
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, String> {
+        let Some(bytes) = bytes.strip_prefix(MAGIC) else {
+            return Err("Data is not a Uiua snapshot".into());
+        };
+        let mut dec = Decoder {
+            bytes,
+            strings: Vec::new(),
+            prims: HashMap::new(),
+        };
+        let version = u16::from_le_bytes(dec.array()?);
+        if version != FORMAT_VERSION {
+            return Err(format!(
+                "Snapshot has format version {version}, \
+                but version {FORMAT_VERSION} is required"
+            ));
+        }
+        let source_hash = u64::from_le_bytes(dec.array()?);
+        for _ in 0..dec.uint()? {
+            let s = dec.str()?;
+            dec.strings.push(s.into());
+        }
+        let mut spans = Vec::new();
+        for _ in 0..dec.uint()? {
+            spans.push(match dec.u8()? {
+                0 => Span::Builtin,
+                _ => Span::Code(dec.code_span()?),
+            });
+        }
+        let mut bindings = Vec::new();
+        for _ in 0..dec.uint()? {
+            let name: Ident = dec.str()?.into();
+            bindings.push((name, dec.value(spans.len())?));
+        }
+        let mut stack = Vec::new();
+        for _ in 0..dec.uint()? {
+            stack.push(dec.value(spans.len())?);
+        }
+        if !dec.bytes.is_empty() {
+            return Err("Snapshot has trailing data".into());
+        }
+        Ok(Snapshot {
+            source_hash,
+            bindings,
+            stack,
+            spans,
+        })
+    }
+}
+
+impl Uiua {
+    /// Run a file and take a snapshot of its bindings and stack
+    ///
+    /// The file is run as it would be by [`Uiua::load_file`], including any side effects.
+    pub fn snapshot_file<P: AsRef<Path>>(&mut self, path: P) -> UiuaResult<Snapshot> {
+        let path = path.as_ref();
+        let input = fs::read_to_string(path).map_err(|e| UiuaError::Load(path.into(), e.into()))?;
+        self.load_str_path(&input, path)?;
+        let mut bindings: Vec<(Ident, usize)> = (self.scope.names.iter())
+            .filter(|(_, idx)| **idx >= CONSTANTS.len())
+            .map(|(name, idx)| (name.clone(), *idx))
+            .collect();
+        bindings.sort_by_key(|(_, idx)| *idx);
+        let bindings = {
+            let globals = self.globals.lock();
+            (bindings.into_iter())
+                .map(|(name, idx)| (name, globals[idx].clone()))
+                .collect()
+        };
+        Ok(self.snapshot(
+            source_hash(Some(path), &input),
+            bindings,
+            self.stack.clone(),
+        ))
+    }
+    /// Load a snapshot
+    ///
+    /// Its bindings are bound in the current scope and its stack values are pushed.
+    pub fn load_snapshot(&mut self, snapshot: &Snapshot) {
+        let values = self.import_snapshot(snapshot);
+        let (bindings, stack) = values.split_at(snapshot.bindings.len());
+        for ((name, _), value) in snapshot.bindings.iter().zip(bindings) {
+            let mut globals = self.globals.lock();
+            self.scope.names.insert(name.clone(), globals.len());
+            globals.push(value.clone());
+        }
+        self.stack.extend_from_slice(stack);
+    }
+    /// Set a directory in which snapshots of imports are cached
+    ///
+    /// Cached imports are keyed by a hash of their path and source.
+    /// Imports that run impure primitives, such as system functions (including `&i`) or `rand`,
+    /// are never cached, because their stacks do not depend only on their source.
+    /// The cache is accessed directly on the file system rather than through the system backend.
+    pub fn with_import_cache(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.import_cache = Some(dir.into());
+        self
+    }
+    /// Get the stack of an import from the cache
+    pub(crate) fn cached_import(&self, input: &str, path: &Path) -> Option<Vec<Value>> {
+        let cache_path = self.import_cache_path(input, path)?;
+        let bytes = fs::read(cache_path).ok()?;
+        let snapshot = Snapshot::from_bytes(&bytes).ok()?;
+        if snapshot.source_hash != source_hash(Some(path), input) {
+            return None;
+        }
+        Some(self.import_snapshot(&snapshot))
+    }
+    /// Save the stack of an import to the cache
+    ///
+    /// Failing to write the cache is not an error.
+    pub(crate) fn cache_import(&self, input: &str, path: &Path, stack: &[Value]) {
+        let Some(cache_path) = self.import_cache_path(input, path) else {
+            return;
+        };
+        let snapshot = self.snapshot(source_hash(Some(path), input), Vec::new(), stack.to_vec());
+        if let Ok(bytes) = snapshot.to_bytes() {
+            if let Some(dir) = cache_path.parent() {
+                _ = fs::create_dir_all(dir);
+            }
+            _ = fs::write(cache_path, bytes);
+        }
+    }
+    fn import_cache_path(&self, input: &str, path: &Path) -> Option<PathBuf> {
+        let dir = self.import_cache.as_ref()?;
+        let hash = source_hash(Some(path), input);
+        Some(dir.join(format!("{hash:016x}.uiuas")))
+    }
+    /// Make a snapshot whose spans are independent of this runtime
+    fn snapshot(
+        &self,
+        source_hash: u64,
+        bindings: Vec<(Ident, Value)>,
+        stack: Vec<Value>,
+    ) -> Snapshot {
+        let env_spans = self.spans.lock();
+        let mut spans = Vec::new();
+        let mut indices = HashMap::new();
+        let mut remap = |i: usize| {
+            *indices.entry(i).or_insert_with(|| {
+                spans.push(env_spans[i].clone());
+                spans.len() - 1
+            })
+        };
+        let bindings = (bindings.into_iter())
+            .map(|(name, value)| (name, map_spans(&value, &mut remap)))
+            .collect();
+        let stack = (stack.iter())
+            .map(|value| map_spans(value, &mut remap))
+            .collect();
+        Snapshot {
+            source_hash,
+            bindings,
+            stack,
+            spans,
+        }
+    }
+    /// Add a snapshot's spans to this runtime and get its binding values followed by its stack
+    fn import_snapshot(&self, snapshot: &Snapshot) -> Vec<Value> {
+        let mut env_spans = self.spans.lock();
+        let offset = env_spans.len();
+        env_spans.extend(snapshot.spans.iter().cloned());
+        drop(env_spans);
+        let mut remap = |i: usize| i + offset;
+        (snapshot.bindings.iter().map(|(_, value)| value))
+            .chain(&snapshot.stack)
+            .map(|value| map_spans(value, &mut remap))
+            .collect()
+    }
+}
+
+/// Change the span indices of all instructions in a value
+fn map_spans(value: &Value, f: &mut dyn FnMut(usize) -> usize) -> Value {
+    let Value::Func(arr) = value else {
+        return value.clone();
+    };
+    let data: Vec<Arc<Function>> = (arr.data.iter())
+        .map(|func| {
+            let instrs: Vec<Instr> = (func.instrs.iter())
+                .map(|instr| {
+                    let mut instr = match instr {
+                        Instr::Push(value) => Instr::push(map_spans(value, f)),
+                        instr => instr.clone(),
+                    };
+                    if let Some(span) = instr.span_mut() {
+                        *span = f(*span);
+                    }
+                    instr
+                })
+                .collect();
+            Arc::new(Function::new(func.id.clone(), instrs, func.signature()))
+        })
+        .collect();
+    Array::new(arr.shape.clone(), data).into()
+}
+
+#[derive(Default)]
+struct Encoder {
+    bytes: Vec<u8>,
+    strings: Vec<Arc<str>>,
+    string_indices: HashMap<Arc<str>, usize>,
+}
+
+impl Encoder {
+    fn u8(&mut self, n: u8) {
+        self.bytes.push(n);
+    }
+    /// Write an unsigned LEB128 integer
+    fn uint(&mut self, mut n: usize) {
+        loop {
+            let byte = (n & 0x7f) as u8;
+            n >>= 7;
+            if n == 0 {
+                self.bytes.push(byte);
+                break;
+            }
+            self.bytes.push(byte | 0x80);
+        }
+    }
+    fn str(&mut self, s: &str) {
+        self.uint(s.len());
+        self.bytes.extend(s.as_bytes());
+    }
+    /// Write a reference to an entry in the string table
+    fn string_ref(&mut self, s: &Arc<str>) {
+        let index = match self.string_indices.get(s) {
+            Some(index) => *index,
+            None => {
+                self.strings.push(s.clone());
+                self.string_indices
+                    .insert(s.clone(), self.strings.len() - 1);
+                self.strings.len() - 1
+            }
+        };
+        self.uint(index);
+    }
+    fn loc(&mut self, loc: Loc) {
+        self.uint(loc.char_pos);
+        self.uint(loc.byte_pos);
+        self.uint(loc.line);
+        self.uint(loc.col);
+    }
+    fn code_span(&mut self, span: &CodeSpan) {
+        self.loc(span.start);
+        self.loc(span.end);
+        match &span.path {
+            Some(path) => {
+                self.u8(1);
+                self.string_ref(&Arc::from(path.to_string_lossy()));
+            }
+            None => self.u8(0),
+        }
+        self.string_ref(&span.input);
+    }
+    fn shape(&mut self, shape: &[usize]) {
+        self.uint(shape.len());
+        for &dim in shape {
+            self.uint(dim);
+        }
+    }
+    fn value(&mut self, value: &Value) -> Result<(), String> {
+        match value {
+            Value::Num(arr) => {
+                self.u8(0);
+                self.shape(arr.shape());
+                for n in arr.data.iter() {
+                    self.bytes.extend(n.to_le_bytes());
+                }
+            }
+            Value::Byte(arr) => {
+                self.u8(1);
+                self.shape(arr.shape());
+                self.bytes.extend(arr.data.iter());
+            }
+            Value::Char(arr) => {
+                self.u8(2);
+                self.shape(arr.shape());
+                for c in arr.data.iter() {
+                    self.uint(*c as usize);
+                }
+            }
+            Value::Func(arr) => {
+                self.u8(3);
+                self.shape(arr.shape());
+                for f in arr.data.iter() {
+                    self.function(f)?;
+                }
+            }
+            Value::Complex(arr) => {
+                self.u8(4);
+                self.shape(arr.shape());
+                for c in arr.data.iter() {
+                    self.bytes.extend(c.re.to_le_bytes());
+                    self.bytes.extend(c.im.to_le_bytes());
+                }
+            }
+        }
+        Ok(())
+    }
+    fn function(&mut self, f: &Function) -> Result<(), String> {
+        self.function_id(&f.id);
+        self.uint(f.signature().args);
+        self.uint(f.signature().outputs);
+        self.uint(f.instrs.len());
+        for instr in &f.instrs {
+            self.instr(instr)?;
+        }
+        Ok(())
+    }
+    fn function_id(&mut self, id: &FunctionId) {
+        match id {
+            FunctionId::Named(name) => {
+                self.u8(0);
+                self.str(name);
+            }
+            FunctionId::Anonymous(span) => {
+                self.u8(1);
+                self.code_span(span);
+            }
+            FunctionId::Primitive(prim) => {
+                self.u8(2);
+                self.prim(*prim);
+            }
+            FunctionId::Constant => self.u8(3),
+            FunctionId::Main => self.u8(4),
+            FunctionId::Composed(ids) => {
+                self.u8(5);
+                self.uint(ids.len());
+                for id in ids {
+                    self.function_id(id);
+                }
+            }
+        }
+    }
+    fn prim(&mut self, prim: Primitive) {
+        self.str(&format!("{prim:?}"));
+    }
+    fn instr(&mut self, instr: &Instr) -> Result<(), String> {
+        match instr {
+            Instr::Push(value) => {
+                self.u8(0);
+                self.value(value)?;
+            }
+            Instr::BeginArray => self.u8(1),
+            Instr::EndArray { boxed, span } => {
+                self.u8(2);
+                self.u8(*boxed as u8);
+                self.uint(*span);
+            }
+            Instr::Prim(prim, span) => {
+                self.u8(3);
+                self.prim(*prim);
+                self.uint(*span);
+            }
+            Instr::Call(span) => {
+                self.u8(4);
+                self.uint(*span);
+            }
+            Instr::Dynamic(f) => {
+                return Err(format!(
+                    "Dynamic function {f:?} cannot be saved in a snapshot"
+                ))
+            }
+            Instr::PushTempUnder { count, span } => self.temp(5, *count, *span),
+            Instr::PopTempUnder { count, span } => self.temp(6, *count, *span),
+            Instr::PushTempInline { count, span } => self.temp(7, *count, *span),
+            Instr::PopTempInline { count, span } => self.temp(8, *count, *span),
+            Instr::CopyTempInline {
+                offset,
+                count,
+                span,
+            } => {
+                self.temp(9, *count, *span);
+                self.uint(*offset);
+            }
+            Instr::DropTempInline { count, span } => self.temp(10, *count, *span),
+        }
+        Ok(())
+    }
+    fn temp(&mut self, tag: u8, count: usize, span: usize) {
+        self.u8(tag);
+        self.uint(count);
+        self.uint(span);
+    }
+}
+
+struct Decoder<'a> {
+    bytes: &'a [u8],
+    strings: Vec<Arc<str>>,
+    prims: HashMap<String, Primitive>,
+}
+
+impl<'a> Decoder<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], String> {
+        if self.bytes.len() < n {
+            return Err("Snapshot ended unexpectedly".into());
+        }
+        let (taken, rest) = self.bytes.split_at(n);
+        self.bytes = rest;
+        Ok(taken)
+    }
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], String> {
+        Ok(self.take(N)?.try_into().unwrap())
+    }
+    fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.take(1)?[0])
+    }
+    fn uint(&mut self) -> Result<usize, String> {
+        let mut n = 0usize;
+        let mut shift = 0;
+        loop {
+            let byte = self.u8()?;
+            if shift >= usize::BITS {
+                return Err("Snapshot has an invalid integer".into());
+            }
+            n |= ((byte & 0x7f) as usize) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(n);
+            }
+            shift += 7;
+        }
+    }
+    fn str(&mut self) -> Result<&'a str, String> {
+        let len = self.uint()?;
+        std::str::from_utf8(self.take(len)?).map_err(|e| e.to_string())
+    }
+    fn string_ref(&mut self) -> Result<Arc<str>, String> {
+        let index = self.uint()?;
+        (self.strings.get(index).cloned())
+            .ok_or_else(|| "Snapshot has an invalid string reference".into())
+    }
+    fn loc(&mut self) -> Result<Loc, String> {
+        Ok(Loc {
+            char_pos: self.uint()?,
+            byte_pos: self.uint()?,
+            line: self.uint()?,
+            col: self.uint()?,
+        })
+    }
+    fn code_span(&mut self) -> Result<CodeSpan, String> {
+        let start = self.loc()?;
+        let end = self.loc()?;
+        let path = match self.u8()? {
+            0 => None,
+            _ => Some(Path::new(&*self.string_ref()?).into()),
+        };
+        let input = self.string_ref()?;
+        Ok(CodeSpan {
+            start,
+            end,
+            path,
+            input,
+        })
+    }
+    fn shape(&mut self) -> Result<Shape, String> {
+        let rank = self.uint()?;
+        (0..rank).map(|_| self.uint()).collect()
+    }
+    fn value(&mut self, span_count: usize) -> Result<Value, String> {
+        let tag = self.u8()?;
+        let shape = self.shape()?;
+        // Guard against overflow and allocating for a corrupt shape.
+        // Every element takes at least one byte.
+        let len = if shape.contains(&0) {
+            Some(0)
+        } else {
+            (shape.iter()).try_fold(1usize, |len, &dim| len.checked_mul(dim))
+        };
+        let len = match len {
+            Some(len) if len <= self.bytes.len() => len,
+            _ => return Err("Snapshot has an invalid array shape".into()),
+        };
+        Ok(match tag {
+            0 => {
+                let data: Vec<f64> = (0..len)
+                    .map(|_| self.array().map(f64::from_le_bytes))
+                    .collect::<Result<_, _>>()?;
+                Array::new(shape, data).into()
+            }
+            1 => Array::new(shape, self.take(len)?.to_vec()).into(),
+            2 => {
+                let data: Vec<char> = (0..len)
+                    .map(|_| {
+                        let n = self.uint()?;
+                        (u32::try_from(n).ok().and_then(char::from_u32))
+                            .ok_or_else(|| format!("Snapshot has invalid character {n}"))
+                    })
+                    .collect::<Result<_, _>>()?;
+                Array::new(shape, data).into()
+            }
+            3 => {
+                let data: Vec<Arc<Function>> = (0..len)
+                    .map(|_| self.function(span_count).map(Arc::new))
+                    .collect::<Result<_, _>>()?;
+                Array::new(shape, data).into()
+            }
+            4 => {
+                let data: Vec<Complex> = (0..len)
+                    .map(|_| {
+                        let re = f64::from_le_bytes(self.array()?);
+                        let im = f64::from_le_bytes(self.array()?);
+                        Ok::<_, String>(Complex::new(re, im))
+                    })
+                    .collect::<Result<_, _>>()?;
+                Array::new(shape, data).into()
+            }
+            tag => return Err(format!("Snapshot has invalid value tag {tag}")),
+        })
+    }
+    fn function(&mut self, span_count: usize) -> Result<Function, String> {
+        let id = self.function_id()?;
+        let sig = Signature::new(self.uint()?, self.uint()?);
+        let instrs = (0..self.uint()?)
+            .map(|_| self.instr(span_count))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Function::new(id, instrs, sig))
+    }
+    fn function_id(&mut self) -> Result<FunctionId, String> {
+        Ok(match self.u8()? {
+            0 => FunctionId::Named(self.str()?.into()),
+            1 => FunctionId::Anonymous(self.code_span()?),
+            2 => FunctionId::Primitive(self.prim()?),
+            3 => FunctionId::Constant,
+            4 => FunctionId::Main,
+            5 => FunctionId::Composed(
+                (0..self.uint()?)
+                    .map(|_| self.function_id())
+                    .collect::<Result<_, _>>()?,
+            ),
+            tag => return Err(format!("Snapshot has invalid function id tag {tag}")),
+        })
+    }
+    fn prim(&mut self) -> Result<Primitive, String> {
+        if self.prims.is_empty() {
+            self.prims = Primitive::all().map(|p| (format!("{p:?}"), p)).collect();
+        }
+        let name = self.str()?;
+        (self.prims.get(name).copied())
+            .ok_or_else(|| format!("Snapshot has unknown primitive {name}"))
+    }
+    fn span(&mut self, span_count: usize) -> Result<usize, String> {
+        let span = self.uint()?;
+        if span < span_count {
+            Ok(span)
+        } else {
+            Err("Snapshot has an invalid span reference".into())
+        }
+    }
+    fn instr(&mut self, span_count: usize) -> Result<Instr, String> {
+        Ok(match self.u8()? {
+            0 => Instr::push(self.value(span_count)?),
+            1 => Instr::BeginArray,
+            2 => Instr::EndArray {
+                boxed: self.u8()? != 0,
+                span: self.span(span_count)?,
+            },
+            3 => Instr::Prim(self.prim()?, self.span(span_count)?),
+            4 => Instr::Call(self.span(span_count)?),
+            tag @ 5..=10 => {
+                let count = self.uint()?;
+                let span = self.span(span_count)?;
+                match tag {
+                    5 => Instr::PushTempUnder { count, span },
+                    6 => Instr::PopTempUnder { count, span },
+                    7 => Instr::PushTempInline { count, span },
+                    8 => Instr::PopTempInline { count, span },
+                    9 => Instr::CopyTempInline {
+                        offset: self.uint()?,
+                        count,
+                        span,
+                    },
+                    _ => Instr::DropTempInline { count, span },
+                }
+            }
+            tag => return Err(format!("Snapshot has invalid instruction tag {tag}")),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let dir = std::env::temp_dir().join("uiua-snapshot-round-trip");
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("lib.ua");
+        fs::write(&path, "F ← +1\nG ← ≡(×2)\nH ← ⊡5\n[1 2 3]").unwrap();
+
+        let snapshot = Uiua::with_native_sys().snapshot_file(&path).unwrap();
+        let bytes = snapshot.to_bytes().unwrap();
+        assert!(is_snapshot(&bytes));
+        let loaded = Snapshot::from_bytes(&bytes).unwrap();
+        assert_eq!(loaded.source_hash, snapshot.source_hash);
+        let names: Vec<&str> = loaded.bindings.iter().map(|(name, _)| &**name).collect();
+        assert_eq!(names, ["F", "G", "H"]);
+
+        let mut env = Uiua::with_native_sys();
+        env.load_snapshot(&loaded);
+        env.load_str("F G [1 2]").unwrap();
+        let stack = env.take_stack();
+        assert_eq!(stack[0], Value::from(vec![1.0, 2.0, 3.0]));
+        assert_eq!(stack[1], Value::from(vec![3.0, 5.0]));
+
+        // Errors still point into the original source
+        let err = env.load_str("H [1 2]").unwrap_err().to_string();
+        assert!(err.contains("lib.ua:3:5"), "{err}");
+
+        // Corrupt data is rejected
+        assert!(Snapshot::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+        let mut wrong_version = bytes.clone();
+        wrong_version[4] = wrong_version[4].wrapping_add(1);
+        assert!(Snapshot::from_bytes(&wrong_version).is_err());
+
+        // Shapes whose sizes overflow are rejected
+        let mut huge = Encoder::default();
+        huge.u8(0);
+        huge.shape(&[usize::MAX, 3]);
+        let mut dec = Decoder {
+            bytes: &huge.bytes,
+            strings: Vec::new(),
+            prims: HashMap::new(),
+        };
+        assert!(dec.value(0).is_err());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn import_cache() {
+        let dir = std::env::temp_dir().join("uiua-import-cache");
+        _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let lib = dir.join("lib.ua");
+        fs::write(&lib, "Times ← ×10\nTimes_Times").unwrap();
+        let main = format!(
+            "m ← &i {:?}\nT ← use \"Times\" m\nT 4",
+            lib.to_string_lossy()
+        );
+        let cache = dir.join("cache");
+
+        let run = || {
+            let mut env = Uiua::with_native_sys().with_import_cache(&cache);
+            env.load_str(&main).unwrap();
+            env.take_stack()
+        };
+        assert_eq!(run(), [Value::from(40.0)]);
+        assert_eq!(fs::read_dir(&cache).unwrap().count(), 1);
+        // The second run loads the import from the cache
+        assert_eq!(run(), [Value::from(40.0)]);
+
+        // Changing the source invalidates the cache entry
+        fs::write(&lib, "Times ← ×100\nTimes_Times").unwrap();
+        assert_eq!(run(), [Value::from(400.0)]);
+        assert_eq!(fs::read_dir(&cache).unwrap().count(), 2);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn import_cache_dependencies() {
+        let dir = std::env::temp_dir().join("uiua-import-cache-dependencies");
+        _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let inner = dir.join("inner.ua");
+        let outer = dir.join("outer.ua");
+        fs::write(&inner, "Plus ← +10\nPlus_Plus").unwrap();
+        fs::write(
+            &outer,
+            format!(
+                "n ← &i {:?}\nP ← use \"Plus\" n\nTimes ← ×P 0\nTimes_Times",
+                inner.to_string_lossy()
+            ),
+        )
+        .unwrap();
+        let main = format!(
+            "m ← &i {:?}\nT ← use \"Times\" m\nT 4",
+            outer.to_string_lossy()
+        );
+        let cache = dir.join("cache");
+
+        let run = || {
+            let mut env = Uiua::with_native_sys().with_import_cache(&cache);
+            env.load_str(&main).unwrap();
+            env.take_stack()
+        };
+        assert_eq!(run(), [Value::from(40.0)]);
+        // Only the module without imports is cached
+        assert_eq!(fs::read_dir(&cache).unwrap().count(), 1);
+
+        // Changing a transitive import changes the result
+        fs::write(&inner, "Plus ← +100\nPlus_Plus").unwrap();
+        assert_eq!(run(), [Value::from(400.0)]);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -1,31 +1,45 @@
 use std::{
     any::Any,
-    collections::{HashMap, HashSet},
+    collections::{HashMap, HashSet, VecDeque},
     env,
     fs::{self, File},
     io::{stderr, stdin, stdout, BufRead, Cursor, Read, Write},
     net::*,
-    process::Command,
+    process::{Child, ChildStdin, Command, Stdio},
     sync::{
         atomic::{self, AtomicU64},
         Arc, OnceLock,
     },
     thread::{sleep, spawn, JoinHandle},
-    time::Duration,
+    time::{Duration, UNIX_EPOCH},
 };
 
+use base64::{prelude::BASE64_STANDARD, Engine};
 use bufreaderwriter::seq::BufReaderWriterSeq;
+use crossbeam_channel::{unbounded, Receiver, Sender, TryRecvError};
 use dashmap::DashMap;
 use enum_iterator::Sequence;
 use hound::{SampleFormat, WavReader, WavSpec, WavWriter};
 use image::{DynamicImage, ImageOutputFormat};
 use once_cell::sync::Lazy;
-use parking_lot::Mutex;
+use parking_lot::{Condvar, Mutex};
 use tinyvec::tiny_vec;
 
 use crate::{
-    array::Array, cowslice::CowSlice, function::Function, grid_fmt::GridFmt, primitive::PrimDoc,
-    value::Value, Uiua, UiuaError, UiuaResult,
+    array::Array,
+    compress::{
+        deflate_compress, deflate_decompress, gzip_compress, gzip_decompress, zip_list, zip_read,
+        zip_write, zlib_compress, zlib_decompress,
+    },
+    cowslice::CowSlice,
+    csv::{csv_to_value, value_to_csv},
+    function::Function,
+    grid_fmt::GridFmt,
+    hash::{digest, hex_decode, hex_encode},
+    json::{json_to_value, value_to_json},
+    primitive::PrimDoc,
+    value::Value,
+    Uiua, UiuaError, UiuaResult,
 };
 
 pub fn example_ua<T>(f: impl FnOnce(&mut String) -> T) -> T {
@@ -133,6 +147,32 @@ sys_op! {
     ///
     /// Expects either a string, a rank `2` character array, or a rank `1` array of [box] strings.
     (1(2), RunCapture, "&runc", "run command capture"),
+    /// Run a command without waiting for it to finish
+    ///
+    /// Returns a handle to the running process.
+    /// Writing to the handle with [&w] writes to the process's stdin.
+    /// Reading from the handle with [&rs], [&rb], or [&ru] reads from the process's stdout.
+    /// Use [&pse] to read from its stderr.
+    /// The process's stdout and stderr are read into memory as it runs, so it never waits for them to be read.
+    ///
+    /// Expects either a string, a rank `2` character array, or a rank `1` array of [box] strings.
+    ///
+    /// See also: [&pw] [&pk]
+    (1, RunStream, "&runs", "run command stream"),
+    /// Get a handle to the stderr of a process started with [&runs]
+    ///
+    /// The returned handle can be read from with [&rs], [&rb], or [&ru].
+    /// This can only be done once per process.
+    (1, ProcessStderr, "&pse", "process - stderr"),
+    /// Wait for a process started with [&runs] to finish and get its exit code
+    ///
+    /// The process's stdin is closed before waiting, so processes that read until the end of their input will finish.
+    /// If the process was terminated by a signal, the exit code is `¯1`.
+    /// Any output that was not read yet can still be read after waiting.
+    /// Waiting does not stop other threads from using the process's handles.
+    (1, ProcessWait, "&pw", "process - wait"),
+    /// Kill a process started with [&runs]
+    (1(0), ProcessKill, "&pk", "process - kill"),
     /// Change the current directory
     (1(0), ChangeDirectory, "&cd", "change directory"),
     /// Sleep for n seconds
@@ -159,7 +199,11 @@ sys_op! {
     (1, Import, "&i", "import"),
     /// Close a stream by its handle
     ///
-    /// This will close files, tcp listeners, and tcp sockets.
+    /// This will close files, tcp listeners, tcp sockets, udp sockets, process streams, and channels.
+    /// Closing a process's handle does not kill it.
+    /// A process that is still running is waited on in the background,
+    /// and anything it writes to its output after that is discarded.
+    /// Closing a channel frees it, so it can no longer be used at all.
     (1(0), Close, "&cl", "close handle"),
     /// Open a file and return a handle to it
     ///
@@ -177,6 +221,30 @@ sys_op! {
     (1, FListDir, "&fld", "file - list directory"),
     /// Check if a path is a file
     (1, FIsFile, "&fif", "file - is file"),
+    /// Check if a path is a directory
+    (1, FIsDir, "&fid", "file - is directory"),
+    /// Get the size of a file in bytes
+    (1, FSize, "&fsz", "file - size"),
+    /// Get the time a file was last modified
+    ///
+    /// The time is in seconds since the Unix epoch.
+    (1, FModified, "&fmt", "file - modified time"),
+    /// Delete a file or directory
+    ///
+    /// Directories are deleted along with everything in them.
+    (1(0), FDelete, "&fde", "file - delete"),
+    /// Move or rename a file or directory
+    ///
+    /// Expects the current path and the new path.
+    (2(0), FRename, "&fmv", "file - move"),
+    /// Copy a file
+    ///
+    /// Expects the path to copy from and the path to copy to.
+    (2(0), FCopy, "&fcp", "file - copy"),
+    /// Create a directory
+    ///
+    /// Any missing parent directories will also be created.
+    (1(0), FMakeDir, "&fmd", "file - make directory"),
     /// Read all the contents of a file into a string
     ///
     /// Expects a path and returns a [rank]`1` character array.
@@ -309,6 +377,208 @@ sys_op! {
     (2(0), TcpSetWriteTimeout, "&tcpswt", "tcp - set write timeout"),
     /// Get the connection address of a TCP socket
     (1, TcpAddr, "&tcpaddr", "tcp - address"),
+    /// Decode a JSON string
+    ///
+    /// Numbers become numbers, `true` and `false` become `1` and `0`, and `null` becomes `NaN`.
+    /// Strings become character lists.
+    /// Arrays of numbers, or of strings that are all the same length, become arrays with one more axis.
+    /// Other arrays become lists of [box]es.
+    /// ex: &jsond "[1, 2, 3]"
+    /// ex: &jsond "[[1, 2], [3, 4]]"
+    /// ex: &jsond "[1, \"two\", [3]]"
+    /// Objects become rank `2` arrays of boxed key-value pairs.
+    /// ex: &jsond "{\"name\": \"Uiua\", \"version\": 18}"
+    ///
+    /// Anything decoded with [&jsond] can be encoded back to equivalent JSON with [&jsone].
+    (1, JsonDecode, "&jsond", "json - decode"),
+    /// Encode a value as a JSON string
+    ///
+    /// This is the inverse of [&jsond].
+    /// ex: &jsone [1_2 3_4]
+    /// ex: &jsone {"a" [1 2 3]}
+    /// Rank `2` arrays of boxed pairs whose first column is strings become objects.
+    /// ex: &jsone [{"name" "Uiua"} {"version" 18}]
+    ///
+    /// `NaN` is encoded as `null`, but infinities and functions cannot be encoded.
+    /// ex! &jsone ∞
+    (1, JsonEncode, "&jsone", "json - encode"),
+    /// Decode a CSV string into a rank `2` array of [box]ed strings
+    ///
+    /// Rows that are shorter than the longest row are padded with empty strings.
+    /// ex: &csvd "a,b\n1,2\n3,4"
+    ///
+    /// See also: [&csvdn] [&csve]
+    (1, CsvDecode, "&csvd", "csv - decode"),
+    /// Decode a CSV string into a rank `2` array, parsing numeric columns into numbers
+    ///
+    /// A column is numeric if all of its cells, other than one in the first row, are numbers or empty.
+    /// This means that a header row is left as strings.
+    /// Empty cells in numeric columns become `NaN`.
+    /// ex: &csvdn "name,age\nAnn,30\nBob,25"
+    /// If every cell is a number, the result is a plain numeric array.
+    /// ex: &csvdn "1,2\n3,4"
+    (1, CsvDecodeNumbers, "&csvdn", "csv - decode numbers"),
+    /// Encode a rank `1` or `2` array as a CSV string
+    ///
+    /// Cells can be numbers or [box]ed numbers or strings.
+    /// Each row of a character array is one cell, so a list of strings of the same length is a column.
+    /// Cells are quoted if they need to be.
+    /// ex: &csve [1_2 3_4]
+    /// ex: &csve [{"name" "quote"} {"Uiua" "Hello, World!"}]
+    (1, CsvEncode, "&csve", "csv - encode"),
+    /// Compute the hash of a byte array or string
+    ///
+    /// The first argument is the name of the algorithm, which is one of `"sha256"`, `"sha1"`, or `"md5"`.
+    /// Strings are hashed as UTF-8. The hash is returned as a byte array.
+    /// ex: &hash "sha256" "abc"
+    /// Use [&hexe] to get a hex string.
+    /// ex: &hexe &hash "sha1" "abc"
+    /// ex: &hexe &hash "md5" "abc"
+    (2, Hash, "&hash", "hash"),
+    /// Compute the CRC32 checksum of a byte array or string
+    ///
+    /// Strings are hashed as UTF-8. The checksum is returned as `4` big-endian bytes.
+    /// ex: &crc "abc"
+    (1, Crc32, "&crc", "hash - crc32"),
+    /// Encode a byte array or string as base64
+    ///
+    /// Strings are encoded as UTF-8.
+    /// ex: &bsfe "Hello, World!"
+    /// ex: &bsfe [0 1 2 3 255]
+    (1, Base64Encode, "&bsfe", "base64 - encode"),
+    /// Decode a base64 string into a byte array
+    ///
+    /// This is the inverse of [&bsfe].
+    /// ex: &bsfd "AAECA/8="
+    /// ex! &bsfd "not base64!"
+    (1, Base64Decode, "&bsfd", "base64 - decode"),
+    /// Encode a byte array or string as lowercase hexadecimal
+    ///
+    /// Strings are encoded as UTF-8.
+    /// ex: &hexe [0 15 16 255]
+    (1, HexEncode, "&hexe", "hex - encode"),
+    /// Decode a hexadecimal string into a byte array
+    ///
+    /// Both uppercase and lowercase digits are accepted.
+    /// ex: &hexd "000f10FF"
+    /// ex! &hexd "abc"
+    (1, HexDecode, "&hexd", "hex - decode"),
+    /// Compress a byte array or string with gzip
+    ///
+    /// Strings are compressed as UTF-8.
+    /// ex: ⧻&gzc ♭↯100 "Uiua"
+    /// See also: [&gzd] [&zlibc]
+    (1, GzipCompress, "&gzc", "gzip - compress"),
+    /// Decompress a gzipped byte array
+    ///
+    /// This is the inverse of [&gzc].
+    /// It is an error if the data decompresses to more than 1GB.
+    /// ex: +@\0 &gzd &gzc "Hello, World!"
+    /// ex! &gzd [1 2 3]
+    (1, GzipDecompress, "&gzd", "gzip - decompress"),
+    /// Compress a byte array or string with zlib
+    ///
+    /// zlib data is deflated data with a small header and checksum.
+    /// Strings are compressed as UTF-8.
+    /// ex: &zlibc "Hello, World!"
+    (1, ZlibCompress, "&zlibc", "zlib - compress"),
+    /// Decompress a zlib-compressed byte array
+    ///
+    /// This is the inverse of [&zlibc].
+    /// It is an error if the data decompresses to more than 1GB.
+    /// ex: +@\0 &zlibd &zlibc "Hello, World!"
+    (1, ZlibDecompress, "&zlibd", "zlib - decompress"),
+    /// Compress a byte array or string with raw deflate
+    ///
+    /// Unlike [&gzc] and [&zlibc], the result has no header or checksum.
+    /// Strings are compressed as UTF-8.
+    /// ex: &deflc "Hello, World!"
+    (1, DeflateCompress, "&deflc", "deflate - compress"),
+    /// Decompress a raw deflated byte array
+    ///
+    /// This is the inverse of [&deflc].
+    /// It is an error if the data decompresses to more than 1GB.
+    /// ex: +@\0 &defld &deflc "Hello, World!"
+    (1, DeflateDecompress, "&defld", "deflate - decompress"),
+    /// List the names of the entries in a zip archive
+    ///
+    /// The archive should be a byte array, like one read with [&frab].
+    /// ex: &zipl &zipw [{"a.txt" "hello"} {"b/c.txt" "world"}]
+    (1, ZipList, "&zipl", "zip - list entries"),
+    /// Read an entry from a zip archive
+    ///
+    /// Expects the entry name and the archive bytes.
+    /// The entry's contents are returned as a byte array.
+    /// ex: +@\0 &zipr "a.txt" &zipw [{"a.txt" "hello"} {"b/c.txt" "world"}]
+    /// ex! &zipr "d.txt" &zipw [{"a.txt" "hello"}]
+    (2, ZipRead, "&zipr", "zip - read entry"),
+    /// Write a zip archive
+    ///
+    /// Expects a rank `2` array of [box]ed name-content pairs.
+    /// Contents can be byte arrays or strings.
+    /// The archive is returned as a byte array, which can be written to a file with [&fwa].
+    /// ex: &zipw [{"a.txt" "hello"} {"b/c.txt" "world"}]
+    (1, ZipWrite, "&zipw", "zip - write archive"),
+    /// Create a UDP socket and bind it to an address
+    ///
+    /// Use port `0` to let the operating system pick a free port. [&udpaddr] will tell you which one it picked.
+    (1, UdpBind, "&udpb", "udp - bind"),
+    /// Connect a UDP socket to an address
+    ///
+    /// Once connected, the socket can be written to with [&w] and read from with [&rs] or [&rb].
+    /// Packets from other addresses will be ignored.
+    (2(0), UdpConnect, "&udpc", "udp - connect"),
+    /// Send bytes or a string to an address with a UDP socket
+    ///
+    /// Expects the data, the address, and the socket handle.
+    (3(0), UdpSendTo, "&udpst", "udp - send to"),
+    /// Receive at most n bytes with a UDP socket
+    ///
+    /// The received bytes are pushed on top of the address that sent them.
+    (2(2), UdpReceiveFrom, "&udprf", "udp - receive from"),
+    /// Set the read timeout of a UDP socket in seconds
+    (2(0), UdpSetReadTimeout, "&udpsrt", "udp - set read timeout"),
+    /// Set the write timeout of a UDP socket in seconds
+    (2(0), UdpSetWriteTimeout, "&udpswt", "udp - set write timeout"),
+    /// Get the local address of a UDP socket
+    (1, UdpAddr, "&udpaddr", "udp - address"),
+    /// Create a channel for sending values between threads
+    ///
+    /// Values can be sent with [&chs] and received with [&chr] or [&chtr].
+    /// Any thread can send to or receive from the channel.
+    /// ex: ch ← &chn
+    ///   : &chs 1 ch
+    ///   : &chs 2 ch
+    ///   : &chr ch
+    ///   : &chr ch
+    ///
+    /// See also: [spawn] [wait]
+    (0, ChannelNew, "&chn", "channel - new"),
+    /// Send a value on a channel
+    ///
+    /// Sending never blocks.
+    /// It is an error to send on a channel that has been closed with [&chc].
+    (2(0), ChannelSend, "&chs", "channel - send"),
+    /// Receive a value from a channel
+    ///
+    /// Blocks until a value is sent.
+    /// If the channel is closed and all sent values have been received, this is an error.
+    (1, ChannelRecv, "&chr", "channel - receive"),
+    /// Try to receive a value from a channel without blocking
+    ///
+    /// Pushes the value and then `1` if one was available, or `0` and `0` if not.
+    /// Like [&chr], this is an error if the channel is closed and all sent values have been received,
+    /// so a loop that polls a channel stops once it is done.
+    /// ex: ch ← &chn
+    ///   : &chs 5 ch
+    ///   : [&chtr ch]
+    ///   : [&chtr ch]
+    (1(2), ChannelTryRecv, "&chtr", "channel - try receive"),
+    /// Close a channel
+    ///
+    /// No more values can be sent on a closed channel, but values that were already sent can still be received.
+    /// Use [&cl] to free a channel once it is no longer needed.
+    (1(0), ChannelClose, "&chc", "channel - close"),
     /// Make an HTTP request
     ///
     /// Takes in an 1.x HTTP request and returns an HTTP response.
@@ -397,6 +667,28 @@ pub trait SysBackend: Any + Send + Sync + 'static {
     fn is_file(&self, path: &str) -> Result<bool, String> {
         Err("This IO operation is not supported in this environment".into())
     }
+    fn is_dir(&self, path: &str) -> Result<bool, String> {
+        Err("This IO operation is not supported in this environment".into())
+    }
+    fn file_size(&self, path: &str) -> Result<u64, String> {
+        Err("This IO operation is not supported in this environment".into())
+    }
+    /// Get the time a file was last modified in seconds since the Unix epoch
+    fn file_modified(&self, path: &str) -> Result<f64, String> {
+        Err("This IO operation is not supported in this environment".into())
+    }
+    fn delete(&self, path: &str) -> Result<(), String> {
+        Err("This IO operation is not supported in this environment".into())
+    }
+    fn rename(&self, from: &str, to: &str) -> Result<(), String> {
+        Err("This IO operation is not supported in this environment".into())
+    }
+    fn copy_file(&self, from: &str, to: &str) -> Result<(), String> {
+        Err("This IO operation is not supported in this environment".into())
+    }
+    fn make_dir(&self, path: &str) -> Result<(), String> {
+        Err("This IO operation is not supported in this environment".into())
+    }
     fn read(&self, handle: Handle, count: usize) -> Result<Vec<u8>, String> {
         Err("This IO operation is not supported in this environment".into())
     }
@@ -482,9 +774,58 @@ pub trait SysBackend: Any + Send + Sync + 'static {
     ) -> Result<(), String> {
         Err("TCP sockets are not supported in this environment".into())
     }
+    fn udp_bind(&self, addr: &str) -> Result<Handle, String> {
+        Err("UDP sockets are not supported in this environment".into())
+    }
+    fn udp_connect(&self, handle: Handle, addr: &str) -> Result<(), String> {
+        Err("UDP sockets are not supported in this environment".into())
+    }
+    fn udp_send_to(&self, handle: Handle, data: &[u8], addr: &str) -> Result<(), String> {
+        Err("UDP sockets are not supported in this environment".into())
+    }
+    /// Receive a single packet of at most `len` bytes, along with the address that sent it
+    fn udp_recv_from(&self, handle: Handle, len: usize) -> Result<(Vec<u8>, String), String> {
+        Err("UDP sockets are not supported in this environment".into())
+    }
+    fn udp_addr(&self, handle: Handle) -> Result<String, String> {
+        Err("UDP sockets are not supported in this environment".into())
+    }
+    fn udp_set_read_timeout(
+        &self,
+        handle: Handle,
+        timeout: Option<Duration>,
+    ) -> Result<(), String> {
+        Err("UDP sockets are not supported in this environment".into())
+    }
+    fn udp_set_write_timeout(
+        &self,
+        handle: Handle,
+        timeout: Option<Duration>,
+    ) -> Result<(), String> {
+        Err("UDP sockets are not supported in this environment".into())
+    }
     fn close(&self, handle: Handle) -> Result<(), String> {
         Ok(())
     }
+    fn channel_new(&self) -> Result<Handle, String> {
+        Err("Channels are not supported in this environment".into())
+    }
+    fn channel_send(&self, handle: Handle, value: Value) -> Result<(), String> {
+        Err("Channels are not supported in this environment".into())
+    }
+    fn channel_recv(&self, handle: Handle) -> Result<Value, String> {
+        Err("Channels are not supported in this environment".into())
+    }
+    /// Receive a value from a channel without blocking
+    ///
+    /// Should return `Ok(None)` if no value is available yet,
+    /// and an error if the channel is closed and empty.
+    fn channel_try_recv(&self, handle: Handle) -> Result<Option<Value>, String> {
+        Err("Channels are not supported in this environment".into())
+    }
+    fn channel_close(&self, handle: Handle) -> Result<(), String> {
+        Err("Channels are not supported in this environment".into())
+    }
     fn spawn(
         &self,
         env: Uiua,
@@ -507,6 +848,19 @@ pub trait SysBackend: Any + Send + Sync + 'static {
     ) -> Result<(String, String), String> {
         Err("Running commands is not supported in this environment".into())
     }
+    fn run_command_stream(&self, command: &str, args: &[&str]) -> Result<Handle, String> {
+        Err("Running commands is not supported in this environment".into())
+    }
+    fn process_stderr(&self, handle: Handle) -> Result<Handle, String> {
+        Err("Running commands is not supported in this environment".into())
+    }
+    /// Wait for a process to finish and get its exit code
+    fn process_wait(&self, handle: Handle) -> Result<i32, String> {
+        Err("Running commands is not supported in this environment".into())
+    }
+    fn process_kill(&self, handle: Handle) -> Result<(), String> {
+        Err("Running commands is not supported in this environment".into())
+    }
     fn change_directory(&self, path: &str) -> Result<(), String> {
         Err("Changing directories is not supported in this environment".into())
     }
@@ -526,7 +880,11 @@ struct GlobalNativeSys {
     tcp_listeners: DashMap<Handle, TcpListener>,
     tcp_sockets: DashMap<Handle, Buffered<TcpStream>>,
     hostnames: DashMap<Handle, String>,
+    udp_sockets: DashMap<Handle, UdpSocket>,
+    processes: DashMap<Handle, ChildProcess>,
+    process_stderrs: DashMap<Handle, ProcessOutput>,
     threads: DashMap<Handle, JoinHandle<UiuaResult<Vec<Value>>>>,
+    channels: DashMap<Handle, Arc<Channel>>,
     #[cfg(feature = "audio")]
     audio_stream_time: Mutex<Option<f64>>,
     #[cfg(feature = "audio")]
@@ -534,10 +892,101 @@ struct GlobalNativeSys {
     colored_errors: DashMap<String, String>,
 }
 
+/// A process started with [`SysOp::RunStream`]
+struct ChildProcess {
+    child: Child,
+    /// Dropped when waiting so that the process sees the end of its input
+    stdin: Option<ChildStdin>,
+    stdout: ProcessOutput,
+    /// Taken by [`SysOp::ProcessStderr`]
+    stderr: Option<ProcessOutput>,
+}
+
+impl ChildProcess {
+    /// Make sure the process is waited on once it exits, so that it does not linger as a zombie
+    fn reap(self) {
+        let ChildProcess {
+            mut child, stdin, ..
+        } = self;
+        drop(stdin);
+        if !matches!(child.try_wait(), Ok(Some(_))) {
+            spawn(move || {
+                _ = child.wait();
+            });
+        }
+    }
+}
+
+/// An output stream of a process that is read into memory on a background thread
+///
+/// Reading the pipe as the process writes to it means the process never blocks
+/// because the pipe is full, even if nothing reads its output.
+#[derive(Clone)]
+struct ProcessOutput(Arc<(Mutex<ProcessOutputBuffer>, Condvar)>);
+
+#[derive(Default)]
+struct ProcessOutputBuffer {
+    bytes: VecDeque<u8>,
+    /// Whether the process has closed the stream
+    closed: bool,
+}
+
+impl ProcessOutput {
+    fn new(mut pipe: impl Read + Send + 'static) -> Self {
+        let output = ProcessOutput(Default::default());
+        let inner = output.0.clone();
+        spawn(move || {
+            let (buffer, cvar) = &*inner;
+            let mut chunk = [0; 8192];
+            loop {
+                let n = match pipe.read(&mut chunk) {
+                    Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                    res => res.unwrap_or(0),
+                };
+                // Once the handle is closed, keep draining the pipe so the process does not block,
+                // but discard the output. The thread ends when the process closes the stream.
+                if n > 0 && Arc::strong_count(&inner) == 1 {
+                    continue;
+                }
+                let mut buffer = buffer.lock();
+                if n == 0 {
+                    buffer.closed = true;
+                    cvar.notify_all();
+                    break;
+                }
+                buffer.bytes.extend(&chunk[..n]);
+                cvar.notify_all();
+            }
+        });
+        output
+    }
+    /// Read up to `len` bytes, waiting until that many are available or the stream is closed
+    fn read(&self, len: usize) -> Vec<u8> {
+        let (buffer, cvar) = &*self.0;
+        let mut buffer = buffer.lock();
+        while buffer.bytes.len() < len && !buffer.closed {
+            cvar.wait(&mut buffer);
+        }
+        let n = len.min(buffer.bytes.len());
+        buffer.bytes.drain(..n).collect()
+    }
+}
+
+/// Both ends of a channel
+///
+/// The sender is dropped when the channel is closed.
+struct Channel {
+    sender: Mutex<Option<Sender<Value>>>,
+    receiver: Receiver<Value>,
+}
+
 enum SysStream<'a> {
     File(dashmap::mapref::one::RefMut<'a, Handle, Buffered<File>>),
     TcpListener(dashmap::mapref::one::RefMut<'a, Handle, TcpListener>),
     TcpSocket(dashmap::mapref::one::RefMut<'a, Handle, Buffered<TcpStream>>),
+    UdpSocket(dashmap::mapref::one::RefMut<'a, Handle, UdpSocket>),
+    Process(dashmap::mapref::one::RefMut<'a, Handle, ChildProcess>),
+    ProcessStderr(dashmap::mapref::one::RefMut<'a, Handle, ProcessOutput>),
 }
 
 impl Default for GlobalNativeSys {
@@ -548,7 +997,11 @@ impl Default for GlobalNativeSys {
             tcp_listeners: DashMap::new(),
             tcp_sockets: DashMap::new(),
             hostnames: DashMap::new(),
+            udp_sockets: DashMap::new(),
+            processes: DashMap::new(),
+            process_stderrs: DashMap::new(),
             threads: DashMap::new(),
+            channels: DashMap::new(),
             #[cfg(feature = "audio")]
             audio_stream_time: Mutex::new(None),
             #[cfg(feature = "audio")]
@@ -565,6 +1018,11 @@ impl GlobalNativeSys {
             if !self.files.contains_key(&handle)
                 && !self.tcp_listeners.contains_key(&handle)
                 && !self.tcp_sockets.contains_key(&handle)
+                && !self.udp_sockets.contains_key(&handle)
+                && !self.processes.contains_key(&handle)
+                && !self.process_stderrs.contains_key(&handle)
+                && !self.threads.contains_key(&handle)
+                && !self.channels.contains_key(&handle)
             {
                 return handle;
             }
@@ -578,12 +1036,27 @@ impl GlobalNativeSys {
             SysStream::TcpListener(listener)
         } else if let Some(socket) = self.tcp_sockets.get_mut(&handle) {
             SysStream::TcpSocket(socket)
+        } else if let Some(socket) = self.udp_sockets.get_mut(&handle) {
+            SysStream::UdpSocket(socket)
+        } else if let Some(process) = self.processes.get_mut(&handle) {
+            SysStream::Process(process)
+        } else if let Some(stderr) = self.process_stderrs.get_mut(&handle) {
+            SysStream::ProcessStderr(stderr)
         } else {
             return Err("Invalid file handle".to_string());
         })
     }
+    fn get_channel(&self, handle: Handle) -> Result<Arc<Channel>, String> {
+        self.channels
+            .get(&handle)
+            .map(|channel| channel.clone())
+            .ok_or_else(|| "Invalid channel handle".to_string())
+    }
 }
 
+/// The largest payload a UDP packet can carry
+const MAX_UDP_PACKET: usize = 65507;
+
 static NATIVE_SYS: Lazy<GlobalNativeSys> = Lazy::new(Default::default);
 
 #[cfg(feature = "audio")]
@@ -641,6 +1114,43 @@ impl SysBackend for NativeSys {
             .map(|m| m.is_file())
             .map_err(|e| e.to_string())
     }
+    fn is_dir(&self, path: &str) -> Result<bool, String> {
+        fs::metadata(path)
+            .map(|m| m.is_dir())
+            .map_err(|e| e.to_string())
+    }
+    fn file_size(&self, path: &str) -> Result<u64, String> {
+        fs::metadata(path)
+            .map(|m| m.len())
+            .map_err(|e| e.to_string())
+    }
+    fn file_modified(&self, path: &str) -> Result<f64, String> {
+        let modified = fs::metadata(path)
+            .and_then(|m| m.modified())
+            .map_err(|e| e.to_string())?;
+        Ok(match modified.duration_since(UNIX_EPOCH) {
+            Ok(since) => since.as_secs_f64(),
+            Err(e) => -e.duration().as_secs_f64(),
+        })
+    }
+    fn delete(&self, path: &str) -> Result<(), String> {
+        let metadata = fs::symlink_metadata(path).map_err(|e| e.to_string())?;
+        if metadata.is_dir() {
+            fs::remove_dir_all(path)
+        } else {
+            fs::remove_file(path)
+        }
+        .map_err(|e| e.to_string())
+    }
+    fn rename(&self, from: &str, to: &str) -> Result<(), String> {
+        fs::rename(from, to).map_err(|e| e.to_string())
+    }
+    fn copy_file(&self, from: &str, to: &str) -> Result<(), String> {
+        fs::copy(from, to).map(drop).map_err(|e| e.to_string())
+    }
+    fn make_dir(&self, path: &str) -> Result<(), String> {
+        fs::create_dir_all(path).map_err(|e| e.to_string())
+    }
     fn list_dir(&self, path: &str) -> Result<Vec<String>, String> {
         let mut paths = Vec::new();
         for entry in fs::read_dir(path).map_err(|e| e.to_string())? {
@@ -680,6 +1190,26 @@ impl SysBackend for NativeSys {
                     .map_err(|e| e.to_string())?;
                 buf
             }
+            SysStream::UdpSocket(socket) => {
+                // The socket is cloned so that blocking does not hold a lock on the map
+                let cloned = socket.try_clone().map_err(|e| e.to_string())?;
+                drop(socket);
+                let mut buf = vec![0; len.min(MAX_UDP_PACKET)];
+                let n = cloned.recv(&mut buf).map_err(|e| e.to_string())?;
+                buf.truncate(n);
+                buf
+            }
+            // The output is cloned so that waiting for it does not hold a lock on the map
+            SysStream::Process(process) => {
+                let stdout = process.stdout.clone();
+                drop(process);
+                stdout.read(len)
+            }
+            SysStream::ProcessStderr(stderr) => {
+                let output = stderr.clone();
+                drop(stderr);
+                output.read(len)
+            }
         })
     }
     fn write(&self, handle: Handle, conts: &[u8]) -> Result<(), String> {
@@ -698,6 +1228,22 @@ impl SysBackend for NativeSys {
             SysStream::File(mut file) => file.write_all(conts).map_err(|e| e.to_string()),
             SysStream::TcpListener(_) => Err("Cannot write to a tcp listener".to_string()),
             SysStream::TcpSocket(mut socket) => socket.write_all(conts).map_err(|e| e.to_string()),
+            SysStream::UdpSocket(socket) => {
+                let sent = socket.send(conts).map_err(|e| e.to_string())?;
+                if sent < conts.len() {
+                    return Err(format!("Only sent {sent} of {} bytes", conts.len()));
+                }
+                Ok(())
+            }
+            SysStream::Process(mut process) => {
+                let stdin = process
+                    .stdin
+                    .as_mut()
+                    .ok_or("The process's stdin has been closed")?;
+                stdin.write_all(conts).map_err(|e| e.to_string())?;
+                stdin.flush().map_err(|e| e.to_string())
+            }
+            SysStream::ProcessStderr(_) => Err("Cannot write to a process's stderr".to_string()),
         }
     }
     fn sleep(&self, seconds: f64) -> Result<(), String> {
@@ -896,9 +1442,82 @@ impl SysBackend for NativeSys {
             .map_err(|e| e.to_string())?;
         Ok(())
     }
+    fn udp_bind(&self, addr: &str) -> Result<Handle, String> {
+        let socket = UdpSocket::bind(addr).map_err(|e| e.to_string())?;
+        let handle = NATIVE_SYS.new_handle();
+        NATIVE_SYS.udp_sockets.insert(handle, socket);
+        Ok(handle)
+    }
+    fn udp_connect(&self, handle: Handle, addr: &str) -> Result<(), String> {
+        let socket = NATIVE_SYS
+            .udp_sockets
+            .get(&handle)
+            .ok_or_else(|| "Invalid udp socket handle".to_string())?;
+        socket.connect(addr).map_err(|e| e.to_string())
+    }
+    fn udp_send_to(&self, handle: Handle, data: &[u8], addr: &str) -> Result<(), String> {
+        let socket = NATIVE_SYS
+            .udp_sockets
+            .get(&handle)
+            .ok_or_else(|| "Invalid udp socket handle".to_string())?;
+        let sent = socket.send_to(data, addr).map_err(|e| e.to_string())?;
+        if sent < data.len() {
+            return Err(format!("Only sent {sent} of {} bytes", data.len()));
+        }
+        Ok(())
+    }
+    fn udp_recv_from(&self, handle: Handle, len: usize) -> Result<(Vec<u8>, String), String> {
+        // The socket is cloned so that blocking does not hold a lock on the map
+        let socket = NATIVE_SYS
+            .udp_sockets
+            .get(&handle)
+            .ok_or_else(|| "Invalid udp socket handle".to_string())?
+            .try_clone()
+            .map_err(|e| e.to_string())?;
+        let mut buf = vec![0; len.min(MAX_UDP_PACKET)];
+        let (n, addr) = socket.recv_from(&mut buf).map_err(|e| e.to_string())?;
+        buf.truncate(n);
+        Ok((buf, addr.to_string()))
+    }
+    fn udp_addr(&self, handle: Handle) -> Result<String, String> {
+        let socket = NATIVE_SYS
+            .udp_sockets
+            .get(&handle)
+            .ok_or_else(|| "Invalid udp socket handle".to_string())?;
+        Ok(socket.local_addr().map_err(|e| e.to_string())?.to_string())
+    }
+    fn udp_set_read_timeout(
+        &self,
+        handle: Handle,
+        timeout: Option<Duration>,
+    ) -> Result<(), String> {
+        let socket = NATIVE_SYS
+            .udp_sockets
+            .get(&handle)
+            .ok_or_else(|| "Invalid udp socket handle".to_string())?;
+        socket.set_read_timeout(timeout).map_err(|e| e.to_string())
+    }
+    fn udp_set_write_timeout(
+        &self,
+        handle: Handle,
+        timeout: Option<Duration>,
+    ) -> Result<(), String> {
+        let socket = NATIVE_SYS
+            .udp_sockets
+            .get(&handle)
+            .ok_or_else(|| "Invalid udp socket handle".to_string())?;
+        socket.set_write_timeout(timeout).map_err(|e| e.to_string())
+    }
     fn close(&self, handle: Handle) -> Result<(), String> {
+        if let Some((_, process)) = NATIVE_SYS.processes.remove(&handle) {
+            process.reap();
+            return Ok(());
+        }
         if NATIVE_SYS.files.remove(&handle).is_some()
             || NATIVE_SYS.tcp_listeners.remove(&handle).is_some()
+            || NATIVE_SYS.udp_sockets.remove(&handle).is_some()
+            || NATIVE_SYS.process_stderrs.remove(&handle).is_some()
+            || NATIVE_SYS.channels.remove(&handle).is_some()
             || (NATIVE_SYS.tcp_sockets.remove(&handle).is_some()
                 && NATIVE_SYS.hostnames.remove(&handle).is_some())
         {
@@ -931,6 +1550,46 @@ impl SysBackend for NativeSys {
             Err(e) => Err(Err(format!("Thread panicked: {:?}", e))),
         }
     }
+    fn channel_new(&self) -> Result<Handle, String> {
+        let (sender, receiver) = unbounded();
+        let handle = NATIVE_SYS.new_handle();
+        let channel = Channel {
+            sender: Mutex::new(Some(sender)),
+            receiver,
+        };
+        NATIVE_SYS.channels.insert(handle, Arc::new(channel));
+        Ok(handle)
+    }
+    fn channel_send(&self, handle: Handle, value: Value) -> Result<(), String> {
+        let channel = NATIVE_SYS.get_channel(handle)?;
+        let sender = channel.sender.lock();
+        let sender = sender.as_ref().ok_or("Cannot send on a closed channel")?;
+        sender.send(value).map_err(|e| e.to_string())
+    }
+    fn channel_recv(&self, handle: Handle) -> Result<Value, String> {
+        // The channel is cloned out of the map so that blocking
+        // does not hold a lock on it
+        let channel = NATIVE_SYS.get_channel(handle)?;
+        channel
+            .receiver
+            .recv()
+            .map_err(|_| "Cannot receive from a closed and empty channel".into())
+    }
+    fn channel_try_recv(&self, handle: Handle) -> Result<Option<Value>, String> {
+        let channel = NATIVE_SYS.get_channel(handle)?;
+        match channel.receiver.try_recv() {
+            Ok(value) => Ok(Some(value)),
+            Err(TryRecvError::Empty) => Ok(None),
+            Err(TryRecvError::Disconnected) => {
+                Err("Cannot receive from a closed and empty channel".into())
+            }
+        }
+    }
+    fn channel_close(&self, handle: Handle) -> Result<(), String> {
+        let channel = NATIVE_SYS.get_channel(handle)?;
+        channel.sender.lock().take();
+        Ok(())
+    }
     fn run_command_inherit(&self, command: &str, args: &[&str]) -> Result<(), String> {
         Command::new(command)
             .args(args)
@@ -954,6 +1613,61 @@ impl SysBackend for NativeSys {
             String::from_utf8_lossy(&output.stderr).into(),
         ))
     }
+    fn run_command_stream(&self, command: &str, args: &[&str]) -> Result<Handle, String> {
+        let mut child = Command::new(command)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| e.to_string())?;
+        let process = ChildProcess {
+            stdin: child.stdin.take(),
+            stdout: ProcessOutput::new(child.stdout.take().unwrap()),
+            stderr: Some(ProcessOutput::new(child.stderr.take().unwrap())),
+            child,
+        };
+        let handle = NATIVE_SYS.new_handle();
+        NATIVE_SYS.processes.insert(handle, process);
+        Ok(handle)
+    }
+    fn process_stderr(&self, handle: Handle) -> Result<Handle, String> {
+        let stderr = NATIVE_SYS
+            .processes
+            .get_mut(&handle)
+            .ok_or_else(|| "Invalid process handle".to_string())?
+            .stderr
+            .take()
+            .ok_or("The process's stderr has already been taken")?;
+        let handle = NATIVE_SYS.new_handle();
+        NATIVE_SYS.process_stderrs.insert(handle, stderr);
+        Ok(handle)
+    }
+    fn process_wait(&self, handle: Handle) -> Result<i32, String> {
+        // The process is polled rather than waited on so that the map is not
+        // locked while it runs, which would stop it from being killed
+        let mut delay = Duration::from_millis(1);
+        loop {
+            let mut process = NATIVE_SYS
+                .processes
+                .get_mut(&handle)
+                .ok_or_else(|| "Invalid process handle".to_string())?;
+            process.stdin.take();
+            if let Some(status) = process.child.try_wait().map_err(|e| e.to_string())? {
+                return Ok(status.code().unwrap_or(-1));
+            }
+            drop(process);
+            sleep(delay);
+            delay = (delay * 2).min(Duration::from_millis(20));
+        }
+    }
+    fn process_kill(&self, handle: Handle) -> Result<(), String> {
+        let mut process = NATIVE_SYS
+            .processes
+            .get_mut(&handle)
+            .ok_or_else(|| "Invalid process handle".to_string())?;
+        process.child.kill().map_err(|e| e.to_string())
+    }
     fn change_directory(&self, path: &str) -> Result<(), String> {
         env::set_current_dir(path).map_err(|e| e.to_string())
     }
@@ -1271,6 +1985,9 @@ impl SysOp {
                     Value::Num(arr) => arr.data.iter().map(|&x| x as u8).collect(),
                     Value::Byte(arr) => arr.data.into(),
                     Value::Char(arr) => arr.data.iter().collect::<String>().into(),
+                    Value::Complex(_) => {
+                        return Err(env.error("Cannot write complex array to file"))
+                    }
                     Value::Func(_) => return Err(env.error("Cannot write function array to file")),
                 };
                 match handle {
@@ -1328,6 +2045,9 @@ impl SysOp {
                     Value::Num(arr) => arr.data.iter().map(|&x| x as u8).collect(),
                     Value::Byte(arr) => arr.data.into(),
                     Value::Char(arr) => arr.data.iter().collect::<String>().into(),
+                    Value::Complex(_) => {
+                        return Err(env.error("Cannot write complex array to file"))
+                    }
                     Value::Func(_) => return Err(env.error("Cannot write function array to file")),
                 };
                 env.backend
@@ -1358,6 +2078,41 @@ impl SysOp {
                 let is_file = env.backend.is_file(&path).map_err(|e| env.error(e))?;
                 env.push(is_file);
             }
+            SysOp::FIsDir => {
+                let path = env.pop(1)?.as_string(env, "Path must be a string")?;
+                let is_dir = env.backend.is_dir(&path).map_err(|e| env.error(e))?;
+                env.push(is_dir);
+            }
+            SysOp::FSize => {
+                let path = env.pop(1)?.as_string(env, "Path must be a string")?;
+                let size = env.backend.file_size(&path).map_err(|e| env.error(e))?;
+                env.push(size as f64);
+            }
+            SysOp::FModified => {
+                let path = env.pop(1)?.as_string(env, "Path must be a string")?;
+                let time = env.backend.file_modified(&path).map_err(|e| env.error(e))?;
+                env.push(time);
+            }
+            SysOp::FDelete => {
+                let path = env.pop(1)?.as_string(env, "Path must be a string")?;
+                env.backend.delete(&path).map_err(|e| env.error(e))?;
+            }
+            SysOp::FRename => {
+                let from = env.pop(1)?.as_string(env, "Path must be a string")?;
+                let to = env.pop(2)?.as_string(env, "Path must be a string")?;
+                env.backend.rename(&from, &to).map_err(|e| env.error(e))?;
+            }
+            SysOp::FCopy => {
+                let from = env.pop(1)?.as_string(env, "Path must be a string")?;
+                let to = env.pop(2)?.as_string(env, "Path must be a string")?;
+                env.backend
+                    .copy_file(&from, &to)
+                    .map_err(|e| env.error(e))?;
+            }
+            SysOp::FMakeDir => {
+                let path = env.pop(1)?.as_string(env, "Path must be a string")?;
+                env.backend.make_dir(&path).map_err(|e| env.error(e))?;
+            }
             SysOp::Import => {
                 let path = env.pop(1)?.as_string(env, "Import path must be a string")?;
                 let input = String::from_utf8(
@@ -1615,6 +2370,284 @@ impl SysOp {
                     .into();
                 env.backend.close(handle).map_err(|e| env.error(e))?;
             }
+            SysOp::JsonDecode => {
+                let json = env.pop(1)?.as_string(env, "JSON must be a string")?;
+                let value = json_to_value(&json).map_err(|e| env.error(e))?;
+                env.push(value);
+            }
+            SysOp::JsonEncode => {
+                let value = env.pop(1)?;
+                let json = value_to_json(&value).map_err(|e| env.error(e))?;
+                env.push(json);
+            }
+            SysOp::CsvDecode => {
+                let csv = env.pop(1)?.as_string(env, "CSV must be a string")?;
+                let value = csv_to_value(&csv, false).map_err(|e| env.error(e))?;
+                env.push(value);
+            }
+            SysOp::CsvDecodeNumbers => {
+                let csv = env.pop(1)?.as_string(env, "CSV must be a string")?;
+                let value = csv_to_value(&csv, true).map_err(|e| env.error(e))?;
+                env.push(value);
+            }
+            SysOp::CsvEncode => {
+                let value = env.pop(1)?;
+                let csv = value_to_csv(&value).map_err(|e| env.error(e))?;
+                env.push(csv);
+            }
+            SysOp::Hash => {
+                let algorithm = env
+                    .pop(1)?
+                    .as_string(env, "Hash algorithm must be a string")?;
+                let bytes = env
+                    .pop(2)?
+                    .into_bytes(env, "Hashed value must be a byte array or string")?;
+                let hash = digest(&algorithm, &bytes).map_err(|e| env.error(e))?;
+                env.push(Array::<u8>::from(hash));
+            }
+            SysOp::Crc32 => {
+                let bytes = env
+                    .pop(1)?
+                    .into_bytes(env, "Hashed value must be a byte array or string")?;
+                let checksum = crc32fast::hash(&bytes).to_be_bytes();
+                env.push(Array::<u8>::from_iter(checksum));
+            }
+            SysOp::Base64Encode => {
+                let bytes = env
+                    .pop(1)?
+                    .into_bytes(env, "Encoded value must be a byte array or string")?;
+                env.push(BASE64_STANDARD.encode(bytes));
+            }
+            SysOp::Base64Decode => {
+                let encoded = env.pop(1)?.as_string(env, "Base64 must be a string")?;
+                let bytes = BASE64_STANDARD
+                    .decode(encoded.trim())
+                    .map_err(|e| env.error(format!("Invalid base64: {e}")))?;
+                env.push(Array::<u8>::from(bytes));
+            }
+            SysOp::HexEncode => {
+                let bytes = env
+                    .pop(1)?
+                    .into_bytes(env, "Encoded value must be a byte array or string")?;
+                env.push(hex_encode(&bytes));
+            }
+            SysOp::HexDecode => {
+                let encoded = env.pop(1)?.as_string(env, "Hex must be a string")?;
+                let bytes = hex_decode(encoded.trim()).map_err(|e| env.error(e))?;
+                env.push(Array::<u8>::from(bytes));
+            }
+            SysOp::GzipCompress => {
+                let bytes = (env.pop(1)?)
+                    .into_bytes(env, "Compressed value must be a byte array or string")?;
+                let compressed = gzip_compress(&bytes).map_err(|e| env.error(e))?;
+                env.push(Array::<u8>::from(compressed));
+            }
+            SysOp::GzipDecompress => {
+                let bytes = (env.pop(1)?).into_bytes(env, "Gzipped data must be a byte array")?;
+                let decompressed = gzip_decompress(&bytes).map_err(|e| env.error(e))?;
+                env.push(Array::<u8>::from(decompressed));
+            }
+            SysOp::ZlibCompress => {
+                let bytes = (env.pop(1)?)
+                    .into_bytes(env, "Compressed value must be a byte array or string")?;
+                let compressed = zlib_compress(&bytes).map_err(|e| env.error(e))?;
+                env.push(Array::<u8>::from(compressed));
+            }
+            SysOp::ZlibDecompress => {
+                let bytes = (env.pop(1)?).into_bytes(env, "Zlib data must be a byte array")?;
+                let decompressed = zlib_decompress(&bytes).map_err(|e| env.error(e))?;
+                env.push(Array::<u8>::from(decompressed));
+            }
+            SysOp::DeflateCompress => {
+                let bytes = (env.pop(1)?)
+                    .into_bytes(env, "Compressed value must be a byte array or string")?;
+                let compressed = deflate_compress(&bytes).map_err(|e| env.error(e))?;
+                env.push(Array::<u8>::from(compressed));
+            }
+            SysOp::DeflateDecompress => {
+                let bytes = (env.pop(1)?).into_bytes(env, "Deflated data must be a byte array")?;
+                let decompressed = deflate_decompress(&bytes).map_err(|e| env.error(e))?;
+                env.push(Array::<u8>::from(decompressed));
+            }
+            SysOp::ZipList => {
+                let archive = (env.pop(1)?).into_bytes(env, "Zip archive must be a byte array")?;
+                let names = zip_list(&archive).map_err(|e| env.error(e))?;
+                let names: Array<Arc<Function>> = (names.into_iter())
+                    .map(|name| Arc::new(Function::constant(name.chars().collect::<Array<char>>())))
+                    .collect();
+                env.push(names);
+            }
+            SysOp::ZipRead => {
+                let name = env.pop(1)?.as_string(env, "Entry name must be a string")?;
+                let archive = (env.pop(2)?).into_bytes(env, "Zip archive must be a byte array")?;
+                let bytes = zip_read(&archive, &name).map_err(|e| env.error(e))?;
+                env.push(Array::<u8>::from(bytes));
+            }
+            SysOp::ZipWrite => {
+                let pairs = env.pop(1)?;
+                let pairs = match pairs {
+                    Value::Func(arr) if arr.rank() == 2 && arr.shape()[1] == 2 => arr,
+                    value => {
+                        return Err(env.error(format!(
+                            "Zip entries must be a rank 2 array of boxed name-content pairs, \
+                            but it is a rank {} {} array",
+                            value.rank(),
+                            value.type_name()
+                        )))
+                    }
+                };
+                let mut entries = Vec::with_capacity(pairs.row_count());
+                for pair in pairs.data.chunks_exact(2) {
+                    let name = (pair[0].as_boxed())
+                        .ok_or_else(|| env.error("Zip entry names must be boxed strings"))?
+                        .as_string(env, "Zip entry names must be strings")?;
+                    let contents = (pair[1].as_boxed())
+                        .ok_or_else(|| env.error("Zip entry contents must be boxed"))?
+                        .clone()
+                        .into_bytes(env, "Zip entry contents must be byte arrays or strings")?;
+                    entries.push((name, contents));
+                }
+                let archive = zip_write(
+                    (entries.iter()).map(|(name, contents)| (name.as_str(), contents.as_slice())),
+                )
+                .map_err(|e| env.error(e))?;
+                env.push(Array::<u8>::from(archive));
+            }
+            SysOp::UdpBind => {
+                let addr = env.pop(1)?.as_string(env, "Address must be a string")?;
+                let handle = env.backend.udp_bind(&addr).map_err(|e| env.error(e))?;
+                env.push(handle);
+            }
+            SysOp::UdpConnect => {
+                let addr = env.pop(1)?.as_string(env, "Address must be a string")?;
+                let handle = env
+                    .pop(2)?
+                    .as_nat(env, "Handle must be an natural number")?
+                    .into();
+                env.backend
+                    .udp_connect(handle, &addr)
+                    .map_err(|e| env.error(e))?;
+            }
+            SysOp::UdpSendTo => {
+                let data = env.pop(1)?;
+                let addr = env.pop(2)?.as_string(env, "Address must be a string")?;
+                let handle = env
+                    .pop(3)?
+                    .as_nat(env, "Handle must be an natural number")?
+                    .into();
+                let bytes: Vec<u8> = match data {
+                    Value::Num(arr) => arr.data.iter().map(|&x| x as u8).collect(),
+                    Value::Byte(arr) => arr.data.into(),
+                    Value::Char(arr) => arr.data.iter().collect::<String>().into(),
+                    Value::Complex(_) => return Err(env.error("Cannot send complex array")),
+                    Value::Func(_) => return Err(env.error("Cannot send function array")),
+                };
+                env.backend
+                    .udp_send_to(handle, &bytes, &addr)
+                    .map_err(|e| env.error(e))?;
+            }
+            SysOp::UdpReceiveFrom => {
+                let count = env.pop(1)?.as_nat(env, "Count must be an integer")?;
+                let handle = env
+                    .pop(2)?
+                    .as_nat(env, "Handle must be an natural number")?
+                    .into();
+                let (bytes, addr) = env
+                    .backend
+                    .udp_recv_from(handle, count)
+                    .map_err(|e| env.error(e))?;
+                env.push(addr);
+                env.push(bytes);
+            }
+            SysOp::UdpSetReadTimeout => {
+                let timeout = env.pop(1)?.as_num(env, "Timeout must be a number")?.abs();
+                let timeout = if timeout.is_infinite() {
+                    None
+                } else {
+                    Some(Duration::from_secs_f64(timeout))
+                };
+                let handle = env
+                    .pop(2)?
+                    .as_nat(env, "Handle must be an natural number")?
+                    .into();
+                env.backend
+                    .udp_set_read_timeout(handle, timeout)
+                    .map_err(|e| env.error(e))?;
+            }
+            SysOp::UdpSetWriteTimeout => {
+                let timeout = env.pop(1)?.as_num(env, "Timeout must be a number")?.abs();
+                let timeout = if timeout.is_infinite() {
+                    None
+                } else {
+                    Some(Duration::from_secs_f64(timeout))
+                };
+                let handle = env
+                    .pop(2)?
+                    .as_nat(env, "Handle must be an natural number")?
+                    .into();
+                env.backend
+                    .udp_set_write_timeout(handle, timeout)
+                    .map_err(|e| env.error(e))?;
+            }
+            SysOp::UdpAddr => {
+                let handle = env
+                    .pop(1)?
+                    .as_nat(env, "Handle must be an natural number")?
+                    .into();
+                let addr = env.backend.udp_addr(handle).map_err(|e| env.error(e))?;
+                env.push(addr);
+            }
+            SysOp::ChannelNew => {
+                let handle = env.backend.channel_new().map_err(|e| env.error(e))?;
+                env.push(handle);
+            }
+            SysOp::ChannelSend => {
+                let value = env.pop(1)?;
+                let handle = env
+                    .pop(2)?
+                    .as_nat(env, "Handle must be an natural number")?
+                    .into();
+                env.backend
+                    .channel_send(handle, value)
+                    .map_err(|e| env.error(e))?;
+            }
+            SysOp::ChannelRecv => {
+                let handle = env
+                    .pop(1)?
+                    .as_nat(env, "Handle must be an natural number")?
+                    .into();
+                let value = env.backend.channel_recv(handle).map_err(|e| env.error(e))?;
+                env.push(value);
+            }
+            SysOp::ChannelTryRecv => {
+                let handle = env
+                    .pop(1)?
+                    .as_nat(env, "Handle must be an natural number")?
+                    .into();
+                match env
+                    .backend
+                    .channel_try_recv(handle)
+                    .map_err(|e| env.error(e))?
+                {
+                    Some(value) => {
+                        env.push(value);
+                        env.push(1.0);
+                    }
+                    None => {
+                        env.push(0.0);
+                        env.push(0.0);
+                    }
+                }
+            }
+            SysOp::ChannelClose => {
+                let handle = env
+                    .pop(1)?
+                    .as_nat(env, "Handle must be an natural number")?
+                    .into();
+                env.backend
+                    .channel_close(handle)
+                    .map_err(|e| env.error(e))?;
+            }
             SysOp::RunInherit => {
                 let (command, args) = value_to_command(&env.pop(1)?, env)?;
                 let args: Vec<_> = args.iter().map(|s| s.as_str()).collect();
@@ -1632,6 +2665,41 @@ impl SysOp {
                 env.push(stdout);
                 env.push(stderr);
             }
+            SysOp::RunStream => {
+                let (command, args) = value_to_command(&env.pop(1)?, env)?;
+                let args: Vec<_> = args.iter().map(|s| s.as_str()).collect();
+                let handle = env
+                    .backend
+                    .run_command_stream(&command, &args)
+                    .map_err(|e| env.error(e))?;
+                env.push(handle);
+            }
+            SysOp::ProcessStderr => {
+                let handle = env
+                    .pop(1)?
+                    .as_nat(env, "Handle must be an natural number")?
+                    .into();
+                let stderr = env
+                    .backend
+                    .process_stderr(handle)
+                    .map_err(|e| env.error(e))?;
+                env.push(stderr);
+            }
+            SysOp::ProcessWait => {
+                let handle = env
+                    .pop(1)?
+                    .as_nat(env, "Handle must be an natural number")?
+                    .into();
+                let code = env.backend.process_wait(handle).map_err(|e| env.error(e))?;
+                env.push(code as f64);
+            }
+            SysOp::ProcessKill => {
+                let handle = env
+                    .pop(1)?
+                    .as_nat(env, "Handle must be an natural number")?
+                    .into();
+                env.backend.process_kill(handle).map_err(|e| env.error(e))?;
+            }
             SysOp::ChangeDirectory => {
                 let path = env.pop(1)?.as_string(env, "Path must be a string")?;
                 env.backend
@@ -1690,7 +2758,7 @@ fn value_to_command(value: &Value, env: &Uiua) -> UiuaResult<(String, Vec<String
                 )))
             }
         },
-        Value::Num(_) | Value::Byte(_) => {
+        Value::Num(_) | Value::Byte(_) | Value::Complex(_) => {
             return Err(env.error(format!(
                 "Command must be a string or function array, but it is {}s",
                 value.type_name()
@@ -1986,3 +3054,64 @@ pub fn value_to_gif_bytes(value: &Value, frame_rate: f64) -> Result<Vec<u8>, Str
     drop(encoder);
     Ok(bytes.into_inner())
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::Uiua;
+
+    #[test]
+    #[cfg(unix)]
+    fn process_streams() {
+        let mut env = Uiua::with_native_sys();
+        env.load_str(
+            r#"
+            P ← &runs {"cat"}
+            &w "hello\nworld\n" P
+            ⍤∶≅, "hello\n" &ru "\n" P
+            ⍤∶≅, 0 &pw P
+            ⍤∶≅, "world\n" &rs 100 P
+            &cl P
+            Q ← &runs {"sh" "-c" "echo oops >&2; exit 3"}
+            E ← &pse Q
+            ⍤∶≅, 3 &pw Q
+            ⍤∶≅, "oops\n" &rs 100 E
+            &cl E
+            &cl Q
+            # Output that is not read does not stop the process from finishing
+            L ← &runs {"sh" "-c" "head -c 200000 /dev/zero; head -c 100000 /dev/zero >&2"}
+            ⍤∶≅, 0 &pw L
+            ⍤∶≅, 200000 ⧻&rs 300000 L
+            S ← &pse L
+            ⍤∶≅, 100000 ⧻&rs 300000 S
+            &cl S
+            &cl L
+            R ← &runs {"sleep" "10"}
+            &pk R
+            ⍤∶≅, ¯1 &pw R
+            &cl R
+            "#,
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn udp_recv_does_not_block_socket() {
+        let mut env = Uiua::with_native_sys();
+        env.load_str(
+            r#"
+            A ← &udpb "127.0.0.1:0"
+            B ← &udpb "127.0.0.1:0"
+            &udpsrt 5 B
+            &udpc &udpaddr A B
+            spawn(&rs 10 B)
+            &sl 0.1
+            # Getting the address would wait for the receive if it held the socket
+            &udpst "hi" &udpaddr B A
+            ⍤∶≅, "hi" wait
+            &cl A
+            &cl B
+            "#,
+        )
+        .unwrap();
+    }
+}
@@ -10,6 +10,7 @@ use std::{
 use crate::{
     algorithm::{pervade::*, FillContext},
     array::*,
+    complex::Complex,
     function::{Function, Signature},
     grid_fmt::GridFmt,
     primitive::Primitive,
@@ -20,6 +21,7 @@ use crate::{
 pub enum Value {
     Num(Array<f64>),
     Byte(Array<u8>),
+    Complex(Array<Complex>),
     Char(Array<char>),
     Func(Array<Arc<Function>>),
 }
@@ -35,6 +37,7 @@ impl fmt::Debug for Value {
         match self {
             Self::Num(array) => array.fmt(f),
             Self::Byte(array) => array.fmt(f),
+            Self::Complex(array) => array.fmt(f),
             Self::Char(array) => array.fmt(f),
             Self::Func(array) => array.fmt(f),
         }
@@ -52,6 +55,13 @@ impl Value {
             Signature::new(0, 1)
         }
     }
+    /// Check if calling any function in the value has no side effects
+    pub fn is_pure(&self) -> bool {
+        match self {
+            Self::Func(fs) => fs.data.iter().all(|f| f.is_pure()),
+            _ => true,
+        }
+    }
     pub fn as_num_array(&self) -> Option<&Array<f64>> {
         match self {
             Self::Num(array) => Some(array),
@@ -97,6 +107,7 @@ impl Value {
         match self {
             Self::Num(array) => Box::new(array.rows().map(Value::from)),
             Self::Byte(array) => Box::new(array.rows().map(Value::from)),
+            Self::Complex(array) => Box::new(array.rows().map(Value::from)),
             Self::Char(array) => Box::new(array.rows().map(Value::from)),
             Self::Func(array) => Box::new(array.rows().map(Value::from)),
         }
@@ -105,6 +116,7 @@ impl Value {
         match self {
             Self::Num(array) => Box::new(array.into_rows().map(Value::from)),
             Self::Byte(array) => Box::new(array.into_rows().map(Value::from)),
+            Self::Complex(array) => Box::new(array.into_rows().map(Value::from)),
             Self::Char(array) => Box::new(array.into_rows().map(Value::from)),
             Self::Func(array) => Box::new(array.into_rows().map(Value::from)),
         }
@@ -113,6 +125,7 @@ impl Value {
         match self {
             Self::Num(array) => Box::new(array.into_rows_rev().map(Value::from)),
             Self::Byte(array) => Box::new(array.into_rows_rev().map(Value::from)),
+            Self::Complex(array) => Box::new(array.into_rows_rev().map(Value::from)),
             Self::Char(array) => Box::new(array.into_rows_rev().map(Value::from)),
             Self::Func(array) => Box::new(array.into_rows_rev().map(Value::from)),
         }
@@ -121,6 +134,7 @@ impl Value {
         match self {
             Self::Num(array) => Box::new(array.data.into_iter().map(Value::from)),
             Self::Byte(array) => Box::new(array.data.into_iter().map(Value::from)),
+            Self::Complex(array) => Box::new(array.data.into_iter().map(Value::from)),
             Self::Char(array) => Box::new(array.data.into_iter().map(Value::from)),
             Self::Func(array) => Box::new(array.data.into_iter().map(Value::from)),
         }
@@ -128,12 +142,19 @@ impl Value {
     pub fn type_name(&self) -> &'static str {
         match self {
             Self::Num(_) | Self::Byte(_) => "number",
+            Self::Complex(_) => "complex",
             Self::Char(_) => "character",
             Self::Func(_) => "function",
         }
     }
     pub fn shape(&self) -> &[usize] {
-        self.generic_ref_shallow(Array::shape, Array::shape, Array::shape, Array::shape)
+        self.generic_ref_shallow(
+            Array::shape,
+            Array::shape,
+            Array::shape,
+            Array::shape,
+            Array::shape,
+        )
     }
     pub fn shape_prefixes_match(&self, other: &Self) -> bool {
         self.shape().iter().zip(other.shape()).all(|(a, b)| a == b)
@@ -144,6 +165,7 @@ impl Value {
             Array::row_count,
             Array::row_count,
             Array::row_count,
+            Array::row_count,
         )
     }
     pub fn row_len(&self) -> usize {
@@ -152,6 +174,7 @@ impl Value {
             Array::row_len,
             Array::row_len,
             Array::row_len,
+            Array::row_len,
         )
     }
     pub fn flat_len(&self) -> usize {
@@ -160,12 +183,14 @@ impl Value {
             Array::flat_len,
             Array::flat_len,
             Array::flat_len,
+            Array::flat_len,
         )
     }
     pub fn reserve_min(&mut self, min: usize) {
         match self {
             Self::Num(arr) => arr.data.reserve_min(min),
             Self::Byte(arr) => arr.data.reserve_min(min),
+            Self::Complex(arr) => arr.data.reserve_min(min),
             Self::Char(arr) => arr.data.reserve_min(min),
             Self::Func(arr) => arr.data.reserve_min(min),
         }
@@ -174,6 +199,7 @@ impl Value {
         match self {
             Self::Num(array) => array.first_dim_zero().into(),
             Self::Byte(array) => array.first_dim_zero().into(),
+            Self::Complex(array) => array.first_dim_zero().into(),
             Self::Char(array) => array.first_dim_zero().into(),
             Self::Func(array) => array.first_dim_zero().into(),
         }
@@ -184,6 +210,7 @@ impl Value {
             Array::format_shape,
             Array::format_shape,
             Array::format_shape,
+            Array::format_shape,
         )
     }
     pub fn rank(&self) -> usize {
@@ -193,6 +220,7 @@ impl Value {
         match self {
             Self::Num(array) => &mut array.shape,
             Self::Byte(array) => &mut array.shape,
+            Self::Complex(array) => &mut array.shape,
             Self::Char(array) => &mut array.shape,
             Self::Func(array) => &mut array.shape,
         }
@@ -203,6 +231,7 @@ impl Value {
             Array::validate_shape,
             Array::validate_shape,
             Array::validate_shape,
+            Array::validate_shape,
         )
     }
     pub fn row(&self, i: usize) -> Self {
@@ -211,18 +240,30 @@ impl Value {
             |arr| arr.row(i).into(),
             |arr| arr.row(i).into(),
             |arr| arr.row(i).into(),
+            |arr| arr.row(i).into(),
+        )
+    }
+    pub fn window(&self, start: usize, size: usize) -> Self {
+        self.generic_ref_shallow(
+            |arr| arr.window(start, size).into(),
+            |arr| arr.window(start, size).into(),
+            |arr| arr.window(start, size).into(),
+            |arr| arr.window(start, size).into(),
+            |arr| arr.window(start, size).into(),
         )
     }
     pub fn generic_into_shallow<T>(
         self,
         n: impl FnOnce(Array<f64>) -> T,
         b: impl FnOnce(Array<u8>) -> T,
+        co: impl FnOnce(Array<Complex>) -> T,
         c: impl FnOnce(Array<char>) -> T,
         f: impl FnOnce(Array<Arc<Function>>) -> T,
     ) -> T {
         match self {
             Self::Num(array) => n(array),
             Self::Byte(array) => b(array),
+            Self::Complex(array) => co(array),
             Self::Char(array) => c(array),
             Self::Func(array) => f(array),
         }
@@ -231,15 +272,17 @@ impl Value {
         self,
         n: impl FnOnce(Array<f64>) -> T,
         b: impl FnOnce(Array<u8>) -> T,
+        co: impl FnOnce(Array<Complex>) -> T,
         c: impl FnOnce(Array<char>) -> T,
         f: impl FnOnce(Array<Arc<Function>>) -> T,
     ) -> T {
         match self {
             Self::Num(array) => n(array),
             Self::Byte(array) => b(array),
+            Self::Complex(array) => co(array),
             Self::Char(array) => c(array),
             Self::Func(array) => match array.into_unboxed() {
-                Ok(value) => value.generic_into_deep(n, b, c, f),
+                Ok(value) => value.generic_into_deep(n, b, co, c, f),
                 Err(array) => f(array),
             },
         }
@@ -248,12 +291,14 @@ impl Value {
         &'a self,
         n: impl FnOnce(&'a Array<f64>) -> T,
         b: impl FnOnce(&'a Array<u8>) -> T,
+        co: impl FnOnce(&'a Array<Complex>) -> T,
         c: impl FnOnce(&'a Array<char>) -> T,
         f: impl FnOnce(&'a Array<Arc<Function>>) -> T,
     ) -> T {
         match self {
             Self::Num(array) => n(array),
             Self::Byte(array) => b(array),
+            Self::Complex(array) => co(array),
             Self::Char(array) => c(array),
             Self::Func(array) => f(array),
         }
@@ -262,16 +307,18 @@ impl Value {
         &'a self,
         n: impl FnOnce(&'a Array<f64>) -> T,
         b: impl FnOnce(&'a Array<u8>) -> T,
+        co: impl FnOnce(&'a Array<Complex>) -> T,
         c: impl FnOnce(&'a Array<char>) -> T,
         f: impl FnOnce(&'a Array<Arc<Function>>) -> T,
     ) -> T {
         match self {
             Self::Num(array) => n(array),
             Self::Byte(array) => b(array),
+            Self::Complex(array) => co(array),
             Self::Char(array) => c(array),
             Self::Func(array) => {
                 if let Some(value) = array.as_boxed() {
-                    value.generic_ref_deep(n, b, c, f)
+                    value.generic_ref_deep(n, b, co, c, f)
                 } else {
                     f(array)
                 }
@@ -282,32 +329,48 @@ impl Value {
         &'a self,
         n: impl FnOnce(&'a Array<f64>, &Uiua) -> UiuaResult<T>,
         b: impl FnOnce(&'a Array<u8>, &Uiua) -> UiuaResult<T>,
+        co: impl FnOnce(&'a Array<Complex>, &Uiua) -> UiuaResult<T>,
         c: impl FnOnce(&'a Array<char>, &Uiua) -> UiuaResult<T>,
         f: impl FnOnce(&'a Array<Arc<Function>>, &Uiua) -> UiuaResult<T>,
         env: &Uiua,
     ) -> UiuaResult<T> {
-        self.generic_ref_shallow(|a| n(a, env), |a| b(a, env), |a| c(a, env), |a| f(a, env))
+        self.generic_ref_shallow(
+            |a| n(a, env),
+            |a| b(a, env),
+            |a| co(a, env),
+            |a| c(a, env),
+            |a| f(a, env),
+        )
     }
     pub fn generic_ref_env_deep<'a, T: 'a>(
         &'a self,
         n: impl FnOnce(&'a Array<f64>, &Uiua) -> UiuaResult<T>,
         b: impl FnOnce(&'a Array<u8>, &Uiua) -> UiuaResult<T>,
+        co: impl FnOnce(&'a Array<Complex>, &Uiua) -> UiuaResult<T>,
         c: impl FnOnce(&'a Array<char>, &Uiua) -> UiuaResult<T>,
         f: impl FnOnce(&'a Array<Arc<Function>>, &Uiua) -> UiuaResult<T>,
         env: &Uiua,
     ) -> UiuaResult<T> {
-        self.generic_ref_deep(|a| n(a, env), |a| b(a, env), |a| c(a, env), |a| f(a, env))
+        self.generic_ref_deep(
+            |a| n(a, env),
+            |a| b(a, env),
+            |a| co(a, env),
+            |a| c(a, env),
+            |a| f(a, env),
+        )
     }
     pub fn generic_mut_shallow<T>(
         &mut self,
         n: impl FnOnce(&mut Array<f64>) -> T,
         b: impl FnOnce(&mut Array<u8>) -> T,
+        co: impl FnOnce(&mut Array<Complex>) -> T,
         c: impl FnOnce(&mut Array<char>) -> T,
         f: impl FnOnce(&mut Array<Arc<Function>>) -> T,
     ) -> T {
         match self {
             Self::Num(array) => n(array),
             Self::Byte(array) => b(array),
+            Self::Complex(array) => co(array),
             Self::Char(array) => c(array),
             Self::Func(array) => f(array),
         }
@@ -316,16 +379,18 @@ impl Value {
         &mut self,
         n: impl FnOnce(&mut Array<f64>) -> T,
         b: impl FnOnce(&mut Array<u8>) -> T,
+        co: impl FnOnce(&mut Array<Complex>) -> T,
         c: impl FnOnce(&mut Array<char>) -> T,
         f: impl FnOnce(&mut Array<Arc<Function>>) -> T,
     ) -> T {
         match self {
             Self::Num(array) => n(array),
             Self::Byte(array) => b(array),
+            Self::Complex(array) => co(array),
             Self::Char(array) => c(array),
             Self::Func(array) => {
                 if let Some(value) = array.as_boxed_mut() {
-                    value.generic_mut_deep(n, b, c, f)
+                    value.generic_mut_deep(n, b, co, c, f)
                 } else {
                     f(array)
                 }
@@ -337,6 +402,7 @@ impl Value {
         match self {
             Self::Num(array) => array.grid_string(),
             Self::Byte(array) => array.grid_string(),
+            Self::Complex(array) => array.grid_string(),
             Self::Char(array) => array.grid_string(),
             Self::Func(array) => array.grid_string(),
         }
@@ -633,6 +699,7 @@ impl Value {
         match self {
             Value::Num(arr) => arr.convert_with(|n| Arc::new(Function::constant(n))),
             Value::Byte(arr) => arr.convert_with(|n| Arc::new(Function::constant(n))),
+            Value::Complex(arr) => arr.convert_with(|n| Arc::new(Function::constant(n))),
             Value::Char(arr) => arr.convert_with(|n| Arc::new(Function::constant(n))),
             Value::Func(arr) => arr,
         }
@@ -645,6 +712,9 @@ impl Value {
             Value::Byte(arr) => {
                 Cow::Owned(arr.convert_ref_with(|n| Arc::new(Function::constant(n))))
             }
+            Value::Complex(arr) => {
+                Cow::Owned(arr.convert_ref_with(|n| Arc::new(Function::constant(n))))
+            }
             Value::Char(arr) => {
                 Cow::Owned(arr.convert_ref_with(|n| Arc::new(Function::constant(n))))
             }
@@ -685,6 +755,7 @@ macro_rules! value_from {
 
 value_from!(f64, Num);
 value_from!(u8, Byte);
+value_from!(Complex, Complex);
 value_from!(char, Char);
 value_from!(Arc<Function>, Func);
 
@@ -736,7 +807,8 @@ macro_rules! value_un_impl {
             pub fn $name(self, env: &Uiua) -> UiuaResult<Self> {
                 Ok(match self {
                     $(Self::$variant(array) => {
-                        (array.shape, array.data.into_iter().map($name::$f).collect::<Vec<_>>()).into()
+                        let threads = kernel_threads(array.flat_len(), env);
+                        (array.shape, map_kernel(&array.data, threads, $name::$f)).into()
                     },)*
                     Value::Func(mut array) => {
                         let mut new_data = Vec::with_capacity(array.flat_len());
@@ -762,7 +834,15 @@ macro_rules! value_un_impl_all {
     }
 }
 
-value_un_impl_all!(neg, not, abs, sign, sqrt, sin, cos, tan, asin, acos, floor, ceil, round);
+value_un_impl_all!(not, sin, cos, tan, asin, acos, floor, ceil, round);
+
+macro_rules! value_un_impl_complex {
+    ($($name:ident),* $(,)?) => {
+        $(value_un_impl!($name, (Num, num), (Byte, byte), (Complex, com));)*
+    }
+}
+
+value_un_impl_complex!(neg, abs, sign, sqrt);
 
 macro_rules! val_retry {
     (Byte, $env:expr) => {
@@ -780,7 +860,7 @@ macro_rules! value_bin_impl {
             pub fn $name(&self, other: &Self, env: &Uiua) -> UiuaResult<Self> {
                 Ok(match (self, other) {
                     $((Value::$va(a), Value::$vb(b)) => {
-                        let res = bin_pervade(a, b, env, InfalliblePervasiveFn::new($name::$f));
+                        let res = bin_pervade_simple(a, b, env, $name::$f);
                         match res {
                             Ok(arr) => arr.into(),
                             #[allow(unreachable_code, unused_variables)]
@@ -834,6 +914,11 @@ value_bin_impl!(
     (Char, Byte, char_byte),
     (Byte, Num, byte_num, num_num),
     (Num, Byte, num_byte, num_num),
+    (Complex, Complex, com_x),
+    (Complex, Num, com_x),
+    (Num, Complex, com_x),
+    (Complex, Byte, com_x),
+    (Byte, Complex, com_x),
 );
 
 value_bin_impl!(
@@ -845,6 +930,11 @@ value_bin_impl!(
     (Byte, Char, byte_char),
     (Byte, Num, byte_num, num_num),
     (Num, Byte, num_byte, num_num),
+    (Complex, Complex, com_x),
+    (Complex, Num, com_x),
+    (Num, Complex, com_x),
+    (Complex, Byte, com_x),
+    (Byte, Complex, com_x),
 );
 
 value_bin_impl!(
@@ -853,6 +943,11 @@ value_bin_impl!(
     (Byte, Byte, byte_byte, num_num),
     (Byte, Num, byte_num, num_num),
     (Num, Byte, num_byte, num_num),
+    (Complex, Complex, com_x),
+    (Complex, Num, com_x),
+    (Num, Complex, com_x),
+    (Complex, Byte, com_x),
+    (Byte, Complex, com_x),
 );
 value_bin_impl!(
     div,
@@ -860,6 +955,11 @@ value_bin_impl!(
     (Byte, Byte, byte_byte, num_num),
     (Byte, Num, byte_num, num_num),
     (Num, Byte, num_byte, num_num),
+    (Complex, Complex, com_x),
+    (Complex, Num, com_x),
+    (Num, Complex, com_x),
+    (Complex, Byte, com_x),
+    (Byte, Complex, com_x),
 );
 value_bin_impl!(
     modulus,
@@ -874,6 +974,11 @@ value_bin_impl!(
     (Byte, Byte, byte_byte, num_num),
     (Byte, Num, byte_num, num_num),
     (Num, Byte, num_byte, num_num),
+    (Complex, Complex, com_x),
+    (Complex, Num, com_x),
+    (Num, Complex, com_x),
+    (Complex, Byte, com_x),
+    (Byte, Complex, com_x),
 );
 value_bin_impl!(
     log,
@@ -881,8 +986,47 @@ value_bin_impl!(
     (Byte, Byte, byte_byte, num_num),
     (Byte, Num, byte_num, num_num),
     (Num, Byte, num_byte, num_num),
+    (Complex, Complex, com_x),
+    (Complex, Num, com_x),
+    (Num, Complex, com_x),
+    (Complex, Byte, com_x),
+    (Byte, Complex, com_x),
 );
 value_bin_impl!(atan2, (Num, Num, num_num));
+value_bin_impl!(
+    complex,
+    (Num, Num, com_x),
+    (Byte, Byte, com_x),
+    (Byte, Num, com_x),
+    (Num, Byte, com_x),
+    (Complex, Complex, com_x),
+    (Complex, Num, com_x),
+    (Num, Complex, com_x),
+    (Complex, Byte, com_x),
+    (Byte, Complex, com_x),
+);
+
+impl Value {
+    /// Get the real and imaginary parts of a complex array
+    pub fn uncomplex(self, env: &Uiua) -> UiuaResult<(Self, Self)> {
+        match self {
+            Value::Num(_) | Value::Byte(_) => {
+                let im = Array::new(Shape::from(self.shape()), vec![0u8; self.flat_len()]);
+                Ok((self, im.into()))
+            }
+            Value::Complex(arr) => {
+                let threads = kernel_threads(arr.flat_len(), env);
+                let re = map_kernel(&arr.data, threads, |c| c.re);
+                let im = map_kernel(&arr.data, threads, |c| c.im);
+                Ok(((arr.shape.clone(), re).into(), (arr.shape, im).into()))
+            }
+            val => Err(env.error(format!(
+                "Cannot get the real and imaginary parts of {}",
+                val.type_name()
+            ))),
+        }
+    }
+}
 
 value_bin_impl!(
     min,
@@ -891,6 +1035,11 @@ value_bin_impl!(
     (Byte, Byte, byte_byte, num_num),
     (Byte, Num, byte_num, num_num),
     (Num, Byte, num_byte, num_num),
+    (Complex, Complex, com_x),
+    (Complex, Num, com_x),
+    (Num, Complex, com_x),
+    (Complex, Byte, com_x),
+    (Byte, Complex, com_x),
 );
 
 value_bin_impl!(
@@ -900,6 +1049,11 @@ value_bin_impl!(
     (Byte, Byte, byte_byte, num_num),
     (Byte, Num, byte_num, num_num),
     (Num, Byte, num_byte, num_num),
+    (Complex, Complex, com_x),
+    (Complex, Num, com_x),
+    (Num, Complex, com_x),
+    (Complex, Byte, com_x),
+    (Byte, Complex, com_x),
 );
 
 macro_rules! cmp_impls {
@@ -914,11 +1068,18 @@ macro_rules! cmp_impls {
                 (Func, Func, generic),
                 (Num, Byte, num_byte, num_num),
                 (Byte, Num, byte_num, num_num),
+                (Complex, Complex, com_x),
+                (Complex, Num, com_x),
+                (Num, Complex, com_x),
+                (Complex, Byte, com_x),
+                (Byte, Complex, com_x),
                 // Type comparable
                 (Num, Char, always_less),
                 (Byte, Char, always_less),
+                (Complex, Char, always_less),
                 (Char, Num, always_greater),
                 (Char, Byte, always_greater),
+                (Char, Complex, always_greater),
             );
         )*
     };
@@ -931,6 +1092,7 @@ impl PartialEq for Value {
         match (self, other) {
             (Value::Num(a), Value::Num(b)) => a == b,
             (Value::Byte(a), Value::Byte(b)) => a == b,
+            (Value::Complex(a), Value::Complex(b)) => a == b,
             (Value::Char(a), Value::Char(b)) => a == b,
             (Value::Func(a), Value::Func(b)) => a == b,
             (Value::Num(a), Value::Byte(b)) => a == b,
@@ -953,6 +1115,7 @@ impl Ord for Value {
         match (self, other) {
             (Value::Num(a), Value::Num(b)) => a.cmp(b),
             (Value::Byte(a), Value::Byte(b)) => a.cmp(b),
+            (Value::Complex(a), Value::Complex(b)) => a.cmp(b),
             (Value::Char(a), Value::Char(b)) => a.cmp(b),
             (Value::Func(a), Value::Func(b)) => a.cmp(b),
             (Value::Num(a), Value::Byte(b)) => a.partial_cmp(b).unwrap(),
@@ -961,6 +1124,8 @@ impl Ord for Value {
             (_, Value::Num(_)) => Ordering::Greater,
             (Value::Byte(_), _) => Ordering::Less,
             (_, Value::Byte(_)) => Ordering::Greater,
+            (Value::Complex(_), _) => Ordering::Less,
+            (_, Value::Complex(_)) => Ordering::Greater,
             (Value::Char(_), _) => Ordering::Less,
             (_, Value::Char(_)) => Ordering::Greater,
         }
@@ -986,6 +1151,10 @@ impl Hash for Value {
                 3u8.hash(state);
                 arr.hash(state);
             }
+            Value::Complex(arr) => {
+                4u8.hash(state);
+                arr.hash(state);
+            }
         }
     }
 }
@@ -995,6 +1164,7 @@ impl fmt::Display for Value {
         match self {
             Value::Num(n) => n.fmt(f),
             Value::Byte(b) => b.fmt(f),
+            Value::Complex(c) => c.fmt(f),
             Value::Char(c) => c.fmt(f),
             Value::Func(func) => {
                 if let Some(val) = func.as_boxed() {